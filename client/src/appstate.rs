@@ -12,6 +12,11 @@ pub enum AppState
     ///
     BoardSetupMode,
 
+    ///
+    /// A mode that allows the player to enter a path to save the game to, or load a game from.
+    ///
+    FileMode,
+
     ///
     /// A mode that signifies the player is interacting with a binded piece.
     ///
@@ -1,7 +1,6 @@
 
 pub mod appstate;
 pub mod floatingtetromino;
-pub mod ltpcommand;
 pub mod ltpcontroller;
 pub mod states;
 pub mod view;
@@ -14,7 +13,6 @@ use coffee::ui::UserInterface;
 use std::fs::OpenOptions;
 use std::io::Read;
 
-use ltpcontroller::LtpController;
 use view::View;
 
 use lits::*;
@@ -37,7 +35,18 @@ struct CLIArgs
 struct Config 
 {
     log_path: String,
-    exe_path: String
+    exe_path: String,
+
+    #[serde(default = "log_spec")]
+    log_spec: String
+}
+
+///
+/// Returns the default log specification.
+///
+fn log_spec () -> String 
+{
+    "info, wgpu_core::device=warn".to_owned()
 }
 
 fn main() -> Result<()>
@@ -54,8 +63,8 @@ fn main() -> Result<()>
     // Run any required global initializers.
 
     Tetromino::initialize();
-    let _logger = log::initialize(& config.log_path, "client", "info, wgpu_core::device=warn")?;
-    LtpController::initialize(& config.exe_path);
+    let _logger = log::initialize(& config.log_path, "client", & config.log_spec)?;
+    ltpcontroller::initialize(& config.exe_path);
 
     // Create state and feed resources to application.
    
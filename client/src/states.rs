@@ -2,6 +2,7 @@
 use coffee::input::{ButtonState, Event, Input, keyboard, mouse};
 
 use std::collections::HashSet;
+use std::time::{Duration, Instant};
 
 ///
 /// An encapsulation of input mechanisms used by this game.
@@ -12,7 +13,8 @@ pub struct InputState
     pub cursor_position: coffee::graphics::Point,
     pub keys_pressed: HashSet<keyboard::KeyCode>,
     pub mouse_buttons_pressed: HashSet<mouse::Button>,
-    pub mouse_scroll_wheel: coffee::graphics::Point
+    pub mouse_scroll_wheel: coffee::graphics::Point,
+    pub text_entered: String
 }
 
 impl Input for InputState 
@@ -20,6 +22,7 @@ impl Input for InputState
     fn clear (& mut self)
     {
         self.mouse_scroll_wheel = coffee::graphics::Point::new(0.0, 0.0);
+        self.text_entered.clear();
     }
 
     fn new () -> InputState 
@@ -30,6 +33,7 @@ impl Input for InputState
             keys_pressed: HashSet::new(),
             mouse_buttons_pressed: HashSet::new(),
             mouse_scroll_wheel: coffee::graphics::Point::new(0.0, 0.0),
+            text_entered: String::new()
         }
     }
 
@@ -73,7 +77,10 @@ impl Input for InputState
                         self.keys_pressed.remove(& key_code);
                     }
                 },
-                _ => {}
+                keyboard::Event::TextEntered { character } => 
+                {
+                    self.text_entered.push(character);
+                }
             },
             _ => {}
         }
@@ -117,6 +124,26 @@ impl WindowSize
         coffee::graphics::Point::new(x, self.get_button_height() + self.get_spacer() + y)
     }
 
+    ///
+    /// Returns the area of the evaluation graph: a sidebar right of the board if the window 
+    /// is wide enough for one, and otherwise a strip beneath the board.
+    ///
+    pub fn get_graph_area (& self) -> coffee::graphics::Rectangle<f32> 
+    {
+        let corner = self.get_board_corner();
+        let board = self.get_board_size();
+        let spacer = self.get_spacer();
+
+        let sidebar_x = corner.x + board + spacer / 2.0;
+        let sidebar_w = self.width - sidebar_x - spacer / 2.0;
+
+        match sidebar_w >= 4.0 * spacer 
+        {
+            true  => coffee::graphics::Rectangle { x: sidebar_x, y: corner.y, width: sidebar_w, height: board / 3.0 },
+            false => coffee::graphics::Rectangle { x: corner.x, y: corner.y + board + spacer / 4.0, width: board, height: spacer / 2.0 }
+        }
+    }
+
     ///
     /// Returns the side length of the board.
     ///
@@ -207,7 +234,109 @@ pub enum EventState
     CancelSetupButton,
     ConfirmSetupButton,
     PlayMoveButton,
+    AnalyzeMovesButton,
     CancelSearchButton,
-    UndoMoveButton
+    UndoMoveButton,
+    RedoMoveButton,
+    FileModeButton,
+    SaveGameButton,
+    LoadGameButton,
+    CancelFileButton,
+    HistoryEntry(usize),
+    NamedSetup(usize),
+    StrengthButton(usize)
+}
+
+
+///
+/// A two-step guard for destructive actions: the first press arms the guard, and a second 
+/// press within the window confirms the action. The guard disarms when cancelled or when 
+/// the window elapses.
+///
+#[derive(Clone, Copy, Debug)]
+pub struct ConfirmGuard 
+{
+    armed_at: Option<Instant>,
+    window: Duration
+}
+
+impl ConfirmGuard 
+{
+    ///
+    /// Disarms the guard.
+    ///
+    pub fn cancel (& mut self)
+    {
+        self.armed_at = None;
+    }
+
+    ///
+    /// Determines whether the guard is armed at the given time.
+    ///
+    pub fn is_armed (& self, now: Instant) -> bool 
+    {
+        self.armed_at.is_some_and(|armed_at| now.saturating_duration_since(armed_at) <= self.window)
+    }
+
+    ///
+    /// Creates a disarmed guard with the given confirmation window.
+    ///
+    pub fn new (window: Duration) -> ConfirmGuard 
+    {
+        ConfirmGuard { armed_at: None, window }
+    }
+
+    ///
+    /// Presses the guarded button at the given time; returns true if this press confirms 
+    /// the action, in which case the guard disarms, and otherwise arms the guard.
+    ///
+    pub fn press (& mut self, now: Instant) -> bool 
+    {
+        match self.is_armed(now)
+        {
+            true  => 
+            {
+                self.cancel();
+                true
+            },
+            false => 
+            {
+                self.armed_at = Some(now);
+                false
+            }
+        }
+    }
 }
 
+#[cfg(test)]
+mod tests 
+{
+    use super::*;
+
+    #[test]
+    fn guard_confirms_within_its_window ()
+    {
+        let start = Instant::now();
+        let mut guard = ConfirmGuard::new(Duration::from_secs(3));
+
+        // The first press arms the guard, and a second press within the window confirms.
+
+        assert!(! guard.press(start));
+        assert!(guard.is_armed(start + Duration::from_secs(1)));
+        assert!(guard.press(start + Duration::from_secs(2)));
+        assert!(! guard.is_armed(start + Duration::from_secs(2)));
+
+        // A press after the window has expired only arms the guard again.
+
+        assert!(! guard.press(start));
+        assert!(! guard.is_armed(start + Duration::from_secs(4)));
+        assert!(! guard.press(start + Duration::from_secs(4)));
+        assert!(guard.press(start + Duration::from_secs(5)));
+
+        // A cancelled guard needs to be armed afresh.
+
+        assert!(! guard.press(start));
+        guard.cancel();
+        assert!(! guard.press(start + Duration::from_secs(1)));
+    }
+}
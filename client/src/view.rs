@@ -3,17 +3,21 @@ use coffee::{Game, Timer};
 use coffee::graphics::*;
 use coffee::input::{Input, keyboard, mouse};
 use coffee::load::Task;
-use coffee::ui::{button, Button, Element, Renderer, Row, UserInterface, Text};
+use coffee::ui::{button, Button, Column, Element, Renderer, Row, UserInterface, Text};
 
 use std::collections::HashMap;
+use std::time::{Duration, Instant};
 
 use super::appstate::{AppState, StateSet};
 use super::floatingtetromino::FloatingTetromino;
-use super::ltpcontroller::LtpController;
+use super::ltpcontroller;
 use super::states::*;
 
 use lits;
 use lits::{Colour, Player, Tetromino};
+use ltp::{Candidate, LtpClient};
+use utils::error::*;
+use utils::log;
 use utils::notate::Notate;
 
 ///
@@ -24,7 +28,7 @@ use utils::notate::Notate;
 /// - a floating tetromino, which can be picked up from the piece pool and dropped onto the board.
 ///
 /// Engine objects:
-/// - a handle, which is the UUID pointing to a response expected from the engine; and 
+/// - a handle, which is the id of the request whose response is expected from the engine; and 
 /// - an app state, which is an additive set of application states describing modifications to
 ///     functionality.
 ///
@@ -35,26 +39,75 @@ pub struct View
     game: lits::Game,
     backup_copy: lits::Game,
     pub floating_tetromino: Option<FloatingTetromino>,
+    last_eval: Option<f32>,
+    last_pv: Vec<Tetromino>,
+    evals: Vec<f32>,
+    candidates: Vec<Candidate>,
+    notation_input: String,
+    notation_error: Option<String>,
+    placement_error: Option<(String, Instant)>,
+    path_input: String,
+    file_status: Option<String>,
 
     // Engine handles.
 
-    controller: LtpController,
+    controller: LtpClient,
+    handle: Option<u32>,
+    analysis_handle: Option<u32>,
+    moves_handle: Option<u32>,
     app_state: StateSet,
+    strength: usize,
 
     // UI objects.
+
+    new_game_guard: ConfirmGuard,
     
     input_state: InputState,
     window_size: WindowSize,
 
     cancel_search_button: button::State,
     gen_move_button: button::State,
+    analyze_moves_button: button::State,
     undo_move_button: button::State,
+    redo_move_button: button::State,
     new_game_button: button::State,
     setup_mode_button: button::State,
     cancel_setup_button: button::State,
-    confirm_setup_button: button::State
+    confirm_setup_button: button::State,
+    named_setup_buttons: Vec<button::State>,
+    file_mode_button: button::State,
+    save_game_button: button::State,
+    load_game_button: button::State,
+    cancel_file_button: button::State,
+    history_buttons: Vec<button::State>,
+    strength_buttons: [button::State; STRENGTH_LEVELS]
 }
 
+///
+/// The number of moves shown at once in the history panel.
+///
+const HISTORY_ROWS : usize = 12;
+
+///
+/// The number of candidate moves requested from the engine when analyzing a position.
+///
+const ANALYSIS_LINES : usize = 5;
+
+///
+/// The number of strength levels that the engine offers, from 0 to 5.
+///
+const STRENGTH_LEVELS : usize = 6;
+
+///
+/// How long the reason a piece could not be placed stays on screen.
+///
+const PLACEMENT_ERROR_DURATION : Duration = Duration::from_secs(4);
+
+///
+/// How long the engine has to acknowledge the moves played into its position.
+///
+const PLAY_TIMEOUT : Duration = Duration::from_secs(5);
+
 impl std::ops::Drop for View 
 {
     fn drop (self: & mut View) 
@@ -65,13 +118,26 @@ impl std::ops::Drop for View
 
 impl View 
 {
+    ///
+    /// Requests the engine's candidate moves in the position, and swaps to Waiting; the 
+    /// search can be cancelled like a move search, and it plays no move.
+    ///
+    pub fn analyze_moves (& mut self)
+    {
+        self.moves_handle = Some(self.controller.cmd_analyze_moves(ANALYSIS_LINES));
+        self.controller.clear_search_info();
+
+        self.clean_up_piece_mode();
+        self.app_state.insert(AppState::Waiting);
+    }
+
     ///
     /// Blocks on wait-play by first sending an engine cancellation.
     ///
     pub fn cancel_and_play (& mut self)
     {
         self.controller.cmd_cancel();
-        while ! self.wait_to_play() 
+        while ! self.wait_to_play(Duration::from_secs(1)) 
         {
             continue;
         }
@@ -105,17 +171,81 @@ impl View
         self.app_state.insert(AppState::PieceMode);
     }
 
+    ///
+    /// Formats the moves of the game for the history panel: each move is numbered, and 
+    /// the last move played (the current ply) is marked. Undone moves that can still be 
+    /// redone follow the current ply.
+    ///
+    pub fn format_history (game: & lits::Game) -> Vec<(String, bool)>
+    {
+        let ply = game.get_history().len();
+
+        game.get_history().iter()
+            .chain(game.get_future().iter().rev())
+            .enumerate()
+            .map(|(idx, tetromino)| 
+            {
+                let current = idx + 1 == ply;
+                let marker = if current { ">" } else { " " };
+                (format!("{} {}. {}", marker, idx + 1, tetromino.notate()), current)
+            })
+            .collect()
+    }
+
     ///
     /// Requests the engine to generate a move, and swaps to Waiting.
     ///
     pub fn gen_move (& mut self)
     {
-        self.controller.cmd_gen_move(& self.game.to_move());
+        self.handle = Some(self.controller.cmd_gen_move_eval(& self.game.to_move()));
+        self.controller.clear_search_info();
 
         self.clean_up_piece_mode();
         self.app_state.insert(AppState::Waiting);
     }
 
+    ///
+    /// Moves the game to the given ply, and replays the change to the engine.
+    ///
+    pub fn goto_ply (& mut self, ply: usize)
+    {
+        let before = self.game.get_history().clone();
+
+        match self.game.goto(ply)
+        {
+            Ok(()) => 
+            {
+                let after = self.game.get_history().clone();
+                for _ in after.len() .. before.len()
+                {
+                    self.controller.cmd_undo();
+                }
+                if after.len() > before.len()
+                {
+                    if let Err(e) = self.controller.cmd_play_many(& after[before.len() ..], PLAY_TIMEOUT)
+                    {
+                        log::error!("{:#}", e);
+                    }
+                }
+
+                self.clean_up_piece_mode();
+                self.request_analysis();
+            },
+            Err(e) => log::warn!("{:#}", e)
+        };
+    }
+
+    ///
+    /// Arms the new game guard, or starts a new game if it is already armed.
+    ///
+    pub fn guarded_new_game (& mut self)
+    {
+        if self.new_game_guard.press(Instant::now())
+        {
+            self.new_game();
+        }
+    }
+
     ///
     /// Starts a new game.
     ///
@@ -123,11 +253,166 @@ impl View
     {
         let _ = self.controller.cmd_new_game();
         self.game = lits::Game::new();
+        self.evals.clear();
 
         self.clean_up_piece_mode();
         self.app_state.clear();
     }
 
+    ///
+    /// Parses a notation entered by the user, which is either a board notation or a full 
+    /// game notation; since the notation is entered on one line, the moves of a game may 
+    /// be separated from the board and from each other by any whitespace.
+    ///
+    pub fn parse_notation (s: & str) -> Result<lits::Game>
+    {
+        lits::Game::parse(& s.split_whitespace().collect::<Vec<& str>>().join("\n")).context("Could not load the entered notation.")
+    }
+
+    ///
+    /// Replaces the game with the entered notation, pushing it to the engine and leaving 
+    /// setup mode on success, and keeping the error to show otherwise.
+    ///
+    pub fn load_notation (& mut self)
+    {
+        match View::parse_notation(& self.notation_input)
+        {
+            Ok(game) => 
+            {
+                self.replace_game(game);
+                self.notation_input.clear();
+                self.notation_error = None;
+
+                self.app_state.remove(& AppState::PieceMode);
+                self.app_state.remove(& AppState::BoardSetupMode);
+            },
+            Err(e) => 
+            {
+                log::warn!("{:#}", e);
+                self.notation_error = Some(format!("{}", e.root_cause()));
+            }
+        };
+    }
+
+    ///
+    /// Loads the game saved at the entered path, pushing it to the engine and leaving file 
+    /// mode on success, and keeping the error to show otherwise.
+    ///
+    pub fn load_game (& mut self)
+    {
+        let path = self.path_input.clone();
+        let context = format!("Failed to load a game from '{}'.", path);
+
+        let game = std::fs::read_to_string(& path)
+            .context(context.clone())
+            .and_then(|contents| lits::Game::parse(contents.trim()).context(context));
+
+        match game 
+        {
+            Ok(game) => 
+            {
+                self.replace_game(game);
+                self.file_status = None;
+
+                self.app_state.remove(& AppState::FileMode);
+                log::info!("Loaded a game from '{}'.", path);
+            },
+            Err(e) => 
+            {
+                log::warn!("{:#}", e);
+                self.file_status = Some(format!("{}", e.root_cause()));
+            }
+        };
+    }
+
+    ///
+    /// Polls for the response to the outstanding analysis request, if there is one, 
+    /// without blocking; responses to other requests are left with the controller for 
+    /// whoever waits on them.
+    ///
+    pub fn poll_analysis (& mut self)
+    {
+        let id = match self.analysis_handle 
+        {
+            Some(id) => id,
+            None     => return
+        };
+
+        match self.controller.poll_for(id)
+        {
+            Some(Ok(response)) => { self.take_analysis(id, & response); },
+            Some(Err(e))       => 
+            {
+                log::warn!("{:#}", e);
+                self.analysis_handle = None;
+            },
+            None               => {}
+        };
+    }
+
+    ///
+    /// Replaces the game with the given game, pushing its setup and its moves to the engine.
+    ///
+    pub fn replace_game (& mut self, mut game: lits::Game)
+    {
+        self.controller.cmd_apply_setup(game.get_board_base());
+        if ! game.get_history().is_empty()
+        {
+            if let Err(e) = self.controller.cmd_play_many(game.get_history(), PLAY_TIMEOUT)
+            {
+                log::error!("{:#}", e);
+            }
+        }
+
+        self.game = game;
+        self.evals.clear();
+        self.request_analysis();
+    }
+
+    ///
+    /// Requests an analysis of the game from the engine, for the evaluation graph; it 
+    /// supersedes any analysis that is still outstanding. Since the game has changed, the 
+    /// candidate moves of the previous position are discarded.
+    ///
+    pub fn request_analysis (& mut self)
+    {
+        self.candidates.clear();
+        self.analysis_handle = Some(self.controller.cmd_analyze());
+    }
+
+    ///
+    /// Saves the game, including any moves that were taken back, to the entered path, and 
+    /// shows whether it succeeded.
+    ///
+    pub fn save_game (& mut self)
+    {
+        let path = self.path_input.clone();
+        let context = format!("Failed to save the game to '{}'.", path);
+
+        match std::fs::write(& path, self.game.notate()).context(context)
+        {
+            Ok(()) => 
+            {
+                log::info!("Saved the game to '{}'.", path);
+                self.file_status = Some(format!("Saved to '{}'.", path));
+            },
+            Err(e) => 
+            {
+                log::warn!("{:#}", e);
+                self.file_status = Some(format!("{}", e.root_cause()));
+            }
+        };
+    }
+
+    ///
+    /// Sets the engine's playing strength, which applies from its next search.
+    ///
+    pub fn set_strength (& mut self, level: usize)
+    {
+        self.controller.cmd_set_strength(level);
+        self.strength = level;
+    }
+
     ///
     /// Cancels the setup, returning to the previous position.
     ///
@@ -146,11 +431,48 @@ impl View
     {
         self.game = lits::Game::parse(& self.game.get_board().notate()).unwrap();
         let _ = self.controller.cmd_apply_setup(self.game.get_board_base());
+        self.request_analysis();
 
         self.app_state.remove(& AppState::PieceMode);
         self.app_state.remove(& AppState::BoardSetupMode);
     }
 
+    ///
+    /// Replaces the game with the named setup, with the index given in the setup catalogue's 
+    /// list of names, pushing it to the engine and leaving setup mode.
+    ///
+    pub fn setup_named (& mut self, idx: usize)
+    {
+        let name = lits::setups::names()[idx];
+        match lits::setups::named(name).and_then(|board| lits::Game::from_moves(& board, ""))
+        {
+            Ok(game) => 
+            {
+                self.replace_game(game);
+                self.notation_error = None;
+
+                self.app_state.remove(& AppState::PieceMode);
+                self.app_state.remove(& AppState::BoardSetupMode);
+            },
+            Err(e)   => 
+            {
+                log::warn!("{:#}", e);
+                self.notation_error = Some(format!("{}", e.root_cause()));
+            }
+        };
+    }
+
+    ///
+    /// Enters file mode, where a path is entered to save the game to or load a game from.
+    ///
+    pub fn swap_to_file (& mut self)
+    {
+        self.file_status = None;
+
+        self.clean_up_piece_mode();
+        self.app_state.insert(AppState::FileMode);
+    }
+
     ///
     /// Saves the game into the backup copy slot and enters setup mode.
     ///
@@ -163,6 +485,61 @@ impl View
         self.app_state.insert(AppState::BoardSetupMode);
     }
 
+    ///
+    /// Takes the given response as the evaluations for the graph if it answers the 
+    /// outstanding analysis request, and returns whether it did.
+    ///
+    pub fn take_analysis (& mut self, id: u32, response: & str) -> bool 
+    {
+        if self.analysis_handle != Some(id)
+        {
+            return false;
+        }
+        self.analysis_handle = None;
+
+        match LtpClient::parse_analysis_response(response)
+        {
+            Ok(evals) => self.evals = evals,
+            Err(e)    => log::warn!("{:#}", e)
+        };
+        true
+    }
+
+    ///
+    /// Applies typed characters to the notation being entered: backspace erases, enter 
+    /// loads the notation, and other control characters are ignored.
+    ///
+    pub fn type_notation (& mut self, text: & str)
+    {
+        for character in text.chars()
+        {
+            match character 
+            {
+                '\u{8}'      => { self.notation_input.pop(); },
+                '\r' | '\n' => self.load_notation(),
+                c if c.is_control() => {},
+                c            => self.notation_input.push(c)
+            };
+        }
+    }
+
+    ///
+    /// Applies typed characters to the path being entered: backspace erases, and control 
+    /// characters are ignored.
+    ///
+    pub fn type_path (& mut self, text: & str)
+    {
+        for character in text.chars()
+        {
+            match character 
+            {
+                '\u{8}'             => { self.path_input.pop(); },
+                c if c.is_control() => {},
+                c                   => self.path_input.push(c)
+            };
+        }
+    }
+
     ///
     /// Determines the point the mouse is over, if any.
     ///
@@ -191,11 +568,49 @@ impl View
     ///
     pub fn try_placing_piece (& mut self)
     {
-        let floater = self.floating_tetromino.as_mut().unwrap();
-        if self.game.apply(& floater.tetromino()).is_ok()
+        let tetromino = self.floating_tetromino.as_ref().unwrap().tetromino();
+        match self.game.apply(& tetromino)
         {
-            self.controller.cmd_play(& floater.tetromino());
-            self.clean_up_piece_mode();
+            Ok(()) => 
+            {
+                if let Err(e) = self.controller.cmd_play(& tetromino, PLAY_TIMEOUT)
+                {
+                    log::error!("{:#}", e);
+                }
+                self.placement_error = None;
+                self.clean_up_piece_mode();
+                self.request_analysis();
+            },
+            Err(e) => 
+            {
+                // The message is the specific rule that was broken, if the piece broke one.
+
+                let message = match lits::PlacementError::find(& e)
+                {
+                    Some(reason) => format!("Cannot place '{}': {}", tetromino.notate(), reason),
+                    None         => format!("{}", e.root_cause())
+                };
+                log::debug!("{:#}", e);
+                self.placement_error = Some((message, Instant::now()));
+            }
+        };
+    }
+
+    ///
+    /// Tries to redo the last undone move.
+    ///
+    pub fn try_redo (& mut self)
+    {
+        if ! self.game.get_future().is_empty() && self.game.redo().is_ok()
+        {
+            let tetromino = self.game.get_history().last().unwrap().clone();
+
+            if let Err(e) = self.controller.cmd_play(& tetromino, PLAY_TIMEOUT)
+            {
+                log::error!("{:#}", e);
+            }
+            self.app_state.clear();
+            self.request_analysis();
         }
     }
 
@@ -210,6 +625,7 @@ impl View
             {
                 self.controller.cmd_undo();
                 self.app_state.clear();
+                self.request_analysis();
             }
         }
     }
@@ -249,32 +665,81 @@ impl View
 
     ///
     /// The transition function from Waiting to InGame;
-    /// when it receives an engine response, it plays it 
-    /// into the position and moves to InGame.
+    /// when it receives an engine response within the 
+    /// budget, it plays it into the position and moves 
+    /// to InGame.
     ///
-    pub fn wait_to_play (& mut self) -> bool
+    pub fn wait_to_play (& mut self, budget: Duration) -> bool
     {
         // Wait for a response.
 
-        let engine_response = self.controller.poll_response();
+        let engine_response = self.controller.poll_response_timeout(budget);
         let response = match engine_response 
         {
             Ok(string) => Some(string),
             Err(_)     => None
         };
 
-        if response.is_some()
+        if let Some((id, response)) = response
         {
-            // Parse the response to get the tetromino.
+            // An analysis may arrive while waiting; it updates the graph, and the wait goes on.
+
+            if self.take_analysis(id, & response)
+            {
+                return false;
+            }
+
+            // The candidate moves of the position end the wait without playing a move.
+
+            if self.moves_handle == Some(id)
+            {
+                self.moves_handle = None;
+                match LtpClient::parse_moves_response(& response)
+                {
+                    Ok(candidates) => self.candidates = candidates,
+                    Err(e)         => log::warn!("{:#}", e)
+                };
+
+                self.app_state.remove(& AppState::Waiting);
+                return true;
+            }
+
+            // Responses to requests other than the outstanding one are stale, so they are dropped.
+
+            if self.handle != Some(id)
+            {
+                log::warn!("Dropped response '{}' to request {}, while waiting on {:?}.", response, id, self.handle);
+                return false;
+            }
+            self.handle = None;
 
-            let tetromino = Tetromino::parse(& response.unwrap().split_once(" ").unwrap().1.to_string()).unwrap();
+            // Parse the response to get the tetromino and the engine's eval.
+
+            let (tetromino, eval, pv) = match LtpClient::parse_move_response(& response)
+            {
+                Ok(parsed) => parsed,
+                Err(e)     => 
+                {
+                    // The engine could not produce a move, for example in a terminal position.
+
+                    log::warn!("{:#}", e);
+                    self.app_state.remove(& AppState::Waiting);
+                    return true;
+                }
+            };
+            self.last_eval = eval;
+            self.last_pv = pv;
 
             // Play the move and update the app state.
 
             if self.game.apply(& tetromino).is_ok()
             {
-                self.controller.cmd_play(& tetromino);
+                if let Err(e) = self.controller.cmd_play(& tetromino, PLAY_TIMEOUT)
+                {
+                    log::error!("{:#}", e);
+                }
                 self.app_state.remove(& AppState::Waiting);
+                self.request_analysis();
             }
 
             return true;
@@ -490,6 +955,52 @@ impl Game for View
             }
         }
 
+        // Draw the evaluation graph: X's favour after each move, with the current 
+        // position highlighted. The scale grows to fit evals beyond a unit value.
+
+        if ! self.evals.is_empty()
+        {
+            let area = self.window_size.get_graph_area();
+            let scale = self.evals.iter().fold(1.0_f32, |scale, eval| scale.max(eval.abs()));
+            let mid = area.y + area.height / 2.0;
+
+            let point_at = |idx: usize, eval: f32| -> Point 
+            {
+                let x = match self.evals.len() 
+                {
+                    1 => area.x + area.width / 2.0,
+                    n => area.x + area.width * idx as f32 / (n - 1) as f32
+                };
+                Point::new(x, mid - (eval / scale) * area.height / 2.0)
+            };
+
+            mesh.fill(Shape::Rectangle(area), fg);
+            mesh.stroke(
+                Shape::Polyline { points: vec![Point::new(area.x, mid), Point::new(area.x + area.width, mid)] },
+                border,
+                borderwidth / 2.0
+            );
+
+            let points = self.evals.iter().enumerate().map(|(idx, & eval)| point_at(idx, eval)).collect::<Vec<Point>>();
+            if points.len() > 1 
+            {
+                mesh.stroke(Shape::Polyline { points }, * colours.get(& Colour::None).unwrap(), borderwidth);
+            }
+
+            let ply = self.game.get_history().len();
+            if let Some(& eval) = self.evals.get(ply)
+            {
+                let current = point_at(ply, eval);
+
+                mesh.stroke(
+                    Shape::Polyline { points: vec![Point::new(current.x, area.y), Point::new(current.x, area.y + area.height)] },
+                    border,
+                    borderwidth / 2.0
+                );
+                mesh.fill(Shape::Circle { center: current, radius: 2.0 * borderwidth }, * colours.get(& Colour::I).unwrap());
+            }
+        }
+
         mesh.draw(& mut frame.as_target());
     }
 
@@ -512,8 +1023,22 @@ impl Game for View
             }
             else 
             {
-                self.wait_to_play();
+                self.wait_to_play(Duration::from_millis(100));
             }
+
+            return;
+        }
+
+        // Outside of a search, an outstanding analysis is polled for without blocking the interface.
+
+        self.poll_analysis();
+
+        if self.app_state.contains(& AppState::FileMode)
+        {
+            // Typing enters the path to save to or load from.
+
+            let text = self.input_state.text_entered.clone();
+            self.type_path(& text);
         }
         else if self.app_state.contains(& AppState::PieceMode)
         { 
@@ -560,6 +1085,11 @@ impl Game for View
         }
         else if self.app_state.contains(& AppState::BoardSetupMode)
         {
+            // Typing enters a board or game notation to load instead of the setup.
+
+            let text = self.input_state.text_entered.clone();
+            self.type_notation(& text);
+
             // Left-clicking a tile cycles its colour, right-clicking a tile cycles 
             // its player.
             
@@ -587,11 +1117,32 @@ impl Game for View
         }
         else
         {
+            // Clicking on the board disarms a pending new game.
+
+            if self.input_state.mouse_buttons_pressed.contains(& mouse::Button::Left) && self.tile_at_mouse().is_some()
+            {
+                self.new_game_guard.cancel();
+            }
+
             if self.input_state.keys_pressed.contains(& keyboard::KeyCode::M)
                 && self.input_state.keys_pressed.contains(& keyboard::KeyCode::LControl)
             {
                 self.gen_move();
             }
+
+            // The arrow keys step through the history; each press steps once, since the key 
+            // is released from the input until the window repeats it.
+
+            if self.input_state.keys_pressed.contains(& keyboard::KeyCode::Left)
+            {
+                input.keys_pressed.remove(& keyboard::KeyCode::Left);
+                self.try_undo();
+            }
+            else if self.input_state.keys_pressed.contains(& keyboard::KeyCode::Right)
+            {
+                input.keys_pressed.remove(& keyboard::KeyCode::Right);
+                self.try_redo();
+            }
             
             let colour_to_keycode = HashMap::from([
                 (Colour::L, keyboard::KeyCode::L),
@@ -619,17 +1170,40 @@ impl Game for View
                 game: lits::Game::new(),
                 backup_copy: lits::Game::new(),
                 floating_tetromino: None,
-                controller: LtpController::new(),
+                last_eval: None,
+                last_pv: Vec::new(),
+                evals: Vec::new(),
+                candidates: Vec::new(),
+                notation_input: String::new(),
+                notation_error: None,
+                placement_error: None,
+                path_input: String::new(),
+                file_status: None,
+                controller: ltpcontroller::connect(),
+                handle: None,
+                analysis_handle: None,
+                moves_handle: None,
                 app_state: StateSet::new(),
+                strength: STRENGTH_LEVELS - 1,
+                new_game_guard: ConfirmGuard::new(Duration::from_secs(3)),
                 input_state: InputState::new(),
                 window_size: WindowSize::new(0.0, 0.0),
                 cancel_search_button: button::State::new(),
                 gen_move_button: button::State::new(),
+                analyze_moves_button: button::State::new(),
                 undo_move_button: button::State::new(),
+                redo_move_button: button::State::new(),
                 new_game_button: button::State::new(),
                 setup_mode_button: button::State::new(),
                 cancel_setup_button: button::State::new(),
-                confirm_setup_button: button::State::new()
+                confirm_setup_button: button::State::new(),
+                named_setup_buttons: Vec::new(),
+                file_mode_button: button::State::new(),
+                save_game_button: button::State::new(),
+                load_game_button: button::State::new(),
+                cancel_file_button: button::State::new(),
+                history_buttons: Vec::new(),
+                strength_buttons: Default::default()
             }
         )
     }
@@ -646,12 +1220,47 @@ impl UserInterface for View
 
         if self.app_state.contains(& AppState::Waiting)
         {
+            // The engine's latest progress report shows that the search is alive.
+
+            let info_text = match self.controller.search_info()
+            {
+                Some(info) => format!("Best: {}  Eval: {:+.3}  Nodes: {}  ({} nps, {:.1}s)", info.tetromino.notate(), info.eval, info.nodes, info.nps, info.time_ms as f32 / 1000.0),
+                None       => "Searching...".to_owned()
+            };
+
             return Row::new().padding(self.window_size.get_border_width().round() as u32)
                 .max_height(bw)
                 .push(
                     Button::new(& mut self.cancel_search_button, "Cancel Search")
                         .on_press(EventState::CancelSearchButton).width(bw)
                 )
+                .push(
+                    Text::new(& info_text)
+                )
+                .into();
+        }
+        else if self.app_state.contains(& AppState::FileMode)
+        {
+            return Row::new().padding(self.window_size.get_border_width().round() as u32)
+                .max_height(bw)
+                .push(
+                    Button::new(& mut self.cancel_file_button, "Back")
+                        .on_press(EventState::CancelFileButton).width(bw)
+                )
+                .push(
+                    Button::new(& mut self.save_game_button, "Save Game")
+                        .on_press(EventState::SaveGameButton).width(bw)
+                )
+                .push(
+                    Button::new(& mut self.load_game_button, "Load Game")
+                        .on_press(EventState::LoadGameButton).width(bw)
+                )
+                .push(
+                    Text::new(& format!("Path: {}_", self.path_input))
+                )
+                .push(
+                    Text::new(self.file_status.as_deref().unwrap_or(""))
+                )
                 .into();
         }
         else if self.app_state.contains(& AppState::BoardSetupMode)
@@ -662,7 +1271,24 @@ impl UserInterface for View
                 None        => "none".to_owned()
             };
 
-            return Row::new().padding(self.window_size.get_border_width().round() as u32)
+            // A row of buttons starts the game from any of the named setups instead.
+
+            let names = lits::setups::names();
+            self.named_setup_buttons.resize_with(names.len(), button::State::new);
+
+            let mut named_setups = Row::new().padding(self.window_size.get_border_width().round() as u32)
+                .max_height(bw)
+                .push(
+                    Text::new("Named setups:")
+                );
+            for (idx, (state, name)) in self.named_setup_buttons.iter_mut().zip(names).enumerate()
+            {
+                named_setups = named_setups.push(
+                    Button::new(state, name).on_press(EventState::NamedSetup(idx)).width(bw)
+                );
+            }
+
+            let controls = Row::new().padding(self.window_size.get_border_width().round() as u32)
                 .max_height(bw)
                 .push(
                     Button::new(& mut self.cancel_setup_button, "Discard Setup")
@@ -675,43 +1301,229 @@ impl UserInterface for View
                 .push(
                     Text::new(& pt_text.clone())
                 )
+                .push(
+                    Text::new(& format!("Notation: {}_", self.notation_input))
+                )
+                .push(
+                    Text::new(self.notation_error.as_deref().unwrap_or(""))
+                );
+
+            return Column::new()
+                .push(controls)
+                .push(named_setups)
                 .into();
         }
         else 
         {
-            return Row::new().padding(self.window_size.get_border_width().round() as u32)
+            let eval_text = match self.last_eval 
+            {
+                Some(eval) => format!("Eval: {:+.3}", eval),
+                None       => "".to_owned()
+            };
+
+            let pv_text = match self.last_pv.is_empty()
+            {
+                true  => "".to_owned(),
+                false => format!("Expected line: {}", self.last_pv.iter().map(|tetromino| tetromino.notate()).collect::<Vec<String>>().join(" "))
+            };
+
+            let placement_text = match & self.placement_error 
+            {
+                Some((message, at)) if at.elapsed() <= PLACEMENT_ERROR_DURATION => message.clone(),
+                _ => "".to_owned()
+            };
+
+            let new_game_text = match self.new_game_guard.is_armed(Instant::now())
+            {
+                true  => "Confirm New Game?",
+                false => "New Game"
+            };
+
+            // The history panel shows a window of moves that keeps the current ply in view.
+
+            let history = View::format_history(& self.game);
+            let ply = self.game.get_history().len();
+            let end = (ply + HISTORY_ROWS / 2).max(HISTORY_ROWS).min(history.len());
+            let start = end.saturating_sub(HISTORY_ROWS);
+
+            self.history_buttons.resize_with(history.len(), button::State::new);
+
+            let mut history_panel = Column::new().padding(self.window_size.get_border_width().round() as u32);
+            for (idx, (state, (text, current))) in self.history_buttons.iter_mut().zip(history.iter()).enumerate().skip(start).take(end - start)
+            {
+                let class = match current 
+                {
+                    true  => button::Class::Primary,
+                    false => button::Class::Secondary
+                };
+
+                history_panel = history_panel.push(
+                    Button::new(state, text).class(class).on_press(EventState::HistoryEntry(idx + 1)).width(4 * bw)
+                );
+            }
+
+            // The selected strength is highlighted, like the current move in the history.
+
+            let mut strength_row = Row::new().padding(self.window_size.get_border_width().round() as u32)
+                .max_height(bw)
+                .push(
+                    Text::new("Strength:")
+                );
+            for (level, state) in self.strength_buttons.iter_mut().enumerate()
+            {
+                let class = match level == self.strength 
+                {
+                    true  => button::Class::Primary,
+                    false => button::Class::Secondary
+                };
+
+                strength_row = strength_row.push(
+                    Button::new(state, & level.to_string()).class(class).on_press(EventState::StrengthButton(level)).width(bw)
+                );
+            }
+
+            // The candidate moves are listed most visited first, with their variations.
+
+            let mut candidate_list = Column::new().padding(self.window_size.get_border_width().round() as u32);
+            for (idx, candidate) in self.candidates.iter().enumerate()
+            {
+                let pv = candidate.pv.iter().map(|tetromino| tetromino.notate()).collect::<Vec<String>>().join(" ");
+                candidate_list = candidate_list.push(
+                    Text::new(& format!("{}. {} ({} visits, prior {:.3}, eval {:+.3}): {}", idx + 1, candidate.tetromino.notate(), candidate.visits, candidate.prior, candidate.eval, pv))
+                );
+            }
+
+            let controls = Row::new().padding(self.window_size.get_border_width().round() as u32)
                 .max_height(bw)
                 .push(
                     Button::new(& mut self.gen_move_button, "Generate Move")
                         .on_press(EventState::PlayMoveButton).width(bw)
                 )
+                .push(
+                    Button::new(& mut self.analyze_moves_button, "Analyze Moves")
+                        .on_press(EventState::AnalyzeMovesButton).width(bw)
+                )
                 .push(
                     Button::new(& mut self.undo_move_button, "Undo Move")
                         .on_press(EventState::UndoMoveButton).width(bw)
                 )
                 .push(
-                    Button::new(& mut self.new_game_button, "New Game")
+                    Button::new(& mut self.redo_move_button, "Redo Move")
+                        .on_press(EventState::RedoMoveButton).width(bw)
+                )
+                .push(
+                    Button::new(& mut self.new_game_button, new_game_text)
                         .on_press(EventState::NewGameButton).width(bw)
                 )
                 .push(
                     Button::new(& mut self.setup_mode_button, "Enter Setup Mode")
                         .on_press(EventState::SetupModeButton).width(bw)
                 )
+                .push(
+                    Button::new(& mut self.file_mode_button, "Save/Load Game")
+                        .on_press(EventState::FileModeButton).width(bw)
+                )
+                .push(
+                    Text::new(& eval_text)
+                )
+                .push(
+                    Text::new(& pv_text)
+                )
+                .push(
+                    Text::new(& placement_text)
+                );
+
+            return Column::new()
+                .push(controls)
+                .push(strength_row)
+                .push(candidate_list)
+                .push(history_panel)
                 .into();
         }
     }
 
     fn react (& mut self, message: EventState, _window: & mut Window)
     {
+        if ! matches!(message, EventState::NewGameButton)
+        {
+            self.new_game_guard.cancel();
+        }
+
         match message 
         {
-            EventState::NewGameButton      => self.new_game(),
+            EventState::NewGameButton      => self.guarded_new_game(),
             EventState::SetupModeButton    => self.swap_to_setup(),
             EventState::PlayMoveButton     => self.gen_move(),
+            EventState::AnalyzeMovesButton => self.analyze_moves(),
             EventState::CancelSearchButton => self.cancel_and_play(),
             EventState::ConfirmSetupButton => self.setup_confirm(),
             EventState::CancelSetupButton  => self.setup_cancel(),
-            EventState::UndoMoveButton     => self.try_undo()
+            EventState::UndoMoveButton     => self.try_undo(),
+            EventState::RedoMoveButton     => self.try_redo(),
+            EventState::FileModeButton     => self.swap_to_file(),
+            EventState::SaveGameButton     => self.save_game(),
+            EventState::LoadGameButton     => self.load_game(),
+            EventState::CancelFileButton   => { self.app_state.remove(& AppState::FileMode); },
+            EventState::HistoryEntry(ply)  => self.goto_ply(ply),
+            EventState::NamedSetup(idx)    => self.setup_named(idx),
+            EventState::StrengthButton(l)  => self.set_strength(l)
         };
     }
 }
+
+#[cfg(test)]
+mod tests 
+{
+    use super::*;
+
+    #[test]
+    fn history_marks_the_current_ply ()
+    {
+        Tetromino::initialize();
+
+        let mut game = lits::Game::new();
+        let mut moves = Vec::new();
+        for _ in 0 .. 3 
+        {
+            let tetromino = game.get_board().enumerate_moves().into_iter().next().unwrap();
+            game.apply(& tetromino).unwrap();
+            moves.push(tetromino.notate());
+        }
+        game.undo().unwrap();
+
+        // The undone move can still be redone, so it follows the current ply.
+
+        assert_eq!(View::format_history(& game), vec![
+            (format!("  1. {}", moves[0]), false),
+            (format!("> 2. {}", moves[1]), true),
+            (format!("  3. {}", moves[2]), false)
+        ]);
+        assert!(View::format_history(& lits::Game::new()).is_empty());
+    }
+
+    #[test]
+    fn pasted_notations_parse_on_one_line ()
+    {
+        Tetromino::initialize();
+
+        let mut game = lits::Game::new();
+        for _ in 0 .. 2 
+        {
+            let tetromino = game.get_board().enumerate_moves().into_iter().next().unwrap();
+            game.apply(& tetromino).unwrap();
+        }
+
+        let pasted = game.notate().replace('\n', " \t ");
+        assert_eq!(View::parse_notation(& pasted).unwrap(), game);
+
+        let board = game.get_board().notate();
+        assert_eq!(View::parse_notation(& board).unwrap().get_board().notate(), board);
+
+        // A move that is already played cannot be played again.
+
+        let repeated = format!("{} {}", pasted, game.get_history()[1].notate());
+        assert!(View::parse_notation(& repeated).is_err());
+        assert!(View::parse_notation("not a board").is_err());
+        assert!(View::parse_notation("").is_err());
+    }
+}
@@ -0,0 +1,19 @@
+
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use engine::neural::input::Input;
+
+use lits::*;
+
+use std::hint::black_box;
+
+fn input_from_mid (c: & mut Criterion)
+{
+    Tetromino::initialize();
+    let board = bench_fixtures()[1].clone();
+
+    c.bench_function("input_from_mid", |b| b.iter(|| black_box(Input::from(board.clone()))));
+}
+
+criterion_group!(benches, input_from_mid);
+criterion_main!(benches);
@@ -1,6 +1,8 @@
 
 use utils::{Serialize, Deserialize};
+use utils::error::*;
 
+pub use crate::mcts::book::Config as BookConfig;
 pub use crate::mcts::config::Config as MCTSConfig;
 pub use crate::neural::config::Config as NeuralConfig;
 pub use crate::interfaces::selfplay::config::Config as SelfplayConfig;
@@ -20,8 +22,35 @@ pub struct Config
     #[serde(default)]
     pub selfplay: SelfplayConfig,
 
+    #[serde(default)]
+    pub book: BookConfig,
+
     #[serde(default = "log_path")]
-    pub log_path: String
+    pub log_path: String,
+
+    #[serde(default = "log_spec")]
+    pub log_spec: String,
+
+    #[serde(default)]
+    pub seed: u64
+}
+
+impl Config 
+{
+    ///
+    /// Checks that every value in this configuration is usable, describing the first
+    /// offending value otherwise.
+    ///
+    pub fn validate (& self) -> Result<()>
+    {
+        let context = "Invalid engine configuration.";
+
+        self.mcts.validate().context(context)?;
+        self.neural.validate().context(context)?;
+        self.selfplay.validate().context(context)?;
+
+        Ok(())
+    }
 }
 
 ///
@@ -32,3 +61,52 @@ fn log_path () -> String
     "logs".to_owned()
 }
 
+
+///
+/// Returns the default log specification.
+///
+fn log_spec () -> String 
+{
+    "debug".to_owned()
+}
+
+#[cfg(test)]
+mod tests 
+{
+    use super::*;
+
+    ///
+    /// Returns the error of validating the configuration in the given TOML, if any.
+    ///
+    fn invalid (toml: & str) -> Option<String>
+    {
+        let config : Config = toml::from_str(toml).unwrap();
+        config.validate().err().map(|e| format!("{:#}", e))
+    }
+
+    #[test]
+    fn default_configuration_is_valid ()
+    {
+        assert_eq!(invalid(""), None);
+    }
+
+    #[test]
+    fn each_invalid_field_is_named ()
+    {
+        let cases = 
+        [
+            ("[mcts]\nnum_threads = 0", "mcts.num_threads"),
+            ("[mcts]\nmax_time_ms = 0", "mcts.max_time_ms"),
+            ("[mcts]\ndiscount = 0.0", "mcts.discount"),
+            ("[mcts]\ndiscount = 1.5", "mcts.discount"),
+            ("[neural]\nlearning_rate = 0.0", "neural.learning_rate"),
+            ("[neural]\nepochs = 0", "neural.epochs")
+        ];
+
+        for (toml, field) in cases 
+        {
+            let error = invalid(toml).unwrap();
+            assert!(error.contains(field), "'{}' gave '{}'", toml, error);
+        }
+    }
+}
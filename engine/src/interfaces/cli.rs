@@ -0,0 +1,168 @@
+
+use crate::config::*;
+use crate::mcts::book::Book;
+use crate::mcts::mcts::MCTS;
+
+use lits::{Game, Player, Tetromino};
+
+use std::io::Write;
+
+use utils::error::*;
+use utils::log;
+use utils::notate::Notate;
+
+///
+/// The help text printed by the 'help' command.
+///
+const HELP : & str = "\
+<piece>  play the given tetromino, as in 'L[00,01,02,12]'
+swap     swap sides with the engine, which then plays if it is to move
+undo     take back your last move, along with the engine's reply
+new      start a new game
+moves    list the legal moves in this position
+help     show this help
+quit     leave the game";
+
+///
+/// Runs a game against the engine in the terminal, for play without the client.
+///
+pub struct CLIInterface
+{
+    mcts: MCTS,
+    state: Game,
+    human: Player
+}
+
+impl CLIInterface
+{
+    ///
+    /// Applies the given move to the game, and moves the search trees along it.
+    ///
+    fn apply (& mut self, tetromino: & Tetromino) -> Result<()>
+    {
+        self.state.apply(tetromino)?;
+        self.mcts.pivot(tetromino, self.state.get_board());
+        Ok(())
+    }
+
+    ///
+    /// Searches the current position and plays the engine's move.
+    ///
+    fn engine_move (& mut self) -> Result<()>
+    {
+        let context = "The engine failed to move.";
+
+        let tetromino = self.mcts.search_return(self.state.get_board()).context(context)?;
+        self.apply(& tetromino).context(context)?;
+
+        println!("Engine plays {} ({:+.3}).", tetromino.notate(), self.mcts.best_eval());
+        Ok(())
+    }
+
+    ///
+    /// Creates a new CLI interface, in which the human plays X; the engine consults the
+    /// configured opening book, if any, before searching.
+    ///
+    pub fn new (config: & Config) -> Result<CLIInterface>
+    {
+        let mut mcts = MCTS::new(config.clone())?;
+        mcts.set_book(Book::open(& config.book)?);
+        Ok(CLIInterface { mcts, state: Game::new(), human: Player::X })
+    }
+
+    ///
+    /// Prints the board and whose turn it is, or the result if the game is over.
+    ///
+    fn print_position (& mut self)
+    {
+        let board = self.state.get_board().clone();
+        println!("\n{}", board);
+
+        match board.has_moves()
+        {
+            true  => println!("{} to move.", board.to_move()),
+            false => println!("Game over: {}", board.result())
+        };
+    }
+
+    ///
+    /// Runs the game loop until the human quits or the input ends.
+    ///
+    pub fn run_loop (& mut self)
+    {
+        log::info!("CLI controller");
+        println!("You play {}; type 'help' for the commands.", self.human);
+
+        let mut line = String::new();
+        loop
+        {
+            // The engine answers whenever it is to move in a game that is not over.
+
+            if self.state.get_board().to_move() != self.human && self.state.get_board().has_moves()
+            {
+                if let Err(e) = self.engine_move()
+                {
+                    log::error!("{:#}", e);
+                    println!("{}", e.root_cause());
+                }
+            }
+
+            self.print_position();
+            print!("> ");
+            let _ = std::io::stdout().flush();
+
+            line.clear();
+            match std::io::stdin().read_line(& mut line)
+            {
+                Ok(0) | Err(_) => break,
+                Ok(_)          => {}
+            };
+
+            match line.trim()
+            {
+                "" => continue,
+                "quit" => break,
+                "help" => println!("{}", HELP),
+                "swap" =>
+                {
+                    self.human = self.human.next();
+                    println!("You play {}.", self.human);
+                },
+                "new" =>
+                {
+                    self.state = Game::new();
+                    self.mcts.prepare(self.state.get_board());
+                },
+                "moves" =>
+                {
+                    let moves = self.state.get_board().enumerate_moves();
+                    println!("{}", moves.iter().map(|tetromino| tetromino.notate()).collect::<Vec<String>>().join(" "));
+                },
+                "undo" =>
+                {
+                    // Moves are taken back until it is the human's turn again.
+
+                    let mut result = self.state.undo();
+                    while result.is_ok() && self.state.get_board().to_move() != self.human
+                    {
+                        result = self.state.undo();
+                    }
+
+                    if let Err(e) = result
+                    {
+                        println!("{}", e.root_cause());
+                    }
+                    self.mcts.prepare(self.state.get_board());
+                },
+                notation =>
+                {
+                    let result = Tetromino::parse(notation).and_then(|tetromino| self.apply(& tetromino));
+                    if let Err(e) = result
+                    {
+                        println!("{}", e.root_cause());
+                    }
+                }
+            };
+        }
+    }
+}
@@ -0,0 +1,290 @@
+
+use crate::config::*;
+use crate::mcts::book::Book;
+use crate::mcts::mcts::MCTS;
+
+use lits::{Board, Game, Outcome, Player, Tetromino};
+
+use utils::error::*;
+use utils::log;
+use utils::notate::Notate;
+
+///
+/// The version of the Go Text Protocol spoken by this interface.
+///
+pub const GTP_VERSION : u32 = 2;
+
+///
+/// The commands supported by this interface, reported by 'list_commands'.
+///
+pub const GTP_COMMANDS : [& str; 17] =
+[
+    "boardsize", "clear_board", "final_score", "genmove", "known_command", "komi", "list_commands", "lits-setup",
+    "name", "play", "protocol_version", "quit", "showboard", "time_left", "time_settings", "undo", "version"
+];
+
+///
+/// Runs the main loop and interfaces with a controller speaking the Go Text Protocol, so
+/// that GTP tooling can drive the engine.
+///
+/// Black is X and white is O; moves are tetromino notations, and there is no passing.
+/// The board is always 10 by 10, and komi is accepted but ignored.
+///
+pub struct GTPInterface
+{
+    mcts: MCTS,
+    state: Game
+}
+
+impl GTPInterface
+{
+    ///
+    /// Executes the given command, returning its response payload, or the reason it failed;
+    /// a payload may span several lines.
+    ///
+    fn execute (& mut self, cmd: & str, args: & [& str]) -> Result<String>
+    {
+        match cmd
+        {
+            "protocol_version" => Ok(GTP_VERSION.to_string()),
+            "name"             => Ok("blits".to_owned()),
+            "version"          => Ok(env!("CARGO_PKG_VERSION").to_owned()),
+            "known_command"    => Ok(args.first().is_some_and(|cmd| GTP_COMMANDS.contains(cmd)).to_string()),
+            "list_commands"    => Ok(GTP_COMMANDS.join("\n")),
+            "komi"             => Ok(String::new()),
+            "quit"             => Ok(String::new()),
+
+            "boardsize" => match args.first().map(|size| size.parse::<usize>())
+            {
+                Some(Ok(10)) => Ok(String::new()),
+                _            => Err(error!("unacceptable size"))
+            },
+
+            "clear_board" =>
+            {
+                self.state = Game::new();
+                self.mcts.prepare(self.state.get_board());
+                Ok(String::new())
+            },
+
+            "lits-setup" =>
+            {
+                let notation = args.first().ok_or_else(|| error!("syntax error"))?;
+                let setup = Board::parse(notation).map_err(|_| error!("invalid setup"))?;
+
+                self.state = Game::parse(& setup.notate()).map_err(|_| error!("invalid setup"))?;
+                self.mcts.prepare(self.state.get_board());
+                Ok(String::new())
+            },
+
+            "play" =>
+            {
+                let (colour, notation) = match args
+                {
+                    [colour, notation, ..] => (GTPInterface::parse_colour(colour)?, notation),
+                    _                      => return Err(error!("syntax error"))
+                };
+
+                if colour != self.state.get_board().to_move()
+                {
+                    return Err(error!("illegal move"));
+                }
+
+                let tetromino = Tetromino::parse(notation).map_err(|_| error!("illegal move"))?;
+                self.state.apply(& tetromino).map_err(|_| error!("illegal move"))?;
+                self.mcts.pivot(& tetromino, self.state.get_board());
+                Ok(String::new())
+            },
+
+            "genmove" =>
+            {
+                let colour = GTPInterface::parse_colour(args.first().ok_or_else(|| error!("syntax error"))?)?;
+                if colour != self.state.get_board().to_move()
+                {
+                    return Err(error!("wrong colour to move"));
+                }
+
+                if ! self.state.get_board().has_moves()
+                {
+                    return Err(error!("no moves"));
+                }
+
+                let tetromino = self.mcts.search_return(self.state.get_board()).map_err(|e| { log::error!("{:#}", e); error!("no searcher threads available") })?;
+                self.state.apply(& tetromino).map_err(|_| error!("engine produced an illegal move"))?;
+                self.mcts.pivot(& tetromino, self.state.get_board());
+                Ok(tetromino.notate())
+            },
+
+            "undo" =>
+            {
+                self.state.undo().map_err(|_| error!("cannot undo"))?;
+                self.mcts.prepare(self.state.get_board());
+                Ok(String::new())
+            },
+
+            "time_left" => match args.get(1).map(|seconds| seconds.parse::<f64>())
+            {
+                Some(Ok(seconds)) =>
+                {
+                    self.mcts.set_time_left((seconds * 1000.0) as usize);
+                    Ok(String::new())
+                },
+                _ => Err(error!("syntax error"))
+            },
+
+            "time_settings" =>
+            {
+                // Canadian byo-yomi is approximated by an increment of the byo-yomi time over its stones.
+
+                let settings = args.iter().take(3).map(|arg| arg.parse::<f64>()).collect::<std::result::Result<Vec<f64>, _>>();
+                match settings.as_deref()
+                {
+                    Ok(& [main, byo_yomi, stones]) =>
+                    {
+                        let increment = match stones > 0.0
+                        {
+                            true  => byo_yomi / stones,
+                            false => 0.0
+                        };
+                        // Without main time, the first move is made in its byo-yomi period.
+
+                        let main_ms = match main > 0.0 || increment > 0.0
+                        {
+                            true  => Some((main.max(increment) * 1000.0) as usize),
+                            false => None
+                        };
+
+                        self.mcts.set_time_settings(main_ms, (increment * 1000.0) as usize);
+                        Ok(String::new())
+                    },
+                    _ => Err(error!("syntax error"))
+                }
+            },
+
+            "final_score" =>
+            {
+                // The margin is in uncovered tiles; a finished game is never drawn, since a tie 
+                // goes to the last player to move, so only an unfinished level game reports 0.
+
+                let board = self.state.get_board();
+                let margin = board.margin().abs();
+                match board.result()
+                {
+                    Outcome::X(_) => Ok(format!("B+{}", margin)),
+                    Outcome::O(_) => Ok(format!("W+{}", margin)),
+                    _             => match board.margin()
+                    {
+                        m if m > 0 => Ok(format!("B+{}", m)),
+                        m if m < 0 => Ok(format!("W+{}", - m)),
+                        _          => Ok("0".to_owned())
+                    }
+                }
+            },
+
+            "showboard" =>
+            {
+                let board = self.state.get_board();
+                Ok(format!("\n{}{}", board, board.notate()))
+            },
+
+            _ => Err(error!("unknown command"))
+        }
+    }
+
+    ///
+    /// Creates a new GTP interface, which consults the configured opening book, if any,
+    /// before searching.
+    ///
+    pub fn new (config: & Config) -> Result<GTPInterface>
+    {
+        let mut mcts = MCTS::new(config.clone())?;
+        mcts.set_book(Book::open(& config.book)?);
+        Ok(GTPInterface { mcts, state: Game::new() })
+    }
+
+    ///
+    /// Parses a GTP colour into the corresponding player; black is X and white is O.
+    ///
+    fn parse_colour (s: & str) -> Result<Player>
+    {
+        match s.to_lowercase().as_str()
+        {
+            "b" | "black" | "x" => Ok(Player::X),
+            "w" | "white" | "o" => Ok(Player::O),
+            _                   => Err(error!("invalid color"))
+        }
+    }
+
+    ///
+    /// Strips a raw command line as GTP requires: comments are removed, tabs become spaces,
+    /// and other control characters are dropped.
+    ///
+    fn preprocess (line: & str) -> String
+    {
+        let line = line.split('#').next().unwrap_or("");
+        line.chars()
+            .filter_map(|c| match c
+            {
+                '\t'                => Some(' '),
+                c if c.is_control() => None,
+                c                   => Some(c)
+            })
+            .collect()
+    }
+
+    ///
+    /// Runs the main loop until the controller quits or the input ends.
+    ///
+    pub fn run_loop (& mut self)
+    {
+        log::info!("GTP controller");
+
+        let mut cmdline = String::new();
+        loop
+        {
+            cmdline.clear();
+            match std::io::stdin().read_line(& mut cmdline)
+            {
+                Ok(0) | Err(_) => break,
+                Ok(_)          => {}
+            };
+
+            let line = GTPInterface::preprocess(& cmdline);
+            let mut args : Vec<& str> = line.split_whitespace().collect();
+
+            // A command may be prefixed by a numeric id, which is echoed in the response.
+
+            let id = match args.first().and_then(|arg| arg.parse::<u32>().ok())
+            {
+                Some(id) =>
+                {
+                    args.remove(0);
+                    id.to_string()
+                },
+                None     => String::new()
+            };
+
+            let cmd = match args.first()
+            {
+                Some(cmd) => cmd.to_owned(),
+                None      => continue
+            };
+
+            log::info!("Received command: {} {:?}", cmd, args);
+
+            let response = match self.execute(cmd, & args[1 ..])
+            {
+                Ok(payload) => format!("={} {}", id, payload),
+                Err(e)      => format!("?{} {}", id, e)
+            };
+            log::info!("Sent '{}'.", response);
+            println!("{}\n", response.trim_end());
+
+            if cmd == "quit"
+            {
+                self.mcts.stop_early();
+                break;
+            }
+        }
+    }
+}
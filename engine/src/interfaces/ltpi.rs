@@ -1,24 +1,75 @@
 
 use crate::config::*;
+use crate::mcts::book::Book;
 use crate::mcts::mcts::MCTS;
+use crate::mcts::strength::Strength;
 
-use lits::{Game, Tetromino};
+use lits::{Board, Game, Tetromino};
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver};
+use std::thread;
 
 use utils::error::*;
 use utils::log;
 use utils::notate::Notate;
 
+///
+/// The version of the LITS text protocol spoken by this interface.
+///
+pub const PROTOCOL_VERSION : u32 = 1;
+
+///
+/// The commands supported by this interface, reported on initialization.
+///
+pub const CAPABILITIES : [& str; 21] = 
+[
+    "analyze-board", "analyze-moves", "cancel-search", "gen-move", "gen-move-eval", "gen-move-fast", "initialize", 
+    "new-game", "play-move", "ponder", "ponderhit", "search-info", "set-strength", "setup-position", 
+    "setup-position-named", "show-board", "shutdown", "stop-ponder", "time-left", "time-settings", "undo-move"
+];
+
 ///
 /// Runs the main loop and interfaces with a controller program.
 ///
 pub struct LTPInterface 
 {
     mcts: MCTS,
-    state: Game
+    cancel: Arc<AtomicBool>,
+    config: MCTSConfig,
+    state: Game,
+    pondered: Option<Tetromino>,
+    background: Option<Board>
 }
 
 impl LTPInterface
 {
+    ///
+    /// Evaluates each position in the current game's history, from the base board up to
+    /// the current position, and returns the evaluations in X's perspective.
+    ///
+    pub fn analyze_game (& mut self) -> Result<Vec<f32>>
+    {
+        let context = "Failed to analyze the game.";
+
+        let mut board = self.state.get_board_base().clone();
+        let mut values = Vec::new();
+
+        for tetromino in self.state.get_history().iter().map(Some).chain([None])
+        {
+            let (_, value) = self.mcts.policy().predict(& board);
+            values.push(value * board.to_move().value() as f32);
+
+            if let Some(tetromino) = tetromino 
+            {
+                board.place_tetromino(tetromino).context(context)?;
+            }
+        }
+
+        Ok(values)
+    }
+
     ///
     /// Halts this engine.
     ///
@@ -28,35 +79,67 @@ impl LTPInterface
     }
 
     ///
-    /// Creates a new LTP interface.
+    /// Creates a new LTP interface, which consults the configured opening book, if any, 
+    /// before searching.
     ///
     pub fn new (config: & Config) -> Result<LTPInterface>
     {
-        let mcts = MCTS::new(config.clone())?;
-        Ok(LTPInterface { mcts, state: Game::new() })
+        let mut mcts = MCTS::new(config.clone())?;
+        mcts.set_book(Book::open(& config.book)?);
+        Ok(LTPInterface { cancel: mcts.cancel_flag(), mcts, config: config.mcts, state: Game::new(), pondered: None, background: None })
     }
 
     ///
-    /// Runs the main loop.
+    /// Runs the main loop, until the controller shuts the engine down or closes its input.
     ///
     pub fn run_loop (& mut self) 
     {
         log::info!("LTPI controller");
 
-        let mut cmdline = String::new();
+        let commands = LTPInterface::spawn_reader(self.cancel.clone());
         loop 
         {
-            cmdline.clear();
-            std::io::stdin().read_line(& mut cmdline).ok().unwrap();
+            let cmdline = match commands.recv()
+            {
+                Ok(cmdline) => cmdline,
+                Err(_)      => 
+                {
+                    self.halt();
+                    break;
+                }
+            };
             let mut args : Vec<& str> = cmdline.split_whitespace().collect();
+
+            // Each command is prefixed by its id, which is echoed in the response so that 
+            // the controller can match responses to requests.
+
+            let mut id = 0;
             if args.len() > 0
             {
+                id = args[0].parse::<u32>().unwrap_or(0);
                 args.drain(0 ..= 0);
             }
             let cmd  : & str = args.first().unwrap_or(& "");
 
             log::info!("Received command: {} {:?}", cmd, args);
 
+            // Any command other than a ponder command abandons an ongoing ponder, so that
+            // the engine never searches and modifies its state at the same time; a ponder
+            // on the replies to the engine's move is only abandoned by play-move once the 
+            // game leaves the pondered position.
+
+            let keep = match cmd 
+            {
+                "ponderhit" | "stop-ponder" => self.pondered.is_some(),
+                "play-move"                 => self.background.is_some(),
+                _                           => false
+            };
+
+            if (self.pondered.is_some() || self.background.is_some()) && ! keep 
+            {
+                self.stop_ponder();
+            }
+
             match cmd 
             {
                 "" => continue,
@@ -64,6 +147,10 @@ impl LTPInterface
                 "initialize" => 
                 {
                     log::info!("LTP startup");
+
+                    let response = LTPInterface::initialize_response(id);
+                    log::info!("Sent '{}'.", response);
+                    println!("{}\n", response);
                 }
                 
                 "shutdown"   => 
@@ -80,6 +167,16 @@ impl LTPInterface
                         Err(e) => log::error!("{}", e)
                     };
                 },
+
+                "setup-position-named" => 
+                {
+                    let name = args.get(1).map_or("", |name| name);
+                    match lits::setups::named(name).and_then(|board| Game::from_moves(& board, ""))
+                    {
+                        Ok(new_game) => { self.state = new_game },
+                        Err(e) => log::error!("{:#}", e)
+                    };
+                },
                 
                 "new-game" => 
                 {
@@ -88,37 +185,257 @@ impl LTPInterface
 
                 "play-move" => 
                 {
-                    match Tetromino::parse(& args[1])
+                    // The controller learns which move was rejected, if any, so that it can 
+                    // bring its own game back in line with the engine's.
+
+                    let played = self.play_moves(& args[1 ..]);
+                    let response = match played == args.len() - 1
                     {
-                        Ok(tetromino) => 
-                        {
-                            match self.state.apply(& tetromino)
-                            {
-                                Ok(()) => {},
-                                Err(e) => log::error!("{}", e)
-                            }
-                        },
-                        Err(e) => log::error!("{}", e)
+                        true  => format!("= {}", id),
+                        false => format!("? {} illegal move {}", id, played)
                     };
+                    log::info!("Sent '{}'.", response);
+                    println!("{}\n", response);
                 },
 
                 "undo-move" => 
                 {
+                    // The trees only hold the positions after their roots, so an undo usually 
+                    // starts them afresh; they are kept if they already hold the position.
+
                     match self.state.undo()
                     {
-                        Ok(()) => {},
+                        Ok(()) => self.mcts.prepare(self.state.get_board()),
                         Err(e) => log::error!("{}", e)
                     };
                 },
 
+                "analyze-board" => 
+                {
+                    let response = match self.analyze_game()
+                    {
+                        Ok(values) => format!("= {} {}", id, values.iter().map(|value| value.to_string()).collect::<Vec<String>>().join(" ")),
+                        Err(e)     => 
+                        {
+                            log::error!("{:#}", e);
+                            format!("? {} cannot analyze", id)
+                        }
+                    };
+                    log::info!("Sent '{}'.", response);
+                    println!("{}\n", response);
+                },
+
+                // Each candidate is a tagged group of fields, so that the variations, whose 
+                // lengths differ, can share one line.
+
+                "analyze-moves" => 
+                {
+                    let n = args.get(1).map_or(Ok(1), |n| n.parse::<usize>());
+                    let candidates = match n 
+                    {
+                        Ok(n)  => self.mcts.analyze(self.state.get_board(), n),
+                        Err(e) => Err(error!("Invalid number of moves '{}': {}", args[1], e))
+                    };
+
+                    let response = match candidates 
+                    {
+                        Ok(candidates) if candidates.is_empty() => format!("? {} no moves", id),
+                        Ok(candidates) => 
+                        {
+                            let lines = candidates.iter()
+                                .map(
+                                    |candidate| 
+                                    {
+                                        let pv = candidate.pv.iter().map(|tetromino| tetromino.notate()).collect::<Vec<String>>().join(" ");
+                                        format!("move {} visits {} prior {} eval {} pv {}", candidate.tetromino.notate(), candidate.visits, candidate.prior, candidate.eval, pv)
+                                    }
+                                )
+                                .collect::<Vec<String>>();
+                            format!("= {} {}", id, lines.join(" "))
+                        },
+                        Err(e) => 
+                        {
+                            log::error!("{:#}", e);
+                            format!("? {} cannot analyze", id)
+                        }
+                    };
+                    log::info!("Sent '{}'.", response);
+                    println!("{}\n", response);
+                },
+
+                // A search in progress was already cancelled when this command was read, and 
+                // has responded with its best move; the request is spent either way.
+
                 "cancel-search" => 
                 {
-                    self.mcts.stop_early();
+                    self.cancel.store(false, Ordering::SeqCst);
                 },
 
                 "gen-move" => 
                 {
-                    self.mcts.search(self.state.get_board(), true);
+                    match self.mcts.search(self.state.get_board(), Some(id))
+                    {
+                        Ok(()) => self.background = self.mcts.ponder_reply(self.state.get_board()),
+                        Err(e) => 
+                        {
+                            log::error!("{:#}", e);
+                            println!("? {} no searcher threads available\n", id);
+                        }
+                    };
+                },
+
+                "gen-move-eval" => 
+                {
+                    let searched = self.mcts.search(self.state.get_board(), None);
+
+                    let response = match & searched 
+                    {
+                        Err(e) => 
+                        {
+                            log::error!("{:#}", e);
+                            format!("? {} no searcher threads available", id)
+                        },
+                        Ok(()) if self.mcts.best_move().is_null() => format!("? {} no moves", id),
+                        Ok(())                                    => 
+                        {
+                            let pv = self.mcts.principal_variation().iter().map(|tetromino| tetromino.notate()).collect::<Vec<String>>().join(" ");
+                            format!("= {} {} {} pv {}", id, self.mcts.best_move().notate(), self.mcts.best_eval(), pv)
+                        }
+                    };
+                    log::info!("Sent '{}'.", response);
+                    println!("{}\n", response);
+
+                    if searched.is_ok()
+                    {
+                        self.background = self.mcts.ponder_reply(self.state.get_board());
+                    }
+                },
+
+                // The policy's move is immediate, so there is nothing to ponder on after it.
+
+                "gen-move-fast" => 
+                {
+                    self.mcts.search_policy(self.state.get_board(), Some(id));
+                },
+
+                // 'ponder on' and 'ponder off' toggle pondering on the replies to the engine's 
+                // moves; otherwise, the argument is the move to ponder on.
+
+                "ponder" if args.get(1) == Some(& "on") => 
+                {
+                    self.mcts.set_ponder(true);
+                },
+
+                "ponder" if args.get(1) == Some(& "off") => 
+                {
+                    self.mcts.set_ponder(false);
+                },
+
+                "ponder" => 
+                {
+                    match args.get(1).map(|s| Tetromino::parse(s))
+                    {
+                        Some(Ok(tetromino)) => 
+                        {
+                            let mut board = self.state.get_board().clone();
+                            match board.place_tetromino(& tetromino)
+                            {
+                                Ok(()) => 
+                                {
+                                    self.mcts.ponder(& board);
+                                    self.pondered = Some(tetromino);
+                                },
+                                Err(e) => log::error!("{}", e)
+                            }
+                        },
+                        Some(Err(e)) => log::error!("{}", e),
+                        None         => log::error!("Expected a move to ponder on.")
+                    };
+                },
+
+                "ponderhit" => 
+                {
+                    match self.pondered.take()
+                    {
+                        Some(tetromino) => 
+                        {
+                            match self.state.apply(& tetromino)
+                            {
+                                Ok(()) => 
+                                {
+                                    self.mcts.ponder_hit(Some(id));
+                                    self.background = self.mcts.ponder_reply(self.state.get_board());
+                                },
+                                Err(e) => 
+                                {
+                                    log::error!("{}", e);
+                                    self.mcts.stop_ponder();
+                                }
+                            }
+                        },
+                        None => log::error!("There is no ponder to hit.")
+                    };
+                },
+
+                "stop-ponder" => 
+                {
+                    self.stop_ponder();
+                },
+
+                "time-left" => 
+                {
+                    match args.get(1).map(|s| s.parse::<usize>())
+                    {
+                        Some(Ok(remaining_ms)) => self.mcts.set_time_left(remaining_ms),
+                        Some(Err(e))           => log::error!("Invalid time '{}': {}", args[1], e),
+                        None                   => log::error!("Expected the time left in milliseconds.")
+                    };
+                },
+
+                "search-info" => 
+                {
+                    match args.get(1)
+                    {
+                        Some(& "on")  => self.mcts.set_search_info(true),
+                        Some(& "off") => self.mcts.set_search_info(false),
+                        _             => log::error!("Expected 'on' or 'off'.")
+                    };
+                },
+
+                // Strengths are derived from the configured search, so that any level may 
+                // follow any other.
+
+                "set-strength" => 
+                {
+                    let strength = args.get(1)
+                        .ok_or_else(|| error!("Expected a strength level."))
+                        .and_then(|level| level.parse::<usize>().context(format!("Invalid strength level '{}'.", level)))
+                        .and_then(|level| Strength::from_level(level, & self.config));
+
+                    match strength 
+                    {
+                        Ok(strength) => self.mcts.set_strength(& strength),
+                        Err(e)       => log::error!("{:#}", e)
+                    };
+                },
+
+                "time-settings" => 
+                {
+                    let settings = (args.get(1).map(|s| s.parse::<usize>()), args.get(2).map(|s| s.parse::<usize>()).unwrap_or(Ok(0)));
+                    match settings 
+                    {
+                        (Some(Ok(main_ms)), Ok(increment_ms)) => 
+                        {
+                            let main_ms = match main_ms 
+                            {
+                                0       => None,
+                                main_ms => Some(main_ms)
+                            };
+                            self.mcts.set_time_settings(main_ms, increment_ms);
+                        },
+                        (None, _) => log::error!("Expected the main time in milliseconds."),
+                        _         => log::error!("Invalid time settings {:?}.", & args[1 ..])
+                    };
                 },
 
                 "show-board" => 
@@ -133,4 +450,179 @@ impl LTPInterface
             };
         }
     }
+
+    ///
+    /// Forms the response to the initialize request with the given id, of the form 
+    /// '= <id> blits-ltp <version> <capabilities>'.
+    ///
+    fn initialize_response (id: u32) -> String 
+    {
+        format!("= {} blits-ltp {} {}", id, PROTOCOL_VERSION, CAPABILITIES.join(" "))
+    }
+
+    ///
+    /// Determines whether the given position is the one being pondered on after the engine's move.
+    ///
+    fn is_pondered (& self, position: & Board) -> bool 
+    {
+        self.background.as_ref().is_some_and(|background| background.notate() == position.notate())
+    }
+
+    ///
+    /// Plays the given moves in order, stopping at the first that fails to parse or apply, 
+    /// and returns the number of moves played, which is the index of the rejected move if 
+    /// there is one. Each move that is played pivots the search trees, keeping their statistics.
+    ///
+    /// While pondering on the replies to the engine's move, the engine's move reaches the 
+    /// pondered position, and the ponder goes on; the reply after it stops the ponder, and 
+    /// the trees pivot on it, keeping what the ponder found.
+    ///
+    fn play_moves (& mut self, notations: & [& str]) -> usize 
+    {
+        for (idx, notation) in notations.iter().enumerate()
+        {
+            let result = Tetromino::parse(notation).and_then(|tetromino| self.state.apply(& tetromino).map(|()| tetromino));
+            match result 
+            {
+                Ok(tetromino) => 
+                {
+                    let position = self.state.get_board().clone();
+                    if ! self.is_pondered(& position)
+                    {
+                        self.stop_ponder();
+                        self.mcts.pivot(& tetromino, & position);
+                    }
+                },
+                Err(e)        => 
+                {
+                    log::error!("Stopped at move {} of {}: {:#}", idx, notations.len(), e);
+                    return idx;
+                }
+            };
+        }
+
+        notations.len()
+    }
+
+    ///
+    /// Reads the controller's commands on a separate thread, so that a search in progress 
+    /// can be cancelled: a cancel-search raises the given cancel flag as soon as it is read, 
+    /// and every command is then passed on to the main loop in order.
+    ///
+    fn spawn_reader (cancel: Arc<AtomicBool>) -> Receiver<String>
+    {
+        let (sender, receiver) = channel();
+
+        thread::spawn(
+            move || 
+            {
+                let mut cmdline = String::new();
+                loop 
+                {
+                    cmdline.clear();
+                    match std::io::stdin().read_line(& mut cmdline)
+                    {
+                        Ok(0) | Err(_) => break,
+                        Ok(_)          => {}
+                    };
+
+                    if cmdline.split_whitespace().take(2).any(|token| token == "cancel-search")
+                    {
+                        cancel.store(true, Ordering::SeqCst);
+                    }
+
+                    if sender.send(cmdline.clone()).is_err()
+                    {
+                        break;
+                    }
+                }
+            }
+        );
+
+        receiver
+    }
+
+    ///
+    /// Stops and discards any ongoing ponder.
+    ///
+    pub fn stop_ponder (& mut self)
+    {
+        self.mcts.stop_ponder();
+        self.pondered = None;
+        self.background = None;
+    }
+}
+
+#[cfg(test)]
+#[cfg(not(feature = "neural"))]
+mod tests 
+{
+    use super::*;
+
+    #[test]
+    fn analyze_game_evaluates_every_position ()
+    {
+        Tetromino::initialize();
+
+        let config : Config = toml::from_str("[mcts]\nnum_threads = 1\nmax_time_ms = 5").unwrap();
+        let mut ltpi = LTPInterface::new(& config).unwrap();
+
+        let mut boards = vec![ltpi.state.get_board().clone()];
+        for _ in 0 .. 3 
+        {
+            let tetromino = ltpi.state.get_board().enumerate_moves().into_iter().next().unwrap();
+            ltpi.state.apply(& tetromino).unwrap();
+            boards.push(ltpi.state.get_board().clone());
+        }
+
+        // Each eval is the policy's value of the position, turned to X's perspective.
+
+        let values = ltpi.analyze_game().unwrap();
+        let expected = boards.iter()
+            .map(|board| ltpi.mcts.policy().predict(board).1 * board.to_move().value() as f32)
+            .collect::<Vec<f32>>();
+        assert_eq!(values, expected);
+    }
+
+    #[test]
+    fn play_moves_plays_several_moves_in_order ()
+    {
+        Tetromino::initialize();
+
+        let config : Config = toml::from_str("[mcts]\nnum_threads = 1\nmax_time_ms = 5").unwrap();
+        let mut ltpi = LTPInterface::new(& config).unwrap();
+
+        let mut board = ltpi.state.get_board().clone();
+        let mut notations = Vec::new();
+        for _ in 0 .. 3 
+        {
+            let tetromino = board.enumerate_moves().into_iter().next().unwrap();
+            board.place_tetromino(& tetromino).unwrap();
+            notations.push(tetromino.notate());
+        }
+        let notations = notations.iter().map(String::as_str).collect::<Vec<& str>>();
+
+        assert_eq!(ltpi.play_moves(& notations), 3);
+        assert_eq!(ltpi.state.get_history().len(), 3);
+        assert_eq!(ltpi.state.get_board().notate(), board.notate());
+
+        // A move that cannot be played stops the sequence, and its index is reported.
+
+        let tetromino = board.enumerate_moves().into_iter().next().unwrap().notate();
+        assert_eq!(ltpi.play_moves(& [tetromino.as_str(), notations[0], tetromino.as_str()]), 1);
+        assert_eq!(ltpi.state.get_history().len(), 4);
+    }
+
+    #[test]
+    fn initialize_reports_the_version_and_commands ()
+    {
+        let response = LTPInterface::initialize_response(7);
+        let tokens = response.split_whitespace().collect::<Vec<& str>>();
+        assert_eq!(tokens[.. 4], ["=", "7", "blits-ltp", & PROTOCOL_VERSION.to_string()]);
+
+        for command in ["initialize", "new-game", "play-move", "undo-move", "gen-move", "setup-position", "shutdown"]
+        {
+            assert!(tokens[4 ..].contains(& command), "{}", command);
+        }
+    }
 }
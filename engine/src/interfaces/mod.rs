@@ -1,3 +1,5 @@
 
+pub mod cli;
+pub mod gtp;
 pub mod ltpi;
 pub mod selfplay;
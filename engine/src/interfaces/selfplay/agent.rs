@@ -1,6 +1,8 @@
 
 use crate::config::*;
 use crate::mcts::mcts::MCTS;
+#[cfg(feature = "neural")]
+use crate::neural::network::{Network, OptimizerState};
 
 use super::elo::Elo;
 
@@ -13,6 +15,10 @@ use utils::error::*;
 pub struct Agent 
 {
     pub mcts: MCTS,
+    #[cfg(feature = "neural")]
+    pub network: Network,
+    #[cfg(feature = "neural")]
+    pub optimizer: OptimizerState,
     pub config: Config,
     pub elo: Elo
 }
@@ -20,14 +26,22 @@ pub struct Agent
 impl Agent 
 {
     ///
-    /// Creates a new agent. Agents are always created from the template model.
+    /// Creates a new agent. Agents are always created from the template model, and sample 
+    /// their opening moves for the sake of diverse games.
     ///
     pub fn new (config: & Config) -> Result<Agent>
     {
         let mut config = config.clone();
         config.neural.use_best = false;
 
-        let mcts = MCTS::new(config.clone())?;
-        Ok(Agent { mcts, config, elo: Elo::new() })
+        let mut mcts = MCTS::new(config.clone())?;
+        mcts.set_sampling(true);
+
+        #[cfg(feature = "neural")]
+        let network = Network::from_template(& config.neural)?;
+        #[cfg(feature = "neural")]
+        let optimizer = OptimizerState { learning_rate: config.neural.learning_rate, steps: 0 };
+
+        Ok(Agent { mcts, #[cfg(feature = "neural")] network, #[cfg(feature = "neural")] optimizer, config, elo: Elo::new() })
     }
 }
@@ -0,0 +1,220 @@
+
+use crate::config::*;
+use crate::mcts::mcts::MCTS;
+
+use lits::{Board, Game, GameRecord, Outcome, Player};
+use lits::notate::Annotation;
+
+use std::path::Path;
+use std::time::Instant;
+
+use super::elo::Elo;
+use super::matchup::random_start;
+
+use utils::{Serialize, Deserialize};
+use utils::error::*;
+use utils::log;
+use utils::notate::Notate;
+
+///
+/// The z-score of a two-sided 95% confidence interval.
+///
+const Z_95 : f64 = 1.96;
+
+///
+/// One game of an arena match.
+///
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ArenaGame
+{
+    pub index: usize,
+    pub a_is_x: bool,
+    pub setup: String,
+    pub result: String,
+    pub record: Option<String>
+}
+
+///
+/// The result of an arena match between two models, A and B. The Elo delta is A's rating
+/// relative to B's, as implied by A's score, with a 95% confidence interval.
+///
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ArenaReport
+{
+    pub model_a: String,
+    pub model_b: String,
+    pub games: usize,
+    pub wins_a: usize,
+    pub wins_b: usize,
+    pub draws: usize,
+    pub win_rate_a: f64,
+    pub elo_delta: f64,
+    pub elo_low: f64,
+    pub elo_high: f64,
+    pub records: Vec<ArenaGame>
+}
+
+impl ArenaReport
+{
+    ///
+    /// Computes the score of A, its win rate, and the implied Elo delta with its confidence
+    /// interval, from the games played so far.
+    ///
+    fn conclude (& mut self)
+    {
+        let n = self.games.max(1) as f64;
+        let score = (self.wins_a as f64 + 0.5 * self.draws as f64) / n;
+
+        // The variance of a game's score about the mean score gives the standard error.
+
+        let losses = self.games - self.wins_a - self.draws;
+        let variance = (self.wins_a as f64 * (1.0 - score).powi(2) + self.draws as f64 * (0.5 - score).powi(2) + losses as f64 * score.powi(2)) / n;
+        let error = (variance / n).sqrt();
+
+        self.win_rate_a = self.wins_a as f64 / n;
+        self.elo_delta = Elo::difference_from_score(score, n);
+        self.elo_low = Elo::difference_from_score(score - Z_95 * error, n);
+        self.elo_high = Elo::difference_from_score(score + Z_95 * error, n);
+    }
+}
+
+///
+/// Creates the engine of one side of the arena, which searches with the given model artifact.
+///
+fn engine (config: & Config, artifact: & str) -> Result<MCTS>
+{
+    let mut config = config.clone();
+    config.neural.best = artifact.to_owned();
+    config.neural.use_best = true;
+
+    MCTS::new(config).context(format!("Failed to create the engine for model '{}'.", artifact))
+}
+
+///
+/// Plays a game between the two engines from the given position to its end, and returns
+/// its record, annotated with the time and evaluation of every move, and its outcome.
+///
+fn play_record (x: & mut MCTS, o: & mut MCTS, start: & Board) -> Result<(GameRecord, Outcome)>
+{
+    let mut game = Game::from_record(& GameRecord::new(start))?;
+    let mut annotations = Vec::new();
+
+    while game.get_board().has_moves()
+    {
+        let engine = match game.get_board().to_move()
+        {
+            Player::X => & mut * x,
+            _         => & mut * o
+        };
+
+        let clock = Instant::now();
+        let tetromino = engine.search_return(game.get_board())?;
+        game.apply(& tetromino).context("The engine produced an illegal move.")?;
+
+        annotations.push(Annotation { time_ms: Some(clock.elapsed().as_millis() as u64), eval: Some(engine.best_eval()), comment: None });
+    }
+
+    let mut record = game.to_record();
+    for (ply, annotation) in annotations.into_iter().enumerate()
+    {
+        record.annotate(ply, annotation);
+    }
+
+    Ok((record, game.get_board().result()))
+}
+
+///
+/// Plays an arena match of the given number of games between the engines of two model
+/// artifacts, and reports the result. Each pair of games shares a random setup, with the
+/// models swapping colours, so that neither benefits from a lopsided setup. If a records
+/// directory is given, the record of each game is saved to it.
+///
+pub fn run_arena (config: & Config, model_a: & str, model_b: & str, games: usize, records: Option<& Path>) -> Result<ArenaReport>
+{
+    #[cfg(not(feature = "neural"))]
+    log::warn!("Without the neural network, both models search with the static evaluator.");
+
+    let mut a = engine(config, model_a)?;
+    let mut b = engine(config, model_b)?;
+
+    if let Some(dir) = records
+    {
+        std::fs::create_dir_all(dir).context(format!("Failed to create the records directory '{}'.", dir.display()))?;
+    }
+
+    let mut report = ArenaReport
+    {
+        model_a: model_a.to_owned(),
+        model_b: model_b.to_owned(),
+        games: 0,
+        wins_a: 0,
+        wins_b: 0,
+        draws: 0,
+        win_rate_a: 0.0,
+        elo_delta: 0.0,
+        elo_low: 0.0,
+        elo_high: 0.0,
+        records: Vec::with_capacity(games)
+    };
+
+    let mut rng = config.seed;
+    let mut start = Board::blank();
+
+    for index in 0 .. games
+    {
+        let a_is_x = index % 2 == 0;
+        if a_is_x
+        {
+            start = random_start(config, & mut rng);
+        }
+
+        let (mut record, outcome) = match a_is_x
+        {
+            true  => play_record(& mut a, & mut b, & start)?,
+            false => play_record(& mut b, & mut a, & start)?
+        };
+
+        (record.x, record.o) = match a_is_x
+        {
+            true  => (model_a.to_owned(), model_b.to_owned()),
+            false => (model_b.to_owned(), model_a.to_owned())
+        };
+
+        let a_won = match outcome
+        {
+            Outcome::X(_) => Some(a_is_x),
+            Outcome::O(_) => Some(! a_is_x),
+            _             => None
+        };
+
+        match a_won
+        {
+            Some(true)  => report.wins_a += 1,
+            Some(false) => report.wins_b += 1,
+            None        => report.draws += 1
+        };
+        report.games += 1;
+
+        let path = match records
+        {
+            Some(dir) =>
+            {
+                let path = dir.join(format!("arena-{}-{:04}.txt", config.seed, index));
+                record.save(& path)?;
+                Some(path.display().to_string())
+            },
+            None      => None
+        };
+
+        log::info!("Arena game {} of {}: {}; A {} - {} B, {} drawn.", index + 1, games, record.result, report.wins_a, report.wins_b, report.draws);
+        report.records.push(ArenaGame { index, a_is_x, setup: start.notate(), result: record.result.clone(), record: path });
+    }
+
+    a.stop_early();
+    b.stop_early();
+
+    report.conclude();
+    log::info!("Arena: '{}' scored {:.1}% against '{}'; Elo delta {:+.1} [{:+.1}, {:+.1}].", model_a, 100.0 * (report.wins_a as f64 + 0.5 * report.draws as f64) / report.games.max(1) as f64, model_b, report.elo_delta, report.elo_low, report.elo_high);
+
+    Ok(report)
+}
@@ -1,10 +1,11 @@
 
 use utils::{Serialize, Deserialize};
+use utils::error::*;
 
 ///
 /// Represents a selfplay config.
 ///
-#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Config 
 {
     #[serde(default = "elo_k")]
@@ -23,7 +24,49 @@ pub struct Config
     pub rounds: usize,
 
     #[serde(default = "match_length")]
-    pub match_length: usize
+    pub match_length: usize,
+
+    #[serde(default = "resign_threshold")]
+    pub resign_threshold: f32,
+
+    #[serde(default = "resign_moves")]
+    pub resign_moves: usize,
+
+    #[serde(default = "resign_disable_fraction")]
+    pub resign_disable_fraction: f32,
+
+    #[serde(default = "start_retries")]
+    pub start_retries: usize,
+
+    #[serde(default = "temperature")]
+    pub temperature: f32,
+
+    #[serde(default = "temperature_moves")]
+    pub temperature_moves: usize,
+
+    #[serde(default = "promotion_threshold")]
+    pub promotion_threshold: f32,
+
+    #[serde(default = "concurrent_games")]
+    pub concurrent_games: usize,
+
+    #[serde(default = "replay_dir")]
+    pub replay_dir: String,
+
+    #[serde(default = "runs_dir")]
+    pub runs_dir: String,
+
+    #[serde(default = "checkpoint_every_rounds")]
+    pub checkpoint_every_rounds: usize,
+
+    #[serde(default = "setup_tiles")]
+    pub setup_tiles: usize,
+
+    #[serde(default = "setup_symmetric")]
+    pub setup_symmetric: bool,
+
+    #[serde(default = "setup_names")]
+    pub setup_names: Vec<String>
 }
 
 impl Default for Config 
@@ -37,11 +80,46 @@ impl Default for Config
             elo_bound: elo_bound(),
             num_agents: num_agents(),
             rounds: rounds(),
-            match_length: match_length()
+            match_length: match_length(),
+            resign_threshold: resign_threshold(),
+            resign_moves: resign_moves(),
+            resign_disable_fraction: resign_disable_fraction(),
+            start_retries: start_retries(),
+            temperature: temperature(),
+            temperature_moves: temperature_moves(),
+            promotion_threshold: promotion_threshold(),
+            concurrent_games: concurrent_games(),
+            replay_dir: replay_dir(),
+            runs_dir: runs_dir(),
+            checkpoint_every_rounds: checkpoint_every_rounds(),
+            setup_tiles: setup_tiles(),
+            setup_symmetric: setup_symmetric(),
+            setup_names: setup_names()
         }
     }
 }
 
+impl Config 
+{
+    ///
+    /// Checks that this configuration describes a usable tournament.
+    ///
+    pub fn validate (& self) -> Result<()>
+    {
+        if self.setup_tiles > 50 
+        {
+            return Err(error!("selfplay.setup_tiles must be at most 50, but is {}.", self.setup_tiles));
+        }
+
+        for name in & self.setup_names 
+        {
+            lits::setups::named(name).context("selfplay.setup_names must only name known setups.")?;
+        }
+
+        Ok(())
+    }
+}
+
 fn elo_k () -> f32 
 {
     20.0
@@ -71,3 +149,73 @@ fn match_length () -> usize
 {
     5
 }
+
+fn resign_threshold () -> f32 
+{
+    -0.95
+}
+
+fn resign_moves () -> usize 
+{
+    3
+}
+
+fn resign_disable_fraction () -> f32 
+{
+    0.1
+}
+
+fn start_retries () -> usize 
+{
+    16
+}
+
+fn temperature () -> f32 
+{
+    1.0
+}
+
+fn temperature_moves () -> usize 
+{
+    6
+}
+
+fn promotion_threshold () -> f32 
+{
+    0.55
+}
+
+fn concurrent_games () -> usize 
+{
+    4
+}
+
+fn replay_dir () -> String 
+{
+    "replay".to_owned()
+}
+
+fn runs_dir () -> String 
+{
+    "runs".to_owned()
+}
+
+fn checkpoint_every_rounds () -> usize 
+{
+    1
+}
+
+fn setup_tiles () -> usize 
+{
+    30
+}
+
+fn setup_symmetric () -> bool 
+{
+    true
+}
+
+fn setup_names () -> Vec<String> 
+{
+    Vec::new()
+}
@@ -21,8 +21,35 @@ lazy_static!
     static ref ELO_BOUND : RwLock<f32> = RwLock::new(0.0);
 }
 
+impl Default for Elo 
+{
+    fn default () -> Elo 
+    {
+        Elo::new()
+    }
+}
+
 impl Elo 
 {
+    ///
+    /// Returns the Elo difference implied by the given expected score over the given number of
+    /// games; the score is kept half a game away from 0 and 1 so that the difference is finite.
+    ///
+    pub fn difference_from_score (score: f64, n: f64) -> f64
+    {
+        let bound = 0.5 / n;
+        let score = score.clamp(bound, 1.0 - bound);
+        400.0 * (score / (1.0 - score)).log10()
+    }
+
+    ///
+    /// Returns an Elo with the given value, as when restoring a saved rating.
+    ///
+    pub fn from_value (val: f32) -> Elo 
+    {
+        Elo { val }
+    }
+
     ///
     /// Applies an elo configuration.
     ///
@@ -64,5 +91,13 @@ impl Elo
 
         (Elo { val: v_lhs }, Elo { val: v_rhs })
     }
+
+    ///
+    /// Returns the rating.
+    ///
+    pub fn value (& self) -> f32 
+    {
+        self.val
+    }
 }
 
@@ -0,0 +1,122 @@
+
+use crate::config::*;
+use crate::mcts::mcts::MCTS;
+
+use lits::{Board, Game, GameRecord};
+use lits::notate::Annotation;
+
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use super::matchup::random_start;
+
+use utils::error::*;
+use utils::log;
+
+///
+/// Plays the given number of selfplay games on the configured number of concurrent workers, 
+/// each of which searches with its own engine, and writes the record of each finished game 
+/// to the replay directory. Returns the number of games written.
+///
+pub fn generate_games (config: & Config, games: usize) -> Result<usize>
+{
+    let dir = Path::new(& config.selfplay.replay_dir);
+    std::fs::create_dir_all(dir).context(format!("Failed to create the replay directory '{}'.", dir.display()))?;
+
+    let workers = config.selfplay.concurrent_games.clamp(1, games.max(1));
+    let next = AtomicUsize::new(0);
+
+    log::info!("Generating {} selfplay games on {} workers into '{}'.", games, workers, dir.display());
+
+    // Engines are not sendable, so each worker creates its own on its thread.
+
+    let results = std::thread::scope(
+        |scope|
+        {
+            let handles = (0 .. workers)
+                .map(|worker| 
+                {
+                    let next = & next;
+                    scope.spawn(move || run_worker(config, worker, games, next, dir))
+                })
+                .collect::<Vec<_>>();
+
+            handles.into_iter()
+                .map(|handle| handle.join().unwrap_or_else(|_| Err(error!("A selfplay worker panicked."))))
+                .collect::<Vec<Result<usize>>>()
+        }
+    );
+
+    let mut written = 0;
+    for result in results 
+    {
+        written += result.context("Failed to generate selfplay games.")?;
+    }
+
+    log::info!("Generated {} selfplay games.", written);
+    Ok(written)
+}
+
+///
+/// Plays a game from the given position to its end, and returns its record, in which every 
+/// move is annotated with the time spent on it and the evaluation after it.
+///
+fn play_record (mcts: & mut MCTS, start: & Board) -> Result<GameRecord>
+{
+    let mut game = Game::from_record(& GameRecord::new(start))?;
+    let mut annotations = Vec::new();
+
+    while game.get_board().has_moves()
+    {
+        let clock = Instant::now();
+        let tetromino = mcts.search_return(game.get_board())?;
+        game.apply(& tetromino).context("The engine produced an illegal move.")?;
+
+        annotations.push(Annotation { time_ms: Some(clock.elapsed().as_millis() as u64), eval: Some(mcts.best_eval()), comment: None });
+    }
+
+    let mut record = game.to_record();
+    record.x = "blits".to_owned();
+    record.o = "blits".to_owned();
+    for (ply, annotation) in annotations.into_iter().enumerate()
+    {
+        record.annotate(ply, annotation);
+    }
+
+    Ok(record)
+}
+
+///
+/// Runs a worker, which plays games until the given number have been claimed by all of the 
+/// workers, and returns the number of games that it wrote.
+///
+fn run_worker (config: & Config, worker: usize, games: usize, next: & AtomicUsize, dir: & Path) -> Result<usize>
+{
+    let mut worker_config = config.clone();
+    worker_config.seed = config.seed.wrapping_add(worker as u64);
+
+    let mut mcts = MCTS::new(worker_config).context(format!("Failed to create the engine of selfplay worker {}.", worker))?;
+    mcts.set_sampling(true);
+
+    let mut rng = config.seed.wrapping_add(worker as u64);
+    let mut written = 0;
+    loop 
+    {
+        let index = next.fetch_add(1, Ordering::SeqCst);
+        if index >= games 
+        {
+            break;
+        }
+
+        let record = play_record(& mut mcts, & random_start(config, & mut rng)).context(format!("Failed to play selfplay game {}.", index))?;
+        let path = dir.join(format!("selfplay-{}-{:06}.txt", config.seed, index));
+        record.save(& path)?;
+
+        log::info!("Worker {} finished game {} of {} ({}).", worker, index + 1, games, record.result);
+        written += 1;
+    }
+
+    mcts.stop_early();
+    Ok(written)
+}
@@ -0,0 +1,162 @@
+
+use crate::config::*;
+use crate::mcts::mcts::MCTS;
+
+use lits::{Board, Outcome, Player, Transform};
+use lits::setups;
+
+use super::elo::Elo;
+
+use utils::Serialize;
+use utils::error::*;
+use utils::log;
+
+///
+/// The result of a match between two configurations, A and B. The Elo delta is A's rating 
+/// relative to B's, as implied by A's score.
+///
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct MatchResult 
+{
+    pub games: usize,
+    pub wins_a: usize,
+    pub wins_b: usize,
+    pub draws: usize,
+    pub elo_delta: f64
+}
+
+///
+/// Advances the given splitmix64 state by one step, returning its output.
+///
+pub fn next_random (state: & mut u64) -> u64
+{
+    * state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = * state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+///
+/// Plays a game to completion from the given position, and returns its outcome.
+///
+pub fn play_out (x: & mut MCTS, o: & mut MCTS, start: & Board) -> Result<Outcome>
+{
+    let mut board = start.clone();
+
+    while board.has_moves()
+    {
+        let tetromino = match board.to_move()
+        {
+            Player::X => x.search_return(& board)?,
+            _         => o.search_return(& board)?
+        };
+        board.place_tetromino(& tetromino).context("Failed to play out a match game.")?;
+    }
+
+    Ok(board.result())
+}
+
+///
+/// Returns a random starting position: one of the configured named setups, if any, and 
+/// otherwise a random setup with the configured number of scoring tiles for each player, 
+/// which are placed symmetrically about the centre of the board if so configured.
+///
+pub fn random_start (config: & Config, rng: & mut u64) -> Board
+{
+    let names = & config.selfplay.setup_names;
+    let pairing = match config.selfplay.setup_symmetric 
+    {
+        true  => Some(& Transform::IdenRot180),
+        false => None
+    };
+
+    let start = match names.is_empty()
+    {
+        true  => Board::random_setup(& mut || next_random(rng), config.selfplay.setup_tiles, pairing),
+        false => setups::named(& names[(next_random(rng) % names.len() as u64) as usize])
+    };
+
+    match start
+    {
+        Ok(board) => board,
+        Err(e)    => 
+        {
+            log::error!("{:#}", e);
+            Board::blank()
+        }
+    }
+}
+
+///
+/// Plays a match of the given number of games between two configurations, alternating
+/// colours each game; the seed decides which configuration plays X in the first game, and 
+/// the starting positions are drawn from A's selfplay configuration.
+///
+pub fn run_match (cfg_a: & Config, cfg_b: & Config, games: usize, seed: u64) -> Result<MatchResult>
+{
+    let mut a = MCTS::new(cfg_a.clone()).context("Failed to create engine A.")?;
+    let mut b = MCTS::new(cfg_b.clone()).context("Failed to create engine B.")?;
+
+    let mut result = MatchResult { games: 0, wins_a: 0, wins_b: 0, draws: 0, elo_delta: 0.0 };
+
+    let mut rng = seed;
+    for game in 0 .. games 
+    {
+        let a_is_x = (seed + game as u64) % 2 != 1;
+        let start = random_start(cfg_a, & mut rng);
+        let outcome = match a_is_x 
+        {
+            true  => play_out(& mut a, & mut b, & start)?,
+            false => play_out(& mut b, & mut a, & start)?
+        };
+
+        result.games += 1;
+
+        let a_won = match outcome 
+        {
+            Outcome::X(_) => a_is_x,
+            Outcome::O(_) => ! a_is_x,
+            _             => 
+            {
+                result.draws += 1;
+                log::info!("Match game {} of {}: drawn; A {} - {} B.", game + 1, games, result.wins_a, result.wins_b);
+                continue;
+            }
+        };
+
+        match a_won 
+        {
+            true  => result.wins_a += 1,
+            false => result.wins_b += 1
+        };
+
+        log::info!("Match game {} of {}: {} won; A {} - {} B.", game + 1, games, if a_won { "A" } else { "B" }, result.wins_a, result.wins_b);
+    }
+
+    let n = result.games.max(1) as f64;
+    result.elo_delta = Elo::difference_from_score((result.wins_a as f64 + 0.5 * result.draws as f64) / n, n);
+
+    Ok(result)
+}
+
+#[cfg(test)]
+#[cfg(not(feature = "neural"))]
+mod tests 
+{
+    use super::*;
+
+    use lits::Tetromino;
+
+    #[test]
+    fn match_plays_every_game ()
+    {
+        Tetromino::initialize();
+
+        let config : Config = toml::from_str("[mcts]\nnum_threads = 1\nmax_time_ms = 5").unwrap();
+        let result = run_match(& config, & config, 2, 0).unwrap();
+
+        assert_eq!(result.games, 2);
+        assert_eq!(result.wins_a + result.wins_b + result.draws, 2);
+    }
+}
@@ -1,6 +1,10 @@
 
 pub mod agent;
+pub mod arena;
 pub mod config;
 pub mod elo;
+pub mod generate;
+pub mod matchup;
+pub mod run;
 pub mod selfplay;
 
@@ -0,0 +1,180 @@
+
+use crate::config::*;
+#[cfg(feature = "neural")]
+use crate::neural::network::OptimizerState;
+#[cfg(feature = "neural")]
+use crate::neural::replay::ReplayPosition;
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::agent::Agent;
+
+use utils::{Serialize, Deserialize};
+use utils::error::*;
+use utils::log;
+
+///
+/// The saved state of one agent: its rating and, with networks, the state of its optimizer.
+/// Its weights are saved alongside the checkpoint.
+///
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AgentState
+{
+    pub rating: f32,
+    #[cfg(feature = "neural")]
+    pub optimizer: OptimizerState
+}
+
+///
+/// A snapshot of a self-play tournament after a round: everything needed to resume it from
+/// the following round.
+///
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Checkpoint
+{
+    pub round: usize,
+    #[serde(default)]
+    pub rng: u64,
+    pub games_played: usize,
+    pub starts: BTreeSet<u64>,
+    pub agents: Vec<AgentState>,
+    #[cfg(feature = "neural")]
+    pub replay: ReplayPosition
+}
+
+///
+/// A directory of checkpoints of one self-play training run.
+///
+/// Each checkpoint is a directory named after its round, holding the state of the tournament
+/// and the weights of each agent. The state is written last, so that a checkpoint that was
+/// interrupted is never mistaken for a complete one.
+///
+#[derive(Debug)]
+pub struct TrainingRun
+{
+    dir: PathBuf
+}
+
+impl TrainingRun
+{
+    ///
+    /// Returns the path of the weights of the given agent in the given checkpoint directory.
+    ///
+    #[cfg(feature = "neural")]
+    pub fn agent_path (dir: & Path, agent: usize) -> PathBuf
+    {
+        dir.join(format!("agent-{}.pt", agent))
+    }
+
+    ///
+    /// Returns the directory of the checkpoint of the given round.
+    ///
+    fn checkpoint_dir (& self, round: usize) -> PathBuf
+    {
+        self.dir.join(format!("round-{:04}", round))
+    }
+
+    ///
+    /// Creates a new run in the configured runs directory, named after the seed and the
+    /// current time.
+    ///
+    pub fn create (config: & Config) -> Result<TrainingRun>
+    {
+        let time = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        let dir = Path::new(& config.selfplay.runs_dir).join(format!("run-{}-{}", config.seed, time));
+        fs::create_dir_all(& dir).context(format!("Failed to create the training run '{}'.", dir.display()))?;
+
+        log::info!("Created training run '{}'.", dir.display());
+        Ok(TrainingRun { dir })
+    }
+
+    ///
+    /// Returns the directory of this run.
+    ///
+    pub fn dir (& self) -> & Path
+    {
+        & self.dir
+    }
+
+    ///
+    /// Loads the latest complete checkpoint of this run, if any, along with its directory.
+    ///
+    pub fn latest (& self) -> Result<Option<(PathBuf, Checkpoint)>>
+    {
+        let context = format!("Failed to read the training run '{}'.", self.dir.display());
+
+        let mut rounds = Vec::new();
+        for entry in fs::read_dir(& self.dir).context(context.clone())?
+        {
+            let path = entry.context(context.clone())?.path();
+            let round = path.file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_prefix("round-"))
+                .and_then(|round| round.parse::<usize>().ok());
+
+            if let Some(round) = round
+            {
+                rounds.push(round);
+            }
+        }
+        rounds.sort();
+
+        for round in rounds.into_iter().rev()
+        {
+            let dir = self.checkpoint_dir(round);
+            let state = dir.join("state.json");
+            if ! state.exists()
+            {
+                log::warn!("Skipping the incomplete checkpoint '{}'.", dir.display());
+                continue;
+            }
+
+            let contents = fs::read_to_string(& state).context(context.clone())?;
+            let checkpoint = serde_json::from_str(& contents).context(format!("Invalid checkpoint '{}'.", state.display()))?;
+            return Ok(Some((dir, checkpoint)));
+        }
+
+        Ok(None)
+    }
+
+    ///
+    /// Opens the existing run with the given name in the configured runs directory; a path
+    /// to the run may be given instead.
+    ///
+    pub fn open (config: & Config, name: & str) -> Result<TrainingRun>
+    {
+        let dir = Path::new(& config.selfplay.runs_dir).join(name);
+        match dir.is_dir()
+        {
+            true  => Ok(TrainingRun { dir }),
+            false => Err(error!("There is no training run '{}'.", dir.display()))
+        }
+    }
+
+    ///
+    /// Saves the given checkpoint, along with the weights of the given agents.
+    ///
+    pub fn save (& self, checkpoint: & Checkpoint, agents: & [Agent]) -> Result<()>
+    {
+        let dir = self.checkpoint_dir(checkpoint.round);
+        let context = format!("Failed to save the checkpoint '{}'.", dir.display());
+        fs::create_dir_all(& dir).context(context.clone())?;
+
+        #[cfg(feature = "neural")]
+        for (i, agent) in agents.iter().enumerate()
+        {
+            agent.network.save_file(& TrainingRun::agent_path(& dir, i)).context(context.clone())?;
+        }
+        #[cfg(not(feature = "neural"))]
+        let _ = agents;
+
+        let contents = serde_json::to_string_pretty(checkpoint).context(context.clone())?;
+        fs::write(dir.join("state.json"), contents).context(context)?;
+
+        log::info!("Saved checkpoint '{}'.", dir.display());
+        Ok(())
+    }
+}
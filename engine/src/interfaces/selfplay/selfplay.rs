@@ -1,22 +1,592 @@
 
 use crate::config::*;
+#[cfg(feature = "neural")]
+use crate::mcts::mcts::MCTS;
+use crate::mcts::mcts::PolicyTarget;
+#[cfg(feature = "neural")]
+use crate::neural::network::Network;
+
+use lits::{Board, Outcome, Player};
 
 use std::collections::BTreeSet;
 
 use super::agent::*;
 use super::elo::*;
+#[cfg(feature = "neural")]
+use super::matchup::play_out;
+use super::matchup::random_start;
+use super::run::*;
 
 use utils::*;
+use utils::error::error;
+use utils::notate::Notate;
 
 ///
 /// An environment in which a self-play tournament is conducted.
 ///
+/// Each round, every pair of agents plays a match, and the ratings are updated after every 
+/// decisive game. The positions of the round's games are then used to train the network of 
+/// the best-rated agent, which is promoted to the best network if it beats the incumbent.
+///
+/// The tournament is checkpointed to its training run every so many rounds, and can be 
+/// resumed from the run's latest checkpoint.
+///
 pub struct Selfplay 
 {
     config: Config,
-    agents: BTreeSet<Agent>
+    agents: Vec<Agent>,
+    run: TrainingRun,
+    round: usize,
+    rng: u64,
+    games_played: usize,
+    starts: BTreeSet<u64>,
+    memories: Vec<(Board, PolicyTarget, Outcome)>
 }
 
 impl Selfplay 
 {
+    ///
+    /// Determines whether the next game may end by resignation. A fixed fraction of
+    /// games, spread evenly over the games played, are played out to the end so that 
+    /// false-positive resignations can be detected.
+    ///
+    pub fn allows_resign (& self) -> bool 
+    {
+        let fraction = self.config.selfplay.resign_disable_fraction;
+        let before = (self.games_played as f32 * fraction).floor();
+        let after = ((self.games_played + 1) as f32 * fraction).floor();
+        before == after
+    }
+
+    ///
+    /// Returns the index of the best-rated agent.
+    ///
+    #[cfg(feature = "neural")]
+    pub fn best_agent (& self) -> usize 
+    {
+        (0 .. self.agents.len())
+            .max_by(|& a, & b| self.agents[a].elo.value().total_cmp(& self.agents[b].elo.value()))
+            .unwrap_or(0)
+    }
+
+    ///
+    /// Saves a checkpoint of the tournament after the current round to its training run.
+    ///
+    pub fn checkpoint (& mut self) -> Result<()>
+    {
+        #[cfg(feature = "neural")]
+        let replay = match self.agents.first_mut()
+        {
+            Some(agent) => agent.network.replay_position()?,
+            None        => Default::default()
+        };
+
+        let agents = self.agents.iter()
+            .map(|agent| AgentState { rating: agent.elo.value(), #[cfg(feature = "neural")] optimizer: agent.optimizer })
+            .collect();
+
+        let checkpoint = Checkpoint 
+        { 
+            round: self.round, 
+            rng: self.rng, 
+            games_played: self.games_played, 
+            starts: self.starts.clone(), 
+            agents, 
+            #[cfg(feature = "neural")] 
+            replay 
+        };
+
+        self.run.save(& checkpoint, & self.agents)
+    }
+
+    ///
+    /// Creates a tournament of the configured number of agents, each of which searches with 
+    /// its own seed, in a new training run.
+    ///
+    pub fn new (config: & Config) -> Result<Selfplay>
+    {
+        Elo::initialize(& config.selfplay);
+
+        let mut agents = Vec::with_capacity(config.selfplay.num_agents);
+        for i in 0 .. config.selfplay.num_agents 
+        {
+            let mut agent_config = config.clone();
+            agent_config.seed = config.seed.wrapping_add(i as u64);
+            agents.push(Agent::new(& agent_config).context(format!("Failed to create selfplay agent {}.", i))?);
+        }
+
+        let run = TrainingRun::create(config)?;
+        Ok(Selfplay { config: config.clone(), agents, run, round: 0, rng: config.seed, games_played: 0, starts: BTreeSet::new(), memories: Vec::new() })
+    }
+
+    ///
+    /// Samples a random starting position that has not been used in this tournament, 
+    /// resampling on a collision up to the configured number of retries; if every sample 
+    /// collides, the last one is used anyway.
+    ///
+    pub fn next_start (& mut self) -> Board 
+    {
+        let mut start = random_start(& self.config, & mut self.rng);
+
+        for _ in 0 .. self.config.selfplay.start_retries
+        {
+            if ! self.starts.contains(& start.zobrist_hash())
+            {
+                break;
+            }
+            start = random_start(& self.config, & mut self.rng);
+        }
+
+        // Named setups are expected to repeat.
+
+        let fresh = self.starts.insert(start.zobrist_hash());
+        if ! fresh && self.config.selfplay.setup_names.is_empty()
+        {
+            log::warn!("Reusing starting position '{}'.", start.notate());
+        }
+
+        start
+    }
+
+    ///
+    /// Plays a game between the two agents from the given starting position, and 
+    /// returns its outcome. A player resigns, losing the game, when the value of 
+    /// their position stays below the resign threshold for the configured number 
+    /// of consecutive moves. The positions of the game are remembered along with 
+    /// its outcome, for training.
+    ///
+    pub fn play_game (& mut self, x: & mut Agent, o: & mut Agent, start: & Board) -> Outcome 
+    {
+        let mut positions = Vec::new();
+        let outcome = self.play_positions(x, o, start, & mut positions);
+
+        self.memories.extend(positions.into_iter().map(|(board, policy)| (board, policy, outcome)));
+        outcome
+    }
+
+    ///
+    /// Plays a match of the configured length between the two agents with the given indices, 
+    /// where the first is less than the second, alternating colours each game.
+    ///
+    pub fn play_match (& mut self, a: usize, b: usize)
+    {
+        let mut agents = std::mem::take(& mut self.agents);
+        let (head, tail) = agents.split_at_mut(b);
+        let (agent_a, agent_b) = (& mut head[a], & mut tail[0]);
+
+        let games = self.config.selfplay.match_length;
+        for game in 0 .. games 
+        {
+            let start = self.next_start();
+            let a_is_x = game % 2 == 0;
+
+            let outcome = match a_is_x 
+            {
+                true  => self.play_game(agent_a, agent_b, & start),
+                false => self.play_game(agent_b, agent_a, & start)
+            };
+
+            // Ratings are only updated on decisive games, as the Elo update has no notion of a draw.
+
+            let a_won = match outcome 
+            {
+                Outcome::X(_) => a_is_x,
+                Outcome::O(_) => ! a_is_x,
+                _             => 
+                {
+                    log::info!("Agents {} and {} drew game {} of {}.", a, b, game + 1, games);
+                    continue;
+                }
+            };
+
+            (agent_a.elo, agent_b.elo) = Elo::update(& agent_a.elo, & agent_b.elo, a_won);
+            log::info!("Agent {} beat agent {} in game {} of {} ({}).", if a_won { a } else { b }, if a_won { b } else { a }, game + 1, games, outcome);
+        }
+
+        self.agents = agents;
+    }
+
+    ///
+    /// Plays a game as in `play_game`, collecting the positions in which a move was made, 
+    /// each with the visit distribution of its search.
+    ///
+    fn play_positions (& mut self, x: & mut Agent, o: & mut Agent, start: & Board, positions: & mut Vec<(Board, PolicyTarget)>) -> Outcome 
+    {
+        let allows_resign = self.allows_resign();
+        self.games_played += 1;
+
+        let threshold = self.config.selfplay.resign_threshold;
+        let patience = self.config.selfplay.resign_moves;
+
+        let mut board = start.clone();
+        let mut streaks = [0; 2];
+
+        while board.has_moves()
+        {
+            let mover = board.to_move();
+            let agent = match mover 
+            {
+                Player::X => & mut * x,
+                _         => & mut * o
+            };
+
+            let tetromino = match agent.mcts.search_return(& board)
+            {
+                Ok(tetromino) => tetromino,
+                Err(e)        => 
+                {
+                    log::error!("{:#}", e);
+                    break;
+                }
+            };
+
+            // The value is the mean eval of the most visited move, in the mover's perspective; 
+            // a move from the book has no visits, and is never grounds to resign.
+
+            let value = agent.mcts.threadpool().analysis(1).first().map_or(0.0, |candidate| candidate.eval);
+
+            streaks[mover.as_index()] = match value < threshold
+            {
+                true  => streaks[mover.as_index()] + 1,
+                false => 0
+            };
+
+            if allows_resign && streaks[mover.as_index()] >= patience
+            {
+                log::info!("Player {} resigned with value {}.", mover.notate(), value);
+                return match mover 
+                {
+                    Player::X => Outcome::O(board.score().min(0.0)),
+                    _         => Outcome::X(board.score().max(0.0))
+                };
+            }
+
+            positions.push((board.clone(), agent.mcts.policy_target()));
+            if let Err(e) = board.place_tetromino(& tetromino)
+            {
+                log::error!("{}", e);
+                break;
+            }
+        }
+
+        board.result()
+    }
+
+    ///
+    /// Plays the best agent's network against the incumbent best network, and promotes it 
+    /// if it wins at least the configured fraction of the games; without an incumbent, it 
+    /// is promoted outright.
+    ///
+    #[cfg(feature = "neural")]
+    pub fn promote (& mut self, best: usize) -> Result<bool>
+    {
+        let context = "Failed to evaluate the candidate network.";
+
+        let mut incumbent_config = self.config.clone();
+        incumbent_config.neural.use_best = true;
+
+        let mut incumbent = match MCTS::new(incumbent_config)
+        {
+            Ok(incumbent) => incumbent,
+            Err(e)        => 
+            {
+                log::warn!("There is no incumbent best network ({:#}); promoting agent {}.", e, best);
+                self.agents[best].network.save("", & self.config.neural.best).context(context)?;
+                return Ok(true);
+            }
+        };
+
+        // The candidate plays its best moves, since sampling would understate its strength.
+
+        let candidate = & mut self.agents[best];
+        candidate.mcts.set_sampling(false);
+
+        let games = self.config.selfplay.match_length;
+        let mut wins = 0;
+        let mut start = Board::blank();
+        for game in 0 .. games 
+        {
+            // Each pair of games shares a setup, with the networks swapping colours.
+
+            let candidate_is_x = game % 2 == 0;
+            if candidate_is_x 
+            {
+                start = random_start(& self.config, & mut self.rng);
+            }
+
+            let outcome = match candidate_is_x 
+            {
+                true  => play_out(& mut candidate.mcts, & mut incumbent, & start),
+                false => play_out(& mut incumbent, & mut candidate.mcts, & start)
+            };
+
+            match outcome.context(context)?
+            {
+                Outcome::X(_) if candidate_is_x   => wins += 1,
+                Outcome::O(_) if ! candidate_is_x => wins += 1,
+                _                                 => {}
+            };
+        }
+        candidate.mcts.set_sampling(true);
+
+        let fraction = wins as f32 / games.max(1) as f32;
+        let promoted = fraction >= self.config.selfplay.promotion_threshold;
+        log::info!("Agent {} won {} of {} games against the best network; {}.", best, wins, games, if promoted { "promoting it" } else { "keeping the incumbent" });
+
+        if promoted 
+        {
+            // The best network lives directly in the trained directory.
+
+            candidate.network.save("", & self.config.neural.best).context(context)?;
+        }
+
+        Ok(promoted)
+    }
+
+    ///
+    /// Resumes the tournament of the given training run from its latest checkpoint, restoring 
+    /// the ratings, the agents' networks and optimizers, and the replay buffer as they were.
+    /// A run without a checkpoint starts over from the first round.
+    ///
+    pub fn resume (config: & Config, name: & str) -> Result<Selfplay>
+    {
+        let run = TrainingRun::open(config, name)?;
+        let context = format!("Failed to resume the training run '{}'.", run.dir().display());
+
+        let (dir, checkpoint) = match run.latest().context(context.clone())?
+        {
+            Some(latest) => latest,
+            None         => 
+            {
+                log::warn!("The training run '{}' has no checkpoints; starting it over.", run.dir().display());
+                let mut selfplay = Selfplay::new(config)?;
+                selfplay.run = run;
+                return Ok(selfplay);
+            }
+        };
+
+        if checkpoint.agents.len() != config.selfplay.num_agents
+        {
+            return Err(error!("The checkpoint has {} agents, but {} are configured.", checkpoint.agents.len(), config.selfplay.num_agents)).context(context);
+        }
+
+        Elo::initialize(& config.selfplay);
+
+        let mut agents = Vec::with_capacity(checkpoint.agents.len());
+        for (i, state) in checkpoint.agents.iter().enumerate()
+        {
+            let mut agent_config = config.clone();
+            agent_config.seed = config.seed.wrapping_add(i as u64);
+
+            let mut agent = Agent::new(& agent_config).context(format!("Failed to create selfplay agent {}.", i))?;
+            agent.elo = Elo::from_value(state.rating);
+
+            #[cfg(feature = "neural")]
+            {
+                agent.network = Network::from_file(& agent_config.neural, & TrainingRun::agent_path(& dir, i)).context(context.clone())?;
+                agent.mcts.set_network(& agent.network);
+                agent.optimizer = state.optimizer;
+
+                if state.optimizer.learning_rate != config.neural.learning_rate
+                {
+                    log::warn!("Agent {} was trained with learning rate {}, but {} is configured.", i, state.optimizer.learning_rate, config.neural.learning_rate);
+                }
+            }
+
+            agents.push(agent);
+        }
+
+        // Memories remembered after the checkpoint belong to a round that will be replayed.
+
+        #[cfg(feature = "neural")]
+        if let Some(agent) = agents.first_mut()
+        {
+            agent.network.rewind_replay(& checkpoint.replay).context(context.clone())?;
+        }
+
+        log::info!("Resumed training run '{}' after round {}.", dir.display(), checkpoint.round);
+        Ok(Selfplay 
+        { 
+            config: config.clone(), 
+            agents, 
+            run, 
+            round: checkpoint.round, 
+            rng: checkpoint.rng, 
+            games_played: checkpoint.games_played, 
+            starts: checkpoint.starts, 
+            memories: Vec::new() 
+        })
+    }
+
+    ///
+    /// Runs the remaining rounds of the configured number of rounds of the tournament, saving
+    /// a checkpoint every so many rounds and after the last.
+    ///
+    pub fn run (& mut self) -> Result<()>
+    {
+        let (rounds, every) = (self.config.selfplay.rounds, self.config.selfplay.checkpoint_every_rounds);
+        for round in self.round + 1 ..= rounds 
+        {
+            self.run_round(round)?;
+            self.round = round;
+
+            if (every > 0 && round % every == 0) || round == rounds
+            {
+                self.checkpoint()?;
+            }
+        }
+        Ok(())
+    }
+
+    ///
+    /// Runs one round of the tournament: every pair of agents plays a match, and then the 
+    /// best-rated agent trains on the round's games.
+    ///
+    pub fn run_round (& mut self, round: usize) -> Result<()>
+    {
+        log::info!("Starting selfplay round {} of {}.", round, self.config.selfplay.rounds);
+
+        for a in 0 .. self.agents.len()
+        {
+            for b in a + 1 .. self.agents.len()
+            {
+                self.play_match(a, b);
+            }
+        }
+
+        let standings = self.agents.iter()
+            .enumerate()
+            .map(|(i, agent)| format!("{}: {:.1}", i, agent.elo.value()))
+            .collect::<Vec<String>>();
+        log::info!("Ratings after round {}: {}.", round, standings.join(", "));
+
+        #[cfg(feature = "neural")]
+        self.train_best(round)?;
+
+        self.memories.clear();
+        Ok(())
+    }
+
+    ///
+    /// Trains the best-rated agent's network on the memories of this round, saves it, and 
+    /// offers it for promotion.
+    ///
+    #[cfg(feature = "neural")]
+    pub fn train_best (& mut self, round: usize) -> Result<()>
+    {
+        let best = self.best_agent();
+        log::info!("Training agent {} on {} positions.", best, self.memories.len());
+
+        let agent = & mut self.agents[best];
+        for (board, policy, outcome) in & self.memories 
+        {
+            agent.network.remember(board, policy, outcome).context("Failed to remember a selfplay position.")?;
+        }
+
+        let group = format!("selfplay-{}", best);
+        let stats = agent.mcts.train(& mut agent.network, & group);
+        agent.optimizer.steps += stats.steps;
+        log::info!("Trained agent {} on {} memories: policy loss {:.4}, value loss {:.4}.", best, stats.samples, stats.policy_loss, stats.value_loss);
+        agent.network.save(& group, & format!("round-{}.pt", round)).context("Failed to save the trained network.")?;
+
+        self.promote(best)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[cfg(not(feature = "neural"))]
+mod tests 
+{
+    use super::*;
+
+    use crate::mcts::evaluator::Evaluator;
+    use crate::mcts::evaluator::tests::Uniform;
+    use crate::mcts::mcts::MCTS;
+
+    use lits::Tetromino;
+    use lits::tetromino::TETROMINO_RANGE;
+
+    ///
+    /// An evaluator with a uniform policy that always rates the position as lost for X.
+    ///
+    #[derive(Clone, Debug)]
+    struct Hopeless;
+
+    impl Evaluator for Hopeless 
+    {
+        fn predict (& self, board: & Board) -> ([f32; TETROMINO_RANGE], f32)
+        {
+            let (policy, _) = Uniform.predict(board);
+            (policy, -(board.to_move().value() as f32))
+        }
+
+        fn copy (& self) -> Box<dyn Evaluator>
+        {
+            Box::new(self.clone())
+        }
+    }
+
+    ///
+    /// Returns a tournament with the given selfplay settings, whose run lives in the given directory.
+    ///
+    fn tournament (settings: & str, dir: & tempfile::TempDir) -> Selfplay 
+    {
+        Tetromino::initialize();
+
+        let config = format!("[mcts]\nnum_threads = 1\nmax_time_ms = 100\n[selfplay]\nnum_agents = 0\nruns_dir = {:?}\n{}", dir.path(), settings);
+        let config : Config = toml::from_str(& config).unwrap();
+        Selfplay::new(& config).unwrap()
+    }
+
+    #[test]
+    fn hopeless_player_resigns ()
+    {
+        let dir = tempfile::tempdir().unwrap();
+        let mut selfplay = tournament("resign_moves = 2\nresign_disable_fraction = 0.0", & dir);
+
+        let agent = |config: & Config| Agent { mcts: MCTS::with_evaluator(config.clone(), Box::new(Hopeless)), config: config.clone(), elo: Elo::new() };
+        let (mut x, mut o) = (agent(& selfplay.config), agent(& selfplay.config));
+
+        // Past the opening, which has too many moves to search in time, X resigns on its second 
+        // move, so only the moves before it are remembered.
+
+        let mut start = Board::blank();
+        for _ in 0 .. 2 
+        {
+            let tetromino = start.enumerate_moves().into_iter().next().unwrap();
+            start.place_tetromino(& tetromino).unwrap();
+        }
+
+        let outcome = selfplay.play_game(& mut x, & mut o, & start);
+        assert!(matches!(outcome, Outcome::O(_)));
+        assert_eq!(selfplay.memories.len(), 2);
+    }
+
+    #[test]
+    fn colliding_start_is_resampled ()
+    {
+        let dir = tempfile::tempdir().unwrap();
+        let mut selfplay = tournament("setup_tiles = 6", & dir);
+
+        // Rewinding the generator forces the next sample to collide with the first start.
+
+        let rng = selfplay.rng;
+        let first = selfplay.next_start();
+        selfplay.rng = rng;
+        let second = selfplay.next_start();
+
+        assert_eq!(random_start(& selfplay.config, & mut rng.clone()), first);
+        assert_ne!(second, first);
+        assert_eq!(selfplay.starts.len(), 2);
+
+        // Without retries, the colliding sample is used anyway.
+
+        let mut selfplay = tournament("setup_tiles = 6\nstart_retries = 0", & dir);
+        let rng = selfplay.rng;
+        let first = selfplay.next_start();
+        selfplay.rng = rng;
+        assert_eq!(selfplay.next_start(), first);
+        assert_eq!(selfplay.starts.len(), 1);
+    }
 }
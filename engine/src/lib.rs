@@ -0,0 +1,5 @@
+
+pub mod config;
+pub mod interfaces;
+pub mod mcts;
+pub mod neural;
@@ -1,21 +1,19 @@
 
-#![feature(thread_spawn_unchecked)]
-#![feature(total_cmp)]
-
-mod config;
-mod interfaces;
-mod mcts;
-mod neural;
-
 use std::fs::OpenOptions;
-use std::io::Read;
+use std::io::{Read, Write};
 
 use clap::Parser;
 
+use engine::{config, interfaces, mcts};
+#[cfg(feature = "neural")]
+use engine::neural;
 use interfaces::*;
 use lits::{Board, Tetromino};
+use mcts::evaluator::Evaluator;
+#[cfg(feature = "neural")]
 use neural::network::Network;
 use utils::*;
+use utils::notate::Notate;
 
 ///
 /// A structure representing command line arguments.
@@ -27,32 +25,225 @@ struct CLIArgs
     mode: String,
 
     #[clap(short, long, default_value = "/home/rsarvaria/Development/projects/blits/env/engine.toml")]
-    config: String
+    config: String,
+
+    #[clap(long)]
+    config_b: Option<String>,
+
+    #[clap(long)]
+    json: bool,
+
+    #[clap(long)]
+    position: Option<String>,
+
+    #[clap(long, default_value = "5")]
+    lines: usize,
+
+    #[clap(long)]
+    records: Option<String>,
+
+    #[clap(long)]
+    games: Option<usize>,
+
+    #[clap(long)]
+    resume: Option<String>,
+
+    #[clap(long)]
+    model_a: Option<String>,
+
+    #[clap(long)]
+    model_b: Option<String>
 }
 
-fn main () -> Result<()>
+///
+/// A line of the analysis of a position: a candidate move and its eval in X's perspective.
+///
+#[derive(Debug, Serialize)]
+struct Analysis 
 {
-    let args = CLIArgs::parse();
+    #[serde(rename = "move")]
+    tetromino: String,
+    #[serde(with = "mcts::threadpool::eval_format")]
+    eval: f32,
+    visits: f32
+}
+
+///
+/// The result of the sanity check, which evaluates the blank board with the best network.
+///
+#[cfg_attr(not(feature = "neural"), allow(dead_code))]
+#[derive(Debug, Serialize)]
+struct SanityCheck 
+{
+    policy: Vec<f32>,
+    value: f32
+}
+
+///
+/// Searches the position given on the command line, or otherwise the blank board, and reports 
+/// its most visited moves with their evals.
+///
+fn analyze (config: & config::Config, args: & CLIArgs) -> Result<()>
+{
+    let position = match args.position.as_ref()
+    {
+        Some(notation) => Board::parse(notation).context("Mode 'analyze' requires --position to be a board notation.")?,
+        None           => Board::blank()
+    };
+
+    let mut mcts = mcts::mcts::MCTS::new(config.clone())?;
+    let candidates = mcts.analyze(& position, args.lines);
+    mcts.threadpool().set_stop_requirement(true);
+
+    let analysis = candidates?.into_iter()
+        .map(|candidate| Analysis { tetromino: candidate.tetromino.notate(), eval: candidate.eval, visits: candidate.visits })
+        .collect::<Vec<Analysis>>();
+    report(& analysis, args.json, & mut std::io::stdout())
+}
+
+///
+/// Reports the result of a mode; as JSON on the given output if requested, for scripting, 
+/// and otherwise to the log.
+///
+fn report<T: std::fmt::Debug + Serialize> (result: & T, json: bool, out: & mut impl Write) -> Result<()>
+{
+    match json 
+    {
+        true  => writeln!(out, "{}", serde_json::to_string(result)?)?,
+        false => log::info!("{:?}", result)
+    };
+    Ok(())
+}
+
+///
+/// Plays the two models given on the command line against each other, for the given number 
+/// of games or otherwise a selfplay match's length, and reports the result.
+///
+fn arena (config: & config::Config, args: & CLIArgs) -> Result<()>
+{
+    let model_a = args.model_a.as_ref().ok_or_else(|| error::error!("Mode 'arena' requires --model-a."))?;
+    let model_b = args.model_b.as_ref().ok_or_else(|| error::error!("Mode 'arena' requires --model-b."))?;
+    let games = args.games.unwrap_or(config.selfplay.match_length);
+
+    let records = args.records.as_ref().map(std::path::Path::new);
+    let result = selfplay::arena::run_arena(config, model_a, model_b, games, records)?;
+    report(& result, args.json, & mut std::io::stdout())
+}
+
+///
+/// Builds an opening book from the game records in the given directory, and saves it to 
+/// the configured book path.
+///
+fn build_book (config: & config::Config, records: Option<& String>) -> Result<()>
+{
+    let records = records.ok_or_else(|| error::error!("Mode 'build-book' requires --records."))?;
+    let path = config.book.path.as_ref().ok_or_else(|| error::error!("Mode 'build-book' requires book.path to be configured."))?;
+
+    let book = mcts::book::Book::from_records(std::path::Path::new(records))?;
+    book.save(std::path::Path::new(path))?;
+
+    log::info!("Built opening book '{}' with {} positions from '{}'.", path, book.len(), records);
+    Ok(())
+}
 
+///
+/// Reads and validates the configuration at the given path.
+///
+fn load_config (path: & str) -> Result<config::Config>
+{
     let mut config_str = String::new();
-    OpenOptions::new().read(true).open(& args.config)?.read_to_string(& mut config_str)?;
+    OpenOptions::new().read(true).open(path)?.read_to_string(& mut config_str)?;
     let config : config::Config = toml::from_str(& config_str)?;
+    config.validate()?;
+
+    Ok(config)
+}
+
+///
+/// Plays the configuration against the one given by --config-b, for the given number of games 
+/// or otherwise a selfplay match's length, and reports the result.
+///
+fn match_configs (config: & config::Config, args: & CLIArgs) -> Result<()>
+{
+    let path_b = args.config_b.as_ref().ok_or_else(|| error::error!("Mode 'match' requires --config-b."))?;
+    let config_b = load_config(path_b).context(format!("Failed to load configuration B from '{}'.", path_b))?;
+    let games = args.games.unwrap_or(config.selfplay.match_length);
+
+    let result = selfplay::matchup::run_match(config, & config_b, games, config.seed)?;
+    report(& result, args.json, & mut std::io::stdout())
+}
+
+///
+/// Evaluates the blank board with the given evaluator, normally the best network, and 
+/// reports the prediction.
+///
+#[cfg_attr(not(feature = "neural"), allow(dead_code))]
+fn sanity_check (evaluator: & dyn Evaluator, json: bool, out: & mut impl Write) -> Result<()>
+{
+    let (policy, value) = evaluator.predict(& Board::blank());
+    report(& SanityCheck { policy: policy.to_vec(), value }, json, out)
+}
+
+fn main () -> Result<()>
+{
+    let args = CLIArgs::parse();
 
-    let _logger = log::initialize(& config.log_path, "engine", "debug");
+    let config = load_config(& args.config)?;
+
+    let _logger = log::initialize(& config.log_path, "engine", & config.log_spec);
     Tetromino::initialize();
 
     match args.mode.as_str() 
     {
+        "analyze" => 
+        {
+            analyze(& config, & args)?;
+        },
+        "arena" => 
+        {
+            arena(& config, & args)?;
+        },
+        "build-book" => 
+        {
+            build_book(& config, args.records.as_ref())?;
+        },
+        "cli" => 
+        {
+            let mut cliinterface = cli::CLIInterface::new(& config)?;
+            cliinterface.run_loop();
+        },
+        "generate" => 
+        {
+            let games = args.games.ok_or_else(|| error::error!("Mode 'generate' requires --games."))?;
+            selfplay::generate::generate_games(& config, games)?;
+        },
+        "gtp" => 
+        {
+            let mut gtpinterface = gtp::GTPInterface::new(& config)?;
+            gtpinterface.run_loop();
+        },
         "ltpi" => 
         {
             let mut ltpinterface = ltpi::LTPInterface::new(& config)?;
             ltpinterface.run_loop();
         },
+        "match" => 
+        {
+            match_configs(& config, & args)?;
+        },
+        "selfplay" => 
+        {
+            let mut selfplay = match args.resume.as_ref()
+            {
+                Some(run) => selfplay::selfplay::Selfplay::resume(& config, run)?,
+                None      => selfplay::selfplay::Selfplay::new(& config)?
+            };
+            selfplay.run()?;
+        },
+        #[cfg(feature = "neural")]
         "sanity-check" => 
         {
-            let model = Network::from_best(& config.neural)?;
-            let (policy, value) = model.predict(& Board::blank());
-            log::info!("({:?}, {:?})", policy, value);
+            sanity_check(& Network::from_best(& config.neural)?, args.json, & mut std::io::stdout())?;
         },
         _ => 
         {
@@ -62,3 +253,42 @@ fn main () -> Result<()>
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests 
+{
+    use super::*;
+    use lits::tetromino::TETROMINO_RANGE;
+
+    ///
+    /// An evaluator that predicts the same value for every position.
+    ///
+    #[derive(Clone, Debug)]
+    struct Constant;
+
+    impl Evaluator for Constant 
+    {
+        fn predict (& self, _board: & Board) -> ([f32; TETROMINO_RANGE], f32)
+        {
+            ([0.0; TETROMINO_RANGE], 0.25)
+        }
+
+        fn copy (& self) -> Box<dyn Evaluator>
+        {
+            Box::new(self.clone())
+        }
+    }
+
+    #[test]
+    fn sanity_check_emits_json ()
+    {
+        Tetromino::initialize();
+
+        let mut out = Vec::new();
+        sanity_check(& Constant, true, & mut out).unwrap();
+
+        let result : serde_json::Value = serde_json::from_slice(& out).unwrap();
+        assert_eq!(result["value"].as_f64(), Some(0.25));
+        assert_eq!(result["policy"].as_array().map(Vec::len), Some(TETROMINO_RANGE));
+    }
+}
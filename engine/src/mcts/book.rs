@@ -0,0 +1,239 @@
+
+use lits::{Board, Game, GameRecord, Tetromino};
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use utils::{Serialize, Deserialize};
+use utils::error::*;
+use utils::log;
+
+///
+/// A configuration object for the opening book.
+///
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Config 
+{
+    #[serde(default)]
+    pub path: Option<String>,
+
+    #[serde(default = "max_plies")]
+    pub max_plies: usize,
+
+    #[serde(default = "min_weight")]
+    pub min_weight: usize
+}
+
+impl Default for Config 
+{
+    fn default () -> Config 
+    {
+        Config 
+        {
+            path: None,
+            max_plies: max_plies(),
+            min_weight: min_weight()
+        }
+    }
+}
+
+///
+/// An opening book, which maps positions (by Zobrist hash) to the continuations
+/// that were played from them in a collection of games, weighted by how often
+/// each continuation was played.
+///
+/// A book serializes to JSON; the limits on its use come from the configuration
+/// it was opened with.
+///
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Book
+{
+    entries: BTreeMap<u64, Vec<(Tetromino, usize)>>,
+
+    #[serde(skip)]
+    config: Config
+}
+
+impl Book
+{
+    ///
+    /// Adds every position in the given game's history to this book.
+    ///
+    pub fn add_game (& mut self, game: & Game)
+    {
+        let mut game = game.clone();
+        let mut board = game.get_board_base().clone();
+
+        for tetromino in game.get_history()
+        {
+            let candidates = self.entries.entry(board.zobrist_hash()).or_default();
+            match candidates.iter_mut().find(|(candidate, _)| candidate == tetromino)
+            {
+                Some((_, weight)) => * weight += 1,
+                None              => candidates.push((tetromino.clone(), 1))
+            };
+
+            if board.place_tetromino(tetromino).is_err()
+            {
+                break;
+            }
+        }
+    }
+
+    ///
+    /// Creates a book from the game records in the given directory; a file that is not 
+    /// a valid game record is skipped with a warning.
+    ///
+    pub fn from_records (dir: & Path) -> Result<Book>
+    {
+        let context = format!("Failed to read game records from '{}'.", dir.display());
+
+        let mut paths = fs::read_dir(dir).context(context.clone())?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<std::io::Result<Vec<_>>>()
+            .context(context)?;
+        paths.sort();
+
+        let mut book = Book::new();
+        for path in paths.iter().filter(|path| path.is_file())
+        {
+            match GameRecord::load(path).and_then(|record| Game::from_record(& record))
+            {
+                Ok(game) => book.add_game(& game),
+                Err(e)   => log::warn!("Skipping '{}': {:#}", path.display(), e)
+            };
+        }
+
+        Ok(book)
+    }
+
+    ///
+    /// Returns the number of positions in this book.
+    ///
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len (& self) -> usize
+    {
+        self.entries.len()
+    }
+
+    ///
+    /// Returns the most common continuation from the given position, provided it
+    /// is in the book, legal and weighted at least the configured minimum, and the 
+    /// position is within the configured number of plies; ties go to the lowest 
+    /// tetromino.
+    ///
+    pub fn lookup (& self, board: & Board) -> Option<Tetromino>
+    {
+        if board.num_coloured_tiles() / 4 >= self.config.max_plies 
+        {
+            return None;
+        }
+
+        let continuations = self.entries.get(& board.zobrist_hash())?;
+
+        let mut best : Option<(& Tetromino, usize)> = None;
+        for & (ref tetromino, weight) in continuations
+        {
+            if weight < self.config.min_weight || board.validate_tetromino(tetromino).is_err()
+            {
+                continue;
+            }
+
+            let better = best.is_none_or(|(best_tetromino, best_weight)| (weight, best_tetromino) > (best_weight, tetromino));
+            if better 
+            {
+                best = Some((tetromino, weight));
+            }
+        }
+
+        best.map(|(tetromino, _)| tetromino.clone())
+    }
+
+    ///
+    /// Returns a new empty book.
+    ///
+    pub fn new () -> Book
+    {
+        Book { entries: BTreeMap::new(), config: Config::default() }
+    }
+
+    ///
+    /// Opens the book at the path in the given configuration, or returns None if no 
+    /// path is configured.
+    ///
+    pub fn open (config: & Config) -> Result<Option<Book>>
+    {
+        let path = match & config.path 
+        {
+            Some(path) => path,
+            None       => return Ok(None)
+        };
+        let context = format!("Failed to open opening book '{}'.", path);
+
+        let contents = fs::read_to_string(path).context(context.clone())?;
+        let mut book : Book = serde_json::from_str(& contents).context(context)?;
+        book.config = config.clone();
+
+        log::info!("Opened opening book '{}' with {} positions.", path, book.len());
+        Ok(Some(book))
+    }
+
+    ///
+    /// Saves this book as JSON to the given path.
+    ///
+    pub fn save (& self, path: & Path) -> Result<()>
+    {
+        let context = format!("Failed to save opening book '{}'.", path.display());
+
+        let contents = serde_json::to_string(self).context(context.clone())?;
+        fs::write(path, contents).context(context)
+    }
+}
+
+fn max_plies () -> usize 
+{
+    8
+}
+
+fn min_weight () -> usize 
+{
+    1
+}
+
+#[cfg(test)]
+mod tests 
+{
+    use super::*;
+
+    #[test]
+    fn lookup_finds_the_most_played_continuation ()
+    {
+        Tetromino::initialize();
+
+        // Both games open with the same move, and then part ways.
+
+        let blank = Board::blank();
+        let opening = blank.enumerate_moves().into_iter().next().unwrap();
+        let mut after = blank.clone();
+        after.place_tetromino(& opening).unwrap();
+        let replies = after.enumerate_moves().into_iter().take(2).collect::<Vec<Tetromino>>();
+
+        let mut book = Book::new();
+        for reply in replies.iter()
+        {
+            let mut game = Game::new();
+            game.apply(& opening).unwrap();
+            game.apply(reply).unwrap();
+            book.add_game(& game);
+        }
+
+        assert_eq!(book.len(), 2);
+        assert_eq!(book.lookup(& blank), Some(opening));
+        assert_eq!(book.lookup(& after), replies.iter().min().cloned());
+
+        let mut unknown = after.clone();
+        unknown.place_tetromino(& replies[0]).unwrap();
+        assert_eq!(book.lookup(& unknown), None);
+    }
+}
@@ -1,5 +1,6 @@
 
 use utils::{Serialize, Deserialize};
+use utils::error::*;
 
 ///
 /// A configuration object for an MCTS manager.
@@ -13,11 +14,62 @@ pub struct Config
     #[serde(default = "max_time_ms")]
     pub max_time_ms: usize,
 
+    #[serde(default = "hard_time_ms")]
+    pub hard_time_ms: usize,
+
+    #[serde(default = "max_sims")]
+    pub max_sims: usize,
+
+    #[serde(default = "max_nodes")]
+    pub max_nodes: usize,
+
     #[serde(default = "discount")]
     pub discount: f32,
 
     #[serde(default = "uct_const")]
-    pub uct_const: f32
+    pub uct_const: f32,
+
+    #[serde(default = "pw_c")]
+    pub pw_c: f32,
+
+    #[serde(default = "pw_alpha")]
+    pub pw_alpha: f32,
+
+    #[serde(default = "verbose_search")]
+    pub verbose_search: bool,
+
+    #[serde(default = "fpu_reduction")]
+    pub fpu_reduction: f32,
+
+    #[serde(default = "tiebreak_randomize")]
+    pub tiebreak_randomize: bool,
+
+    #[serde(default = "tt_size")]
+    pub tt_size: usize,
+
+    #[serde(default = "clock_ms")]
+    pub clock_ms: usize,
+
+    #[serde(default = "increment_ms")]
+    pub increment_ms: usize,
+
+    #[serde(default = "arena_capacity")]
+    pub arena_capacity: usize,
+
+    #[serde(default = "ponder")]
+    pub ponder: bool,
+
+    #[serde(default = "root_noise")]
+    pub root_noise: bool,
+
+    #[serde(default = "noise_epsilon")]
+    pub noise_epsilon: f32,
+
+    #[serde(default = "noise_alpha")]
+    pub noise_alpha: f32,
+
+    #[serde(default = "policy_only")]
+    pub policy_only: bool
 }
 
 impl Default for Config 
@@ -28,9 +80,67 @@ impl Default for Config
         {
             num_threads: num_threads(),
             max_time_ms: max_time_ms(),
+            hard_time_ms: hard_time_ms(),
+            max_sims: max_sims(),
+            max_nodes: max_nodes(),
             discount: discount(),
-            uct_const: uct_const()
+            uct_const: uct_const(),
+            pw_c: pw_c(),
+            pw_alpha: pw_alpha(),
+            verbose_search: verbose_search(),
+            fpu_reduction: fpu_reduction(),
+            tiebreak_randomize: tiebreak_randomize(),
+            tt_size: tt_size(),
+            clock_ms: clock_ms(),
+            increment_ms: increment_ms(),
+            arena_capacity: arena_capacity(),
+            ponder: ponder(),
+            root_noise: root_noise(),
+            noise_epsilon: noise_epsilon(),
+            noise_alpha: noise_alpha(),
+            policy_only: policy_only()
+        }
+    }
+}
+
+impl Config 
+{
+    ///
+    /// Checks that this configuration describes a usable search.
+    ///
+    pub fn validate (& self) -> Result<()>
+    {
+        if self.num_threads < 1 
+        {
+            return Err(error!("mcts.num_threads must be at least 1, but is {}.", self.num_threads));
+        }
+
+        if self.max_time_ms < 1 
+        {
+            return Err(error!("mcts.max_time_ms must be at least 1, but is {}.", self.max_time_ms));
+        }
+
+        if ! (self.discount > 0.0 && self.discount <= 1.0)
+        {
+            return Err(error!("mcts.discount must be in (0, 1], but is {}.", self.discount));
+        }
+
+        if self.arena_capacity < 2 
+        {
+            return Err(error!("mcts.arena_capacity must be at least 2, but is {}.", self.arena_capacity));
         }
+
+        if ! (0.0 ..= 1.0).contains(& self.noise_epsilon)
+        {
+            return Err(error!("mcts.noise_epsilon must be in [0, 1], but is {}.", self.noise_epsilon));
+        }
+
+        if self.noise_alpha.is_nan() || self.noise_alpha <= 0.0
+        {
+            return Err(error!("mcts.noise_alpha must be positive, but is {}.", self.noise_alpha));
+        }
+
+        Ok(())
     }
 }
 
@@ -44,6 +154,21 @@ fn max_time_ms () -> usize
     5000
 }
 
+fn hard_time_ms () -> usize 
+{
+    0
+}
+
+fn max_sims () -> usize 
+{
+    usize::MAX
+}
+
+fn max_nodes () -> usize 
+{
+    usize::MAX
+}
+
 fn discount () -> f32 
 {
     0.99
@@ -53,3 +178,73 @@ fn uct_const () -> f32
 {
     1.1
 }
+
+fn pw_c () -> f32 
+{
+    1.0e9
+}
+
+fn pw_alpha () -> f32 
+{
+    1.0
+}
+
+fn verbose_search () -> bool 
+{
+    false
+}
+
+fn fpu_reduction () -> f32 
+{
+    0.0
+}
+
+fn tiebreak_randomize () -> bool 
+{
+    false
+}
+
+fn tt_size () -> usize 
+{
+    1 << 16
+}
+
+fn clock_ms () -> usize 
+{
+    0
+}
+
+fn increment_ms () -> usize 
+{
+    0
+}
+
+fn arena_capacity () -> usize 
+{
+    1 << 19
+}
+
+fn ponder () -> bool 
+{
+    false
+}
+
+fn root_noise () -> bool 
+{
+    false
+}
+
+fn noise_epsilon () -> f32 
+{
+    0.25
+}
+
+fn noise_alpha () -> f32 
+{
+    0.3
+}
+
+fn policy_only () -> bool 
+{
+    false
+}
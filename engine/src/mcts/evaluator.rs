@@ -0,0 +1,74 @@
+
+use lits::Board;
+use lits::tetromino::TETROMINO_RANGE;
+
+///
+/// A policy-value oracle that a search can consult at its leaves.
+///
+/// The policy is a vector over the full tetromino range, where illegal
+/// moves are expected to be zeroed; the value is a prediction of the
+/// position's favour in the perspective of the player to move.
+///
+pub trait Evaluator : std::fmt::Debug
+{
+    ///
+    /// Given an input board, returns the policy vector and a value estimation.
+    ///
+    fn predict (& self, board: & Board) -> ([f32; TETROMINO_RANGE], f32);
+
+    ///
+    /// Creates an exact copy of this evaluator for use on another thread.
+    ///
+    fn copy (& self) -> Box<dyn Evaluator>;
+}
+
+#[cfg(test)]
+pub mod tests 
+{
+    use super::*;
+
+    use crate::config::Config;
+    use crate::mcts::mcts::MCTS;
+
+    use lits::Tetromino;
+
+    ///
+    /// An evaluator with a uniform policy over the legal moves and an even value.
+    ///
+    #[derive(Clone, Debug)]
+    pub struct Uniform;
+
+    impl Evaluator for Uniform 
+    {
+        fn predict (& self, board: & Board) -> ([f32; TETROMINO_RANGE], f32)
+        {
+            let mut policy = [0.0; TETROMINO_RANGE];
+            let moves = board.enumerate_moves();
+            for tetromino in & moves 
+            {
+                let action : usize = tetromino.clone().into();
+                policy[action] = 1.0 / moves.len() as f32;
+            }
+            (policy, 0.0)
+        }
+
+        fn copy (& self) -> Box<dyn Evaluator>
+        {
+            Box::new(self.clone())
+        }
+    }
+
+    #[test]
+    fn search_with_stub_plays_a_legal_move ()
+    {
+        Tetromino::initialize();
+
+        let config : Config = toml::from_str("[mcts]\nnum_threads = 1\nmax_time_ms = 5").unwrap();
+        let mut mcts = MCTS::with_evaluator(config, Box::new(Uniform));
+
+        let board = Board::blank();
+        let tetromino = mcts.search_return(& board).unwrap();
+        assert!(board.enumerate_moves().contains(& tetromino));
+        assert!(mcts.policy_target().iter().all(|& (id, _)| board.enumerate_moves().contains(& id.into())));
+    }
+}
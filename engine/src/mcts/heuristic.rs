@@ -0,0 +1,63 @@
+
+use lits::Board;
+use lits::tetromino::TETROMINO_RANGE;
+
+use super::evaluator::Evaluator;
+
+///
+/// A static evaluator that needs no model, for use when the engine is built without
+/// the neural network.
+///
+/// The policy is uniform over the legal moves, and the value is the board's score,
+/// which already lies within [-1, 1], from the perspective of the player to move.
+///
+#[derive(Clone, Debug, Default)]
+pub struct Heuristic;
+
+impl Evaluator for Heuristic
+{
+    fn predict (& self, board: & Board) -> ([f32; TETROMINO_RANGE], f32)
+    {
+        let mut policy = [0.0; TETROMINO_RANGE];
+
+        let moves = board.enumerate_moves();
+        let weight = 1.0 / moves.len().max(1) as f32;
+
+        for tetromino in moves 
+        {
+            let action : usize = tetromino.into();
+            policy[action] = weight;
+        }
+
+        (policy, (board.score() * board.to_move().value()) as f32)
+    }
+
+    fn copy (& self) -> Box<dyn Evaluator>
+    {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests 
+{
+    use super::*;
+
+    use crate::config::Config;
+    use crate::mcts::mcts::MCTS;
+
+    use lits::Tetromino;
+
+    #[test]
+    fn search_plays_a_legal_move ()
+    {
+        Tetromino::initialize();
+
+        let config : Config = toml::from_str("[mcts]\nnum_threads = 1\nmax_time_ms = 20").unwrap();
+        let mut mcts = MCTS::with_evaluator(config, Box::new(Heuristic));
+
+        let board = Board::blank();
+        let tetromino = mcts.search_return(& board).unwrap();
+        assert!(board.enumerate_moves().contains(& tetromino));
+    }
+}
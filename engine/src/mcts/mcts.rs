@@ -2,40 +2,110 @@
 #![allow(mutable_transmutes)]
 
 use crate::config::*;
-use crate::neural::network::Network;
+#[cfg(feature = "neural")]
+use crate::neural::network::{Network, TrainStats};
+#[cfg(feature = "neural")]
+use crate::neural::server::InferenceServer;
 
 use lits::*;
 
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+
+use super::book::Book;
+use super::evaluator::Evaluator;
+#[cfg(not(feature = "neural"))]
+use super::heuristic::Heuristic;
+use super::node::MoveID;
 use super::searcher::*;
+use super::strength::Strength;
 use super::threadpool::*;
+use super::timecontrol::TimeControl;
 
 use utils::error::*;
 use utils::log;
 use utils::notate::Notate;
 
+///
+/// A sparse policy training target, as (tetromino index, probability) pairs.
+///
+pub type PolicyTarget = Vec<(usize, f32)>;
+
 ///
 /// The manager for an MCTS search.
 ///
 #[derive(Debug)]
 pub struct MCTS 
 {
-    threadpool: ThreadPool,
-    policy: Network,
+    threadpool: Box<ThreadPool>,
+    policy: Box<dyn Evaluator>,
+    book: Option<Book>,
+    clock: TimeControl,
     config: MCTSConfig
 }
 
 impl MCTS 
 {
+    ///
+    /// Searches the given position for the configured time, without consulting the book or 
+    /// the clock, and returns up to the given number of its most visited moves, with their 
+    /// evals in X's perspective. A terminal position has no candidates.
+    ///
+    pub fn analyze (& mut self, position: & Board, n: usize) -> Result<Vec<Candidate>>
+    {
+        if self.threadpool.threads.is_empty()
+        {
+            return Err(error!("No searcher threads available.")).context(format!("Failed to analyze position '{}'.", position.notate()));
+        }
+
+        if ! position.has_moves()
+        {
+            log::warn!("Refusing to analyze terminal position '{}'.", position.notate());
+            return Ok(Vec::new());
+        }
+
+        let (soft_ms, hard_ms) = (self.config.max_time_ms, self.config.hard_time_ms.max(self.config.max_time_ms));
+        self.threadpool().move_time_ms = soft_ms;
+        self.threadpool().hard_time_ms = hard_ms;
+        self.prepare(position);
+        self.threadpool().launch(position);
+
+        let perspective = position.to_move().value() as f32;
+        let mut candidates = self.threadpool.analysis(n);
+        candidates.iter_mut().for_each(|candidate| candidate.eval *= perspective);
+
+        Ok(candidates)
+    }
+
+    ///
+    /// Gets the eval of the currently-set best move from the threadpool, in
+    /// X's perspective; solved positions evaluate to an infinity.
+    ///
+    pub fn best_eval (& self) -> f32 
+    {
+        self.threadpool.best_eval * self.threadpool.state.to_move().value() as f32
+    }
+
     ///
     /// Gets the currently-set best move from the threadpool;
     /// please make sure that this actually exists before calling 
-    /// this method.
+    /// this method. After searching a terminal position, this is 
+    /// the null move.
     ///
     pub fn best_move (& self) -> Tetromino 
     {
         self.threadpool.best_move.into()
     }
 
+    ///
+    /// Returns the flag that cancels a search in progress when raised, even from another 
+    /// thread; the search then concludes early, and reports its best move as usual.
+    ///
+    pub fn cancel_flag (& self) -> Arc<AtomicBool>
+    {
+        self.threadpool.cancel.clone()
+    }
+
     ///
     /// Returns this manager's configuration.
     ///
@@ -45,75 +115,446 @@ impl MCTS
     }
 
     ///
-    /// Creates a new MCTS manager.
+    /// Creates a new MCTS manager; if the network is configured to batch its inference, 
+    /// the searchers share an inference server. If an ensemble of artifacts is configured, 
+    /// the searchers consult their averaged predictions instead of a single network.
     ///
+    #[cfg(feature = "neural")]
     pub fn new (config: Config) -> Result<MCTS>
     {
-        let mctsconfig = config.mcts;
+        if ! config.neural.ensemble.is_empty()
+        {
+            let members = config.neural.ensemble.iter().map(|artifact| (config.neural.clone(), artifact.clone())).collect::<Vec<(NeuralConfig, String)>>();
+            let ensemble = Network::ensemble(& members).context("Failed to load the configured ensemble.")?;
+            return Ok(MCTS::with_evaluator(config, Box::new(ensemble)));
+        }
+
         let policy = match config.neural.use_best 
         {
             true  => Network::from_best(& config.neural)?,
             false => Network::from_template(& config.neural)?
         };
-        let threadpool = ThreadPool::new(& config);
 
-        let mut mcts = MCTS { config: mctsconfig, policy, threadpool };
+        Ok(MCTS::with_evaluator(config, InferenceServer::serve(policy)))
+    }
+
+    ///
+    /// Creates a new MCTS manager; without the neural network, it searches with the 
+    /// static evaluator.
+    ///
+    #[cfg(not(feature = "neural"))]
+    pub fn new (config: Config) -> Result<MCTS>
+    {
+        Ok(MCTS::with_evaluator(config, Box::new(Heuristic)))
+    }
+
+    ///
+    /// Determines whether a ponder is in progress.
+    ///
+    pub fn is_pondering (& self) -> bool 
+    {
+        self.threadpool.pondering.load(std::sync::atomic::Ordering::SeqCst)
+    }
 
-        mcts.threadpool.set_num_threads(mctsconfig.num_threads, & mcts.policy);
+    ///
+    /// Starts pondering on the given position, which is the position expected after the 
+    /// opponent's move; the search runs in the background until it is hit or stopped.
+    ///
+    pub fn ponder (& mut self, position: & Board)
+    {
+        self.prepare(position);
+        self.threadpool().ponder(position);
+    }
 
-        Ok(mcts)
+    ///
+    /// Starts pondering on the position after the best move of the last search in the given
+    /// position, if pondering is enabled, so that the opponent's replies are searched while 
+    /// they think. Returns the pondered position, if any.
+    ///
+    pub fn ponder_reply (& mut self, position: & Board) -> Option<Board>
+    {
+        if ! self.config.ponder || self.best_move().is_null()
+        {
+            return None;
+        }
+
+        let mut reply = position.clone();
+        match reply.place_tetromino(& self.best_move()).is_ok() && reply.has_moves()
+        {
+            true  => 
+            {
+                log::debug!("Pondering the replies to '{}'.", self.best_move().notate());
+                self.ponder(& reply);
+                Some(reply)
+            },
+            false => None
+        }
     }
 
     ///
-    /// Returns the policy handle, but highly unsafely.
+    /// Converts the ongoing ponder into the real search of the pondered position; if a
+    /// reply id is given, the best move is sent as the response to that request.
     ///
-    pub fn policy (& mut self) -> & mut Network 
+    pub fn ponder_hit (& mut self, reply: Option<u32>)
     {
-        & mut self.policy
+        let position = self.threadpool.state.clone();
+
+        // As in a search, the time comes from the clock, which is then debited by the time 
+        // taken since the hit.
+
+        let budget = self.clock.budget(& position);
+        log::debug!("Allocated {} millis, up to {}, to pondered position '{}'.", budget.soft_ms, budget.hard_ms, position.fingerprint());
+
+        let start = std::time::Instant::now();
+        self.threadpool().move_time_ms = budget.soft_ms;
+        self.threadpool().hard_time_ms = budget.hard_ms;
+        self.threadpool().ponder_hit(& position);
+        self.clock.debit(start.elapsed().as_millis() as usize);
+
+        if let Some(id) = reply 
+        {
+            self.respond(id);
+        }
     }
 
     ///
-    /// Remembers a state-result pair.
+    /// Returns the policy handle.
     ///
-    pub fn remember (& mut self, board: & Board, outcome: & Outcome)
+    pub fn policy (& self) -> & dyn Evaluator 
     {
-        self.policy.remember(board, outcome);
+        self.policy.as_ref()
     }
 
     ///
-    /// Starts a search on this threadpool, with the given starting position,
-    /// optimizing for the given player.
+    /// Returns the visit distribution over the root's moves in the last search, as sparse
+    /// (tetromino index, probability) pairs, for use as a policy training target. A move
+    /// played from the book, or a search without visits, gives every move it considered an
+    /// equal share.
+    ///
+    pub fn policy_target (& self) -> PolicyTarget
+    {
+        let visits = & self.threadpool.visits;
+        let total : f32 = visits.iter().map(|(_, n)| n).sum();
+
+        match total > 0.0 
+        {
+            true  => visits.iter().filter(|(_, n)| * n > 0.0).map(|& (id, n)| (id, n / total)).collect(),
+            false => visits.iter().map(|& (id, _)| (id, 1.0 / visits.len() as f32)).collect()
+        }
+    }
+
+    ///
+    /// Moves every thread's tree along the given move, which took the game to the given 
+    /// position, so that the statistics under the move are kept for the next search. A tree 
+    /// that does not hold the move is moved to the position as by `prepare`.
     ///
-    pub fn search (& mut self, position: & Board, uci: bool)
+    pub fn pivot (& mut self, tetromino: & Tetromino, position: & Board)
+    {
+        let notation = position.notate();
+        let mut stale = false;
+
+        for handle in self.threadpool().threads.iter_mut()
+        {
+            let thread : & mut Searcher = unsafe { & mut (** (* handle).get()) };
+
+            match thread.pivot(tetromino) && thread.state.notate() == notation 
+            {
+                true  => log::debug!("Searcher {} pivoted on '{}', keeping {} nodes.", thread.id, tetromino.notate(), thread.tree.len()),
+                false => stale = true
+            };
+        }
+
+        if stale 
+        {
+            self.prepare(position);
+        }
+    }
+
+    ///
+    /// Moves every thread's tree to the given position, reusing the subtree of the previous 
+    /// search if the position is in it, and starting a fresh tree otherwise. The shared 
+    /// transposition table is kept while any tree is reused, and cleared otherwise.
+    ///
+    pub fn prepare (& mut self, position: & Board)
     {
         let pool = self.threadpool();
         pool.state = position.clone();
 
+        let mut reused = false;
         for handle in pool.threads.iter_mut()
         {
             let thread : & mut Searcher = unsafe { & mut (** (* handle).get()) };
             
-            thread.clear();
-            thread.initialize(position);
+            match thread.reroot(position)
+            {
+                true  => 
+                {
+                    log::debug!("Searcher {} reused {} nodes.", thread.id, thread.tree.len());
+                    reused = true;
+                },
+                false => thread.initialize(position)
+            };
         }
 
-        pool.launch(position);
+        if ! reused 
+        {
+            pool.table.clear();
+        }
+    }
 
-        if uci 
+    ///
+    /// Gets the principal variation of the last search, which starts with the best move; 
+    /// it is empty after searching a terminal position.
+    ///
+    pub fn principal_variation (& self) -> Vec<Tetromino>
+    {
+        self.threadpool.pv.clone()
+    }
+
+    ///
+    /// Sends the best move and its principal variation as the response to the request with 
+    /// the given id, or reports that there are no moves if the best move is null.
+    ///
+    fn respond (& self, id: u32)
+    {
+        let response = match self.best_move().is_null()
+        {
+            true  => format!("? {} no moves", id),
+            false => format!("= {} {} pv {}", id, self.best_move().notate(), self.principal_variation().iter().map(|tetromino| tetromino.notate()).collect::<Vec<String>>().join(" "))
+        };
+        log::info!("Sent '{}'.", response);
+        println!("{}\n", response);
+    }
+
+    ///
+    /// Starts a search on this threadpool, with the given starting position,
+    /// optimizing for the given player; if a reply id is given, the best move 
+    /// is sent as the response to that request. Errors if the pool has no 
+    /// searcher threads, in which case nothing is sent.
+    ///
+    pub fn search (& mut self, position: & Board, reply: Option<u32>) -> Result<()>
+    {
+        if self.threadpool.threads.is_empty()
         {
-            log::info!("Sent '= 0 {}'.", self.best_move().notate());
-            println!("= 0 {}\n", self.best_move().notate());
+            return Err(error!("No searcher threads available.")).context(format!("Failed to search position '{}'.", position.notate()));
         }
+
+        let booked = self.book.as_ref().and_then(|book| book.lookup(position));
+
+        let pool = self.threadpool();
+        pool.state = position.clone();
+
+        // There is no passing in LITS, so a position without moves is terminal, and there 
+        // is nothing to search; the null move is reported instead.
+
+        if ! position.has_moves()
+        {
+            log::warn!("Refusing to search terminal position '{}'.", position.notate());
+
+            pool.best_move = Tetromino::null().into();
+            pool.best_eval = 0.0;
+            pool.visits = Vec::new();
+            pool.pv = Vec::new();
+
+            if let Some(id) = reply 
+            {
+                self.respond(id);
+            }
+            return Ok(());
+        }
+
+        // A booked move is played immediately, without a search.
+
+        if let Some(tetromino) = booked 
+        {
+            log::info!("Found book move '{}' in position '{}'.", tetromino.notate(), position.fingerprint());
+
+            pool.best_move = tetromino.clone().into();
+            pool.best_eval = 0.0;
+            pool.visits = vec![(tetromino.clone().into(), 0.0)];
+            pool.pv = vec![tetromino];
+
+            if let Some(id) = reply 
+            {
+                self.respond(id);
+            }
+            return Ok(());
+        }
+
+        // Without a tree search, the policy's move is played immediately.
+
+        if self.config.policy_only 
+        {
+            self.search_policy(position, reply);
+            return Ok(());
+        }
+
+        // The search time comes from the clock, which is then debited by the time taken.
+
+        let budget = self.clock.budget(position);
+        log::debug!("Allocated {} millis, up to {}, to position '{}'.", budget.soft_ms, budget.hard_ms, position.fingerprint());
+
+        let start = std::time::Instant::now();
+        self.threadpool().move_time_ms = budget.soft_ms;
+        self.threadpool().hard_time_ms = budget.hard_ms;
+        self.prepare(position);
+        self.threadpool().launch(position);
+        self.clock.debit(start.elapsed().as_millis() as usize);
+
+        if let Some(id) = reply 
+        {
+            self.respond(id);
+        }
+
+        Ok(())
     }
 
     ///
     /// Searches and blocks until the move is found.
     ///
-    pub fn search_return (& mut self, position: & Board) -> Tetromino
+    pub fn search_return (& mut self, position: & Board) -> Result<Tetromino>
     { 
-        self.search(position, false);
+        self.search(position, None)?;
         self.threadpool.wait_for(SearcherEvent::Finish);
-        self.best_move()
+        Ok(self.best_move())
+    }
+
+    ///
+    /// Plays the legal move that the policy rates highest in the given position, without a 
+    /// tree search; if a reply id is given, the move is sent as the response to that request.
+    /// The visit distribution is the policy over the legal moves, and the eval is the value 
+    /// of the position. A terminal position yields the null move.
+    ///
+    pub fn search_policy (& mut self, position: & Board, reply: Option<u32>)
+    {
+        let (priors, value) = self.policy.predict(position);
+
+        let moves = position.enumerate_moves().into_iter().map(|tetromino| tetromino.into()).collect::<Vec<MoveID>>();
+        let total : f32 = moves.iter().map(|& id| priors[id]).sum();
+
+        let pool = self.threadpool();
+        pool.state = position.clone();
+        pool.best_move = moves.iter().copied().max_by(|& a, & b| priors[a].total_cmp(& priors[b])).unwrap_or_else(|| Tetromino::null().into());
+        pool.best_eval = value;
+        pool.visits = match total > 0.0 
+        {
+            true  => moves.iter().map(|& id| (id, priors[id] / total)).collect(),
+            false => moves.iter().map(|& id| (id, 0.0)).collect()
+        };
+        pool.pv = match moves.is_empty()
+        {
+            true  => Vec::new(),
+            false => vec![pool.best_move.into()]
+        };
+
+        log::info!("Played policy move '{}' in position '{}'.", self.best_move().notate(), position.fingerprint());
+
+        if let Some(id) = reply 
+        {
+            self.respond(id);
+        }
+    }
+
+    ///
+    /// Sets the opening book consulted before each search; None disables the book.
+    ///
+    pub fn set_book (& mut self, book: Option<Book>)
+    {
+        self.book = book;
+    }
+
+    ///
+    /// Installs a copy of the given model as the policy on each thread.
+    ///
+    #[cfg(feature = "neural")]
+    pub fn set_network (& mut self, network: & Network)
+    {
+        self.policy = InferenceServer::serve(network.copy());
+
+        self.threadpool.threads.iter_mut()
+            .map(|handle| unsafe { & mut (** handle.get()) })
+            .for_each(
+                |thread|
+                {
+                    thread.evaluator = self.policy.copy();
+                }
+            );
+    }
+
+    ///
+    /// Enables or disables pondering on the opponent's replies after each search.
+    ///
+    pub fn set_ponder (& mut self, enabled: bool)
+    {
+        self.config.ponder = enabled;
+    }
+
+    ///
+    /// Enables or disables sampling the move by visit counts for the opening plies of a game,
+    /// as configured by the selfplay temperature, instead of always playing the best move.
+    ///
+    pub fn set_sampling (& mut self, enabled: bool)
+    {
+        self.threadpool().sampling = enabled;
+    }
+
+    ///
+    /// Enables or disables reporting the progress of each search at intervals while it runs.
+    ///
+    pub fn set_search_info (& mut self, enabled: bool)
+    {
+        self.threadpool().info = enabled;
+    }
+
+    ///
+    /// Limits the engine's search to the given strength, applying it to the clock, the pool 
+    /// and every searcher. A positive temperature samples the move on every ply.
+    ///
+    pub fn set_strength (& mut self, strength: & Strength)
+    {
+        self.config.max_time_ms = strength.max_time_ms;
+        self.config.hard_time_ms = strength.hard_time_ms;
+        self.config.max_sims = strength.max_sims;
+        self.config.root_noise = strength.root_noise;
+        self.config.policy_only = strength.policy_only;
+
+        self.clock.max_time_ms = strength.max_time_ms;
+        self.clock.hard_time_ms = strength.hard_time_ms;
+
+        let config = self.config;
+        let pool = self.threadpool();
+        pool.config.mcts = config;
+        pool.config.selfplay.temperature = strength.temperature;
+        pool.config.selfplay.temperature_moves = usize::MAX;
+        pool.sampling = strength.temperature > 0.0;
+
+        self.threadpool.threads.iter_mut()
+            .map(|handle| unsafe { & mut (** handle.get()) })
+            .for_each(
+                |thread|
+                {
+                    thread.config = config;
+                }
+            );
+
+        log::info!("Set the strength to level {}: {:?}.", strength.level, strength);
+    }
+
+    ///
+    /// Sets the time remaining on the engine's clock.
+    ///
+    pub fn set_time_left (& mut self, remaining_ms: usize)
+    {
+        self.clock.set_remaining(remaining_ms);
+    }
+
+    ///
+    /// Sets the time control of the game: the main time, of which none means an untimed game,
+    /// and the increment credited after each move.
+    ///
+    pub fn set_time_settings (& mut self, main_ms: Option<usize>, increment_ms: usize)
+    {
+        self.clock.set_settings(main_ms, increment_ms);
     }
 
     ///
@@ -124,6 +565,17 @@ impl MCTS
         self.threadpool().set_stop_requirement(true);
     }
 
+    ///
+    /// Stops an ongoing ponder, discarding its search.
+    ///
+    pub fn stop_ponder (& mut self)
+    {
+        if self.is_pondering()
+        {
+            self.threadpool().halt();
+        }
+    }
+
     ///
     /// Returns a non-exclusive-mut reference to the threadpool for use 
     ///
@@ -133,19 +585,236 @@ impl MCTS
     }
 
     ///
-    /// Trains the root model and passes it to each thread.
+    /// Trains the given model, checkpointing to the given group, and installs it as the 
+    /// policy on each thread; returns the training losses.
     ///
-    pub fn train (& mut self) 
+    #[cfg(feature = "neural")]
+    pub fn train (& mut self, network: & mut Network, group: & str) -> TrainStats
     {
-        self.policy.train();
+        let stats = network.train(group);
+        self.set_network(network);
+        stats
+    }
 
-        self.threadpool.threads.iter_mut()
-            .map(|handle| unsafe { & mut (** handle.get()) })
-            .for_each(
-                |thread|
+    ///
+    /// Creates a new MCTS manager that searches with the given evaluator.
+    ///
+    pub fn with_evaluator (config: Config, policy: Box<dyn Evaluator>) -> MCTS
+    {
+        let mctsconfig = config.mcts;
+        // The pool is boxed so that it never moves, since its searchers keep a pointer to it.
+
+        let threadpool = Box::new(ThreadPool::new(& config));
+
+        let clock = TimeControl::new(& mctsconfig);
+        let mut mcts = MCTS { config: mctsconfig, policy, book: None, clock, threadpool };
+
+        mcts.threadpool.set_num_threads(mctsconfig.num_threads, mcts.policy.as_ref());
+
+        mcts
+    }
+}
+
+#[cfg(test)]
+mod tests 
+{
+    use super::*;
+
+    use crate::mcts::evaluator::tests::Uniform;
+
+    ///
+    /// Returns a single-threaded manager that searches briefly with the uniform evaluator.
+    ///
+    fn manager () -> MCTS 
+    {
+        Tetromino::initialize();
+
+        let config : Config = toml::from_str("[mcts]\nnum_threads = 1\nmax_time_ms = 5").unwrap();
+        MCTS::with_evaluator(config, Box::new(Uniform))
+    }
+
+    #[test]
+    fn search_prefers_the_widest_win ()
+    {
+        let mut mcts = manager();
+
+        // With one L left, every move ends the game; X leads by far, and covers more of O's 
+        // tiles in the corner with some moves than others.
+
+        let rows = 
+        [
+            "5555555555",
+            "5555555555",
+            "0000000000",
+            "0000000000",
+            "0000000000",
+            "0000000000",
+            "0000000000",
+            "a000000000",
+            "a000000000",
+            "aa00000000"
+        ];
+        let board = Board::from_grid(& rows, [1, 0, 0, 0], Player::X).unwrap();
+
+        let margin = |tetromino: & Tetromino| 
+        {
+            let mut after = board.clone();
+            after.place_tetromino(tetromino).unwrap();
+            assert!(! after.has_moves());
+            after.score()
+        };
+        let margins = board.enumerate_moves().iter().map(|tetromino| (margin(tetromino) * 1.0e6) as i64).collect::<std::collections::BTreeSet<i64>>();
+        assert!(margins.len() > 1 && * margins.first().unwrap() > 0);
+
+        let tetromino = mcts.search_return(& board).unwrap();
+        assert_eq!((margin(& tetromino) * 1.0e6) as i64, * margins.last().unwrap());
+    }
+
+    #[test]
+    fn stopped_ponder_leaves_pool_idle ()
+    {
+        let mut mcts = manager();
+        let board = Board::blank();
+
+        mcts.ponder(& board);
+        assert!(mcts.is_pondering());
+        std::thread::sleep(std::time::Duration::from_millis(10));
+
+        mcts.stop_ponder();
+        assert!(! mcts.is_pondering());
+        assert!(mcts.threadpool().stop.load(std::sync::atomic::Ordering::SeqCst));
+
+        let tetromino = mcts.search_return(& board).unwrap();
+        assert!(board.enumerate_moves().contains(& tetromino));
+    }
+
+    #[test]
+    fn ponder_hit_keeps_to_the_clock ()
+    {
+        Tetromino::initialize();
+
+        let config : Config = toml::from_str("[mcts]\nnum_threads = 1\nmax_time_ms = 5000\nhard_time_ms = 10000").unwrap();
+        let mut mcts = MCTS::with_evaluator(config, Box::new(Uniform));
+        let board = Board::blank();
+
+        mcts.set_time_left(500);
+        let budget = mcts.clock.budget(& board);
+
+        mcts.ponder(& board);
+        std::thread::sleep(std::time::Duration::from_millis(20));
+
+        let start = std::time::Instant::now();
+        mcts.ponder_hit(None);
+        let elapsed = start.elapsed().as_millis() as usize;
+
+        assert!(! mcts.is_pondering());
+        assert!(budget.hard_ms < 500);
+        assert!(elapsed < 2500);
+
+        // The clock is debited by the time the hit took, which is at most the time measured here.
+
+        let remaining = mcts.clock.remaining_ms.unwrap();
+        assert!(remaining < 500 && remaining >= 500 - elapsed.min(500));
+    }
+
+    #[test]
+    #[cfg(not(feature = "neural"))]
+    fn policy_target_follows_the_visits ()
+    {
+        Tetromino::initialize();
+
+        let config : Config = toml::from_str("[mcts]\nnum_threads = 1\nmax_time_ms = 50").unwrap();
+        let mut mcts = MCTS::with_evaluator(config, Box::new(crate::mcts::heuristic::Heuristic));
+        let board = Board::blank();
+        mcts.search_return(& board).unwrap();
+
+        let target = mcts.policy_target();
+        assert!(target.len() > 1);
+        let total : f32 = target.iter().map(|(_, p)| p).sum();
+        assert!((total - 1.0).abs() < 1.0e-4);
+
+        // Each move's share is in proportion to its visits, so the most visited moves hold the most mass.
+
+        let visits = mcts.threadpool.visits.iter().cloned().collect::<std::collections::HashMap<MoveID, f32>>();
+        for (a, p) in target.iter()
+        {
+            for (b, q) in target.iter()
+            {
+                if visits[a] > visits[b]
                 {
-                    thread.network = self.policy.copy();
+                    assert!(p > q);
                 }
-            );
+            }
+        }
+
+        let (top, _) = target.iter().max_by(|(_, p), (_, q)| p.total_cmp(q)).unwrap();
+        let most = mcts.threadpool.visits.iter().map(|(_, n)| * n).fold(0.0, f32::max);
+        assert_eq!(visits[top], most);
+    }
+
+    #[test]
+    fn terminal_position_gives_the_null_move ()
+    {
+        Tetromino::initialize();
+
+        let config : Config = toml::from_str("[mcts]\nnum_threads = 1\nmax_time_ms = 5000").unwrap();
+        let mut mcts = MCTS::with_evaluator(config, Box::new(Uniform));
+        let board = Board::from_grid(& ["0000000000"; 10], [0; 4], Player::X).unwrap();
+        assert!(! board.has_moves());
+
+        let start = std::time::Instant::now();
+        mcts.search(& board, None).unwrap();
+
+        assert!(start.elapsed() < std::time::Duration::from_secs(1));
+        assert!(mcts.best_move().is_null());
+        assert!(mcts.policy_target().is_empty());
+    }
+
+    #[test]
+    fn searching_without_threads_errors ()
+    {
+        Tetromino::initialize();
+
+        let config : Config = toml::from_str("[mcts]\nnum_threads = 0\nmax_time_ms = 5").unwrap();
+        let mut mcts = MCTS::with_evaluator(config, Box::new(Uniform));
+        let board = Board::blank();
+
+        assert!(mcts.search(& board, None).is_err());
+        assert!(mcts.search_return(& board).is_err());
+        assert!(mcts.analyze(& board, 1).is_err());
+    }
+
+    #[test]
+    fn short_search_has_progress_to_report ()
+    {
+        Tetromino::initialize();
+
+        let config : Config = toml::from_str("[mcts]\nnum_threads = 1\nmax_time_ms = 5000").unwrap();
+        let mut mcts = MCTS::with_evaluator(config, Box::new(Uniform));
+        mcts.set_search_info(true);
+
+        // The search emits the line it has at each interval, once its searchers have published 
+        // a move, which takes longer in an unoptimized build.
+
+        let board = Board::blank();
+
+        mcts.ponder(& board);
+        let mut line = None;
+        for interval in 1 ..= 8 
+        {
+            std::thread::sleep(INFO_INTERVAL);
+            line = mcts.threadpool().progress_line(INFO_INTERVAL * interval);
+            if line.is_some()
+            {
+                break;
+            }
+        }
+        mcts.stop_ponder();
+
+        let line = line.unwrap();
+        let tokens = line.split_whitespace().collect::<Vec<& str>>();
+        assert_eq!(tokens[.. 4], ["=", "0", "info", "move"]);
+        assert!(board.enumerate_moves().contains(& Tetromino::parse(tokens[4]).unwrap()));
+        assert!(tokens[8].parse::<usize>().unwrap() > 0);
     }
 }
@@ -1,8 +1,15 @@
 
+pub mod book;
 pub mod config;
+pub mod evaluator;
+#[cfg(not(feature = "neural"))]
+pub mod heuristic;
 pub mod mcts;
 pub mod node;
 pub mod searcher;
+pub mod strength;
 pub mod sync;
 pub mod threadpool;
+pub mod timecontrol;
+pub mod transposition;
 
@@ -1,5 +1,5 @@
 
-use lits::{Board, Tetromino};
+use lits::Tetromino;
 
 ///
 /// An alias on usize for readability.
@@ -12,13 +12,17 @@ pub type NodeID = usize;
 pub type MoveID = usize;
 
 ///
-/// An outcome for the player in the tree scope.
+/// An outcome for the player in the tree scope, with the non-negative margin of 
+/// the score by which it was won or lost.
 ///
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Outcomes are ordered from the player's point of view: any win beats any loss,
+/// a larger win beats a smaller one, and a narrower loss beats a wider one.
+///
+#[derive(Clone, Copy, Debug)]
 pub enum Outcome 
 {
-    Win,
-    Loss
+    Win(f32),
+    Loss(f32)
 }
 
 impl std::cmp::Ord for Outcome 
@@ -27,13 +31,24 @@ impl std::cmp::Ord for Outcome
     {
         match (self, other)
         {
-            (Outcome::Win, Outcome::Loss) => std::cmp::Ordering::Greater,
-            (Outcome::Loss, Outcome::Win) => std::cmp::Ordering::Less,
-            _                             => std::cmp::Ordering::Equal
+            (Outcome::Win(_), Outcome::Loss(_)) => std::cmp::Ordering::Greater,
+            (Outcome::Loss(_), Outcome::Win(_)) => std::cmp::Ordering::Less,
+            (Outcome::Win(a), Outcome::Win(b))  => a.total_cmp(b),
+            (Outcome::Loss(a), Outcome::Loss(b)) => b.total_cmp(a)
         }
     }
 }
 
+impl std::cmp::PartialEq for Outcome 
+{
+    fn eq (& self, other: & Outcome) -> bool 
+    {
+        self.cmp(other) == std::cmp::Ordering::Equal
+    }
+}
+
+impl std::cmp::Eq for Outcome {}
+
 impl std::cmp::PartialOrd for Outcome 
 {
     fn partial_cmp(& self, other: & Outcome) -> Option<std::cmp::Ordering> 
@@ -48,11 +63,11 @@ impl std::convert::From<f32> for Outcome
     {
         if value > 0.0
         {
-            Outcome::Win 
+            Outcome::Win(value)
         }
         else 
         {
-            Outcome::Loss
+            Outcome::Loss(- value)
         }
     }
 }
@@ -60,14 +75,27 @@ impl std::convert::From<f32> for Outcome
 impl Outcome 
 {
     ///
-    /// Inverts the outcome.
+    /// Gets the margin of this outcome in the primary perspective, which is positive 
+    /// for a win and negative for a loss.
+    ///
+    pub fn margin (& self) -> f32 
+    {
+        match self 
+        {
+            Outcome::Win(margin)  => * margin,
+            Outcome::Loss(margin) => - margin
+        }
+    }
+
+    ///
+    /// Inverts the outcome, keeping its margin.
     ///
     pub fn next (& self) -> Outcome 
     {
         match self 
         {
-            Outcome::Win  => Outcome::Loss,
-            Outcome::Loss => Outcome::Win
+            Outcome::Win(margin)  => Outcome::Loss(* margin),
+            Outcome::Loss(margin) => Outcome::Win(* margin)
         }
     }
 
@@ -78,8 +106,8 @@ impl Outcome
     {
         match self 
         {
-            Outcome::Win => 1.0,
-            _            => 0.0
+            Outcome::Win(_) => 1.0,
+            _               => 0.0
         }
     }
 }
@@ -88,6 +116,7 @@ impl Outcome
 /// Represents a state in a gametree, with the corresponding in-action that lead to this state from
 /// its parent.
 ///
+#[derive(Clone)]
 pub struct Node 
 {
     pub id: NodeID,
@@ -95,13 +124,16 @@ pub struct Node
     pub oldest_child: NodeID,
     pub num_children: usize,
 
-    pub state: Board,
+    pub hash: u64,
+    pub checksum: u32,
     pub in_action: MoveID,
     pub outcome: Option<Outcome>,
 
     pub n: f32,
     pub p: f32, 
-    pub v: f32
+    pub v: f32,
+
+    pub explored: f32
 }
 
 impl Node 
@@ -139,9 +171,20 @@ impl Node
     }
 
     ///
-    /// Creates a new node representing an unvisited parent-action-child state tuple.
+    /// Determines whether this node's state has the given hash and checksum. Nodes do not 
+    /// keep their boards, so a match should be confirmed by replaying the node's path.
     ///
-    pub fn new (id: NodeID, parent: Option<NodeID>, state: & Board, outcome: Option<Outcome>, in_action: MoveID, p: f32) -> Node
+    pub fn matches (& self, hash: u64, checksum: u32) -> bool 
+    {
+        self.hash == hash && self.checksum == checksum
+    }
+
+    ///
+    /// Creates a new node representing an unvisited parent-action-child state tuple. Only 
+    /// the hash and checksum of the node's board are kept; the node's state is its in-action 
+    /// applied to its parent's state.
+    ///
+    pub fn new (id: NodeID, parent: Option<NodeID>, hash: u64, checksum: u32, outcome: Option<Outcome>, in_action: MoveID, p: f32) -> Node
     {
         Node 
         {
@@ -150,13 +193,16 @@ impl Node
             oldest_child: 0,
             num_children: 0,
 
-            state: state.clone(),
+            hash,
+            checksum,
             in_action,
             outcome,
 
             n: 0.0,
             p,
-            v: 0.0
+            v: 0.0,
+
+            explored: 0.0
         }
     }
 
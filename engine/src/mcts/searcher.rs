@@ -1,25 +1,51 @@
 
 use crate::config::*;
-use crate::neural::network::Network;
 
 use lits::{Board, Player, Tetromino};
+use lits::tetromino::TETROMINO_RANGE;
 
 use std::cell::UnsafeCell;
-use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::time::{Duration, Instant};
 
+use super::evaluator::Evaluator;
 use super::node::*;
 use super::sync::*;
 use super::threadpool::*;
+use super::transposition::{Prediction, TranspositionTable};
 
+use utils::error::*;
 use utils::log;
+use utils::notate::Notate;
 
 ///
 /// An alias on usize for readability.
 ///
 pub type TreeID = usize;
 
+///
+/// The deepest that a single selection may descend; a game lasts at most 20 plies, so 
+/// a descent this deep means the tree is malformed, for example by a cycle.
+///
+pub const MAX_DESCENT : usize = 200;
+
+///
+/// The least time between two info lines emitted by a verbose search.
+///
+pub const INFO_INTERVAL : Duration = Duration::from_millis(500);
+
+///
+/// How many simulations a searcher runs between publishing its progress.
+///
+pub const PROGRESS_INTERVAL : usize = 64;
+
+///
+/// How much worse the mean value of the most visited root move may be than the best one,
+/// for the search to count as settled.
+///
+pub const SETTLED_EPSILON : f32 = 1.0e-3;
+
 ///
 /// An enum describing the type of event to wait for.
 ///
@@ -42,6 +68,20 @@ impl std::convert::Into<bool> for SearcherEvent
     }
 }
 
+///
+/// A searcher's progress in its current search, which it publishes for the pool to report 
+/// while the search runs: the most visited move at its root, if any, with the move's visits 
+/// and mean value in the perspective of the player to move, and the simulations run so far.
+///
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Progress 
+{
+    pub best: Option<MoveID>,
+    pub visits: f32,
+    pub value: f32,
+    pub sims: usize
+}
+
 ///
 /// Gives the thread manager an unsafe view into the searcher 
 /// for synchronization purposes.
@@ -61,10 +101,11 @@ pub struct Searcher
 {
     pub pool: * mut ThreadPool,
     pub config: MCTSConfig,
-    pub network: Network,
+    pub evaluator: Box<dyn Evaluator>,
 
     pub id: TreeID,
     pub kill: AtomicBool,
+    pub epoch: AtomicUsize,
     pub search_status: Arc<Guard>,
     pub cond_variable: Arc<Latch>,
 
@@ -74,9 +115,13 @@ pub struct Searcher
     pub tree: Vec<Node>,
     pub root: NodeID,
     pub num_sims: usize,
+    pub table: Arc<TranspositionTable>,
 
     pub best_move: MoveID,
-    pub best_eval: f32
+    pub best_eval: f32,
+    pub progress: Mutex<Progress>,
+
+    pub rng: u64
 }
 
 unsafe impl Sync for Searcher {}
@@ -95,6 +140,9 @@ impl Searcher
         {
             if has_sol && self.node_immut(id).is_unsolved()
             {
+                // The worst outcome for the children is the best for this node; since outcomes 
+                // are ordered by margin, this prefers the widest win and the narrowest loss.
+
                 let mut all = true;
                 let mut worst_outcome = None;
 
@@ -112,9 +160,9 @@ impl Searcher
 
                 let node = self.node(id);
 
-                if worst_outcome == Some(Outcome::Loss)
+                if let Some(Outcome::Loss(margin)) = worst_outcome
                 {
-                    node.solve(Outcome::Win);
+                    node.solve(Outcome::Win(margin));
                     val = - node.v + (node.n + 1.0);
                 }
                 else if node.is_visited() && all 
@@ -127,6 +175,12 @@ impl Searcher
                 {
                     has_sol = false;
                 }
+
+                let node = self.node_immut(id);
+                if let Some(outcome) = node.outcome 
+                {
+                    self.table.solve(node.hash, node.checksum, outcome);
+                }
             }
 
             let node = self.node(id);
@@ -134,6 +188,11 @@ impl Searcher
             node.v += val;
             node.n += 1.0;
 
+            let (hash, checksum) = (node.hash, node.checksum);
+            self.table.update(hash, checksum, val);
+
+            let node = self.node_immut(id);
+
             if node.parent.is_none()
             {
                 break;
@@ -144,6 +203,30 @@ impl Searcher
         }
     }
 
+    ///
+    /// Mixes Dirichlet noise into the priors of the given node's children, as in AlphaZero, 
+    /// so that the search at the root also explores moves that the policy dismisses.
+    ///
+    pub fn add_noise (& mut self, id: NodeID)
+    {
+        let (epsilon, alpha) = (self.config.noise_epsilon, self.config.noise_alpha);
+        let num_children = self.node_immut(id).num_children;
+
+        // A Dirichlet sample is a vector of gamma samples, normalized to sum to one.
+
+        let noise = (0 .. num_children).map(|_| self.sample_gamma(alpha)).collect::<Vec<f32>>();
+        let total : f32 = noise.iter().sum();
+        if total <= 0.0
+        {
+            return;
+        }
+
+        for (child, eta) in self.children_of(id).iter_mut().zip(noise)
+        {
+            child.p = (1.0 - epsilon) * child.p + epsilon * eta / total;
+        }
+    }
+
     ///
     /// Returns the children of the given node.
     ///
@@ -183,21 +266,69 @@ impl Searcher
     }
 
     ///
-    /// Gets the best continuation.
+    /// Returns a copy of the subtree under the given node, renumbered so that the node 
+    /// is the root at index 0. Only the root and the nodes with at least the given number 
+    /// of visits keep their children; any other node keeps its statistics but is copied 
+    /// unexpanded, so that it is expanded again if a later simulation reaches it. The copy 
+    /// is breadth-first, so each node's children stay contiguous.
+    ///
+    pub fn compact (& self, id: NodeID, min_visits: f32) -> Vec<Node>
+    {
+        let mut root = self.node_immut(id).clone();
+        root.id = 0;
+        root.parent = None;
+
+        let mut tree = vec![root];
+        let mut queue = std::collections::VecDeque::from([(id, 0)]);
+
+        while let Some((old_id, new_id)) = queue.pop_front()
+        {
+            if new_id != 0 && self.node_immut(old_id).n < min_visits 
+            {
+                tree[new_id].oldest_child = 0;
+                tree[new_id].num_children = 0;
+                continue;
+            }
+
+            let oldest_child = tree.len();
+
+            for child in self.children_of_immut(old_id)
+            {
+                let mut copy = child.clone();
+                copy.id = tree.len();
+                copy.parent = Some(new_id);
+
+                queue.push_back((child.id, copy.id));
+                tree.push(copy);
+            }
+
+            tree[new_id].oldest_child = oldest_child;
+        }
+
+        tree
+    }
+
+    ///
+    /// Gets the best continuation. Ties are broken in favour of the lowest move id,
+    /// so that selection does not depend on the order of the children in the tree.
     ///
     pub fn continuation (& self, id: NodeID) -> NodeID 
     {
         let mut best_id = None;
+        let mut best_action = MoveID::MAX;
         let mut best_score = f32::NEG_INFINITY;
 
-        for child in self.children_of_immut(id)
+        let widened = self.num_widened(id);
+
+        for child in & self.children_of_immut(id)[.. widened]
         {
             let q = self.get_q(id, child.id);
             let u = self.get_u(id, child.id);
             let score = q + u;
-            if score > best_score
+            if score > best_score || (score == best_score && child.in_action < best_action)
             {
                 best_id = Some(child.id);
+                best_action = child.in_action;
                 best_score = score;
             }
         }
@@ -206,7 +337,38 @@ impl Searcher
     }
 
     ///
-    /// Gets the q value, or the exploitation value of the given states-action pair.
+    /// Descends from the root along the best continuations to the node to evaluate next, which 
+    /// is solved or unvisited, playing each in-action onto the given board, which starts as the 
+    /// root's state. Returns the node and the number of nodes on the path; fails if an in-action 
+    /// does not apply, or if the path grows past the given limit, which means that the tree is 
+    /// malformed, for example by a cycle.
+    ///
+    pub fn descend (& self, board: & mut Board, limit: usize) -> Result<(NodeID, usize)>
+    {
+        let mut id = self.root;
+        let mut depth = 1;
+
+        while self.node_immut(id).is_unsolved() && self.node_immut(id).is_visited()
+        {
+            if depth >= limit 
+            {
+                return Err(error!("Descended {} nodes from the root; the tree may contain a cycle.", depth));
+            }
+
+            id = self.continuation(id);
+            board.place_tetromino(& self.node_immut(id).action()).context("An in-action on the descent does not apply.")?;
+            depth += 1;
+        }
+
+        Ok((id, depth))
+    }
+
+    ///
+    /// Gets the q value, or the exploitation value of the given states-action pair. An 
+    /// unexplored child takes the parent's value, less the first-play urgency reduction 
+    /// scaled by the square root of the total prior of the explored siblings; an explored 
+    /// child takes the statistics of its position in the transposition table, if they 
+    /// hold more visits than the child itself.
     ///
     pub fn get_q (& self, parent: NodeID, child: NodeID) -> f32 
     {
@@ -219,11 +381,24 @@ impl Searcher
         }
         else if child.num_children == 0 
         {
-            parent.v / parent.n
+            let reduction = match self.config.fpu_reduction == 0.0 
+            {
+                true  => 0.0,
+                false => 
+                {
+                    self.config.fpu_reduction * parent.explored.sqrt()
+                }
+            };
+
+            parent.v / parent.n - reduction
         }
         else 
         {
-            - child.v / child.n
+            match self.table.get(child.hash, child.checksum)
+            {
+                Some(entry) if entry.n > child.n => - entry.v / entry.n,
+                _                                => - child.v / child.n
+            }
         }
     }
 
@@ -252,6 +427,16 @@ impl Searcher
             {
                 return;
             }
+
+            // The latch stays set until the pool halts, so a searcher that has already taken 
+            // up the current search waits for the next one rather than searching again.
+
+            if self.epoch.load(Ordering::SeqCst) == self.pool().epoch.load(Ordering::SeqCst)
+            {
+                std::thread::sleep(Duration::from_millis(1));
+                continue;
+            }
+
             log::debug!("Launching thread.");
             self.launch();
         }
@@ -268,10 +453,29 @@ impl Searcher
         self.state = position.clone();
         self.solve_for = position.to_move();
 
-        self.tree.push(Node::new(0, None, position, None, Tetromino::null().into(), 0.0));
+        self.tree.push(Node::new(0, None, position.zobrist_hash(), position.checksum(), None, Tetromino::null().into(), 0.0));
         self.root = 0;
     }
 
+    ///
+    /// Determines whether the best move in this searcher's tree is settled: the most visited 
+    /// move at the root is also (within a tolerance) the one with the best mean value.
+    ///
+    pub fn is_settled (& self) -> bool 
+    {
+        let mean = |child: & Node| child.v / child.n;
+        let visited = self.children_of_immut(self.root).iter().filter(|child| child.n > 0.0);
+
+        let most_visited = visited.clone().max_by(|a, b| a.n.total_cmp(& b.n));
+        let best_valued = visited.min_by(|a, b| mean(a).total_cmp(& mean(b)));
+
+        match (most_visited, best_valued)
+        {
+            (Some(top), Some(best)) => mean(top) <= mean(best) + SETTLED_EPSILON,
+            _                       => false
+        }
+    }
+
     ///
     /// Starts this searcher.
     ///
@@ -279,8 +483,8 @@ impl Searcher
     {
         log::debug!("Notifying start status.");
         self.search_status.set(SearcherEvent::Start.into());
+        self.epoch.store(self.pool().epoch.load(Ordering::SeqCst), Ordering::SeqCst);
 
-        self.pool().set_stop_requirement(false);
         self.search_root();
 
         log::debug!("Notifying finish status.");
@@ -288,18 +492,20 @@ impl Searcher
     }
 
     ///
-    /// Returns a new searcher.
+    /// Returns a new searcher, which shares the given transposition table with the other 
+    /// searchers in its pool.
     ///
-    pub fn new (pool: * mut ThreadPool, config: Config, policy: & Network, id: TreeID, cond_variable: Arc<Latch>) -> Searcher
+    pub fn new (pool: * mut ThreadPool, config: Config, policy: & dyn Evaluator, id: TreeID, cond_variable: Arc<Latch>, table: Arc<TranspositionTable>) -> Searcher
     {
         Searcher 
         {
             pool,
             config: config.mcts.clone(),
-            network: policy.copy(),
+            evaluator: policy.copy(),
 
             id,
             kill: AtomicBool::new(false),
+            epoch: AtomicUsize::new(0),
             search_status: Arc::new(Guard::new(true)),
             cond_variable,
 
@@ -309,9 +515,13 @@ impl Searcher
             tree: Vec::new(),
             root: 0,
             num_sims: 0,
+            table,
 
             best_move: 0,
-            best_eval: 0.0
+            best_eval: 0.0,
+            progress: Mutex::new(Progress::default()),
+
+            rng: config.seed ^ (id as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15)
         }
     }
 
@@ -331,6 +541,186 @@ impl Searcher
         & self.tree[id]
     }
 
+    ///
+    /// Returns the number of children of the given node that are currently eligible 
+    /// for selection under progressive widening, which is ceil(C * n^alpha) bounded 
+    /// by the number of children; since children are ordered by prior, these are 
+    /// the most promising children. A node that has not been backed up yet counts 
+    /// as visited once, and a constant that admits every child on the first visit 
+    /// disables widening outright.
+    ///
+    pub fn num_widened (& self, id: NodeID) -> usize 
+    {
+        let node = self.node_immut(id);
+        if self.config.pw_c >= node.num_children as f32
+        {
+            return node.num_children;
+        }
+
+        let limit = (self.config.pw_c * node.n.max(1.0).powf(self.config.pw_alpha)).ceil();
+
+        match limit >= node.num_children as f32
+        {
+            true  => node.num_children,
+            false => (limit as usize).max(1)
+        }
+    }
+
+    ///
+    /// Reconstructs the board at the given node by replaying the in-actions on the path 
+    /// from the root onto the root's state; errors if an in-action does not apply.
+    ///
+    pub fn position_of (& self, id: NodeID) -> Result<Board>
+    {
+        let mut path = Vec::new();
+        let mut current = id;
+
+        while let Some(parent) = self.node_immut(current).parent 
+        {
+            path.push(self.node_immut(current).action());
+            current = parent;
+        }
+
+        let mut board = self.state.clone();
+        for tetromino in path.iter().rev()
+        {
+            board.place_tetromino(tetromino)?;
+        }
+
+        Ok(board)
+    }
+
+    ///
+    /// Re-roots this searcher's tree at the root's child reached by the given move, keeping 
+    /// the child's subtree and its statistics. Returns whether the child was in the tree; 
+    /// otherwise the tree is left as it was.
+    ///
+    pub fn pivot (& mut self, tetromino: & Tetromino) -> bool 
+    {
+        if self.tree.is_empty()
+        {
+            return false;
+        }
+
+        let action : MoveID = tetromino.clone().into();
+        let child = self.children_of_immut(self.root).iter()
+            .find(|child| child.in_action == action)
+            .map(|child| child.id);
+
+        let mut position = self.state.clone();
+        match child 
+        {
+            Some(id) if position.place_tetromino(tetromino).is_ok() => 
+            {
+                self.tree = self.subtree(id);
+                self.root = 0;
+
+                self.solve_for = position.to_move();
+                self.state = position;
+
+                self.best_move = 0;
+                self.best_eval = 0.0;
+
+                true
+            },
+            _ => false
+        }
+    }
+
+    ///
+    /// Returns the evaluator's policy and value for the given node, whose state is the given 
+    /// board with the given legal moves. A position that any searcher in the pool has already 
+    /// evaluated reuses the prediction stored in the transposition table; otherwise, the new 
+    /// prediction is stored there.
+    ///
+    pub fn predict (& self, id: NodeID, board: & Board, moves: & [Tetromino]) -> ([f32; TETROMINO_RANGE], f32)
+    {
+        let node = self.node_immut(id);
+
+        if let Some(prediction) = self.table.prediction(node.hash, node.checksum)
+        {
+            let mut policy = [0.0; TETROMINO_RANGE];
+            prediction.priors.iter().for_each(|& (action, p)| policy[action] = p);
+            return (policy, prediction.value);
+        }
+
+        let (policy, value) = self.evaluator.predict(board);
+        let priors = moves.iter().map(|tetromino| 
+        {
+            let action : MoveID = tetromino.clone().into();
+            (action, policy[action])
+        }).collect();
+        self.table.store_prediction(node.hash, node.checksum, Prediction { priors, value });
+
+        (policy, value)
+    }
+
+    ///
+    /// Returns the principal variation from the root, which is the line formed by 
+    /// repeatedly following the most-visited child, up to the given depth.
+    ///
+    pub fn principal_variation (& self, depth: usize) -> Vec<Tetromino>
+    {
+        self.variation(self.root, depth)
+    }
+
+    ///
+    /// Publishes this searcher's progress after the given number of simulations, so that 
+    /// the pool can read it without touching the tree while it grows.
+    ///
+    pub fn publish_progress (& self, num_sims: usize)
+    {
+        let best = self.children_of_immut(self.root).iter()
+            .filter(|child| child.n > 0.0)
+            .max_by(|a, b| a.n.total_cmp(& b.n).then(b.in_action.cmp(& a.in_action)));
+
+        let progress = match best 
+        {
+            Some(child) => 
+            {
+                // A child's outcome is in the perspective of its player to move, which is the 
+                // opponent at the root.
+
+                let value = match child.outcome.map(|outcome| outcome.next())
+                {
+                    Some(Outcome::Win(_))  => f32::INFINITY,
+                    Some(Outcome::Loss(_)) => f32::NEG_INFINITY,
+                    None                   => - child.v / child.n
+                };
+                Progress { best: Some(child.in_action), visits: child.n, value, sims: num_sims }
+            },
+            None => Progress { sims: num_sims, .. Progress::default() }
+        };
+
+        * self.progress.lock().unwrap() = progress;
+    }
+
+    ///
+    /// Logs a progress line of the form 'info depth <d> nodes <n> score <v> pv <move>...', 
+    /// where the score is in the perspective of the player to move at the root. It goes to 
+    /// the log rather than stdout, which carries the protocol.
+    ///
+    pub fn print_info (& self, depth: usize)
+    {
+        let root = self.node_immut(self.root);
+        let score = match root.n > 0.0 
+        {
+            true  => root.v / root.n,
+            false => 0.0
+        };
+        let pv = self.principal_variation(depth).iter().map(|tetromino| tetromino.notate()).collect::<Vec<String>>().join(" ");
+
+        log::info!("info depth {} nodes {} score {} pv {}", depth, self.tree.len(), score, pv);
+    }
+
+    ///
+    /// Determines whether the pool is pondering, in which case the search is not timed.
+    ///
+    pub fn pondering (& self) -> bool 
+    {
+        self.pool().pondering.load(Ordering::SeqCst)
+    }
+
     ///
     /// Returns the threadpool from this searcher's parent 
     /// in a somewhat horrifying way.
@@ -344,11 +734,128 @@ impl Searcher
     }
 
     ///
-    /// Returns the root.
+    /// Recycles the arena once it is full, by compacting the tree to at most half of its 
+    /// capacity: the subtrees of the least-visited nodes are discarded, with the visit 
+    /// threshold doubling until the kept nodes fit.
+    ///
+    pub fn prune (& mut self)
+    {
+        let target = self.config.arena_capacity / 2;
+        let root_visits = self.node_immut(self.root).n;
+
+        let kept = |threshold: f32| -> usize 
+        {
+            1 + self.tree.iter()
+                .filter(|node| node.id == self.root || node.n >= threshold)
+                .map(|node| node.num_children)
+                .sum::<usize>()
+        };
+
+        let mut threshold = 1.0;
+        while kept(threshold) > target && threshold <= root_visits 
+        {
+            threshold *= 2.0;
+        }
+
+        let before = self.tree.len();
+        self.tree = self.compact(self.root, threshold);
+        self.root = 0;
+
+        log::debug!("Searcher {} pruned its tree from {} to {} nodes, keeping the nodes with at least {} visits.", self.id, before, self.tree.len(), threshold);
+    }
+
+    ///
+    /// Re-roots this searcher's tree at the node holding the given position, keeping the 
+    /// subtree below it and its statistics and discarding the rest; this reuses the search 
+    /// of the previous move when the game has moved on along the tree. Returns whether 
+    /// the position was found; otherwise the tree is left as it was.
     ///
-    pub fn root (& mut self) -> & mut Node 
+    pub fn reroot (& mut self, position: & Board) -> bool 
     {
-        & mut self.tree[self.root]
+        let (hash, checksum) = (position.zobrist_hash(), position.checksum());
+        let notation = position.notate();
+
+        // A tree that is already rooted at the position is kept as it is.
+
+        if ! self.tree.is_empty() && self.node_immut(self.root).matches(hash, checksum) && self.state.notate() == notation 
+        {
+            return true;
+        }
+
+        let found = self.tree.iter()
+            .filter(|node| node.matches(hash, checksum))
+            .map(|node| node.id)
+            .find(|& id| self.position_of(id).is_ok_and(|board| board.notate() == notation));
+
+        match found 
+        {
+            Some(id) => 
+            {
+                self.tree = self.subtree(id);
+                self.root = 0;
+
+                self.state = position.clone();
+                self.solve_for = position.to_move();
+
+                self.best_move = 0;
+                self.best_eval = 0.0;
+
+                true
+            },
+            None => false
+        }
+    }
+
+    ///
+    /// Draws a sample from the gamma distribution with the given shape and unit scale, by 
+    /// the method of Marsaglia and Tsang; shapes below one are boosted by one and scaled back.
+    ///
+    pub fn sample_gamma (& mut self, alpha: f32) -> f32 
+    {
+        if alpha < 1.0 
+        {
+            let u = self.sample_uniform();
+            return self.sample_gamma(alpha + 1.0) * u.powf(1.0 / alpha);
+        }
+
+        let d = alpha - 1.0 / 3.0;
+        let c = 1.0 / (9.0 * d).sqrt();
+
+        loop 
+        {
+            // A standard normal sample, by the Box-Muller transform.
+
+            let (u1, u2) = (self.sample_uniform(), self.sample_uniform());
+            let x = (- 2.0 * u1.ln()).sqrt() * (2.0 * std::f32::consts::PI * u2).cos();
+
+            let v = (1.0 + c * x).powi(3);
+            if v <= 0.0 
+            {
+                continue;
+            }
+
+            let u = self.sample_uniform();
+            if u.ln() < 0.5 * x * x + d - d * v + d * v.ln()
+            {
+                return d * v;
+            }
+        }
+    }
+
+    ///
+    /// Draws a sample uniformly from (0, 1] with this searcher's seeded generator.
+    ///
+    pub fn sample_uniform (& mut self) -> f32 
+    {
+        // Advance the generator by one step of splitmix64.
+
+        self.rng = self.rng.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.rng;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+
+        ((z >> 40) + 1) as f32 / (1u64 << 24) as f32
     }
 
     ///
@@ -356,100 +863,223 @@ impl Searcher
     ///
     pub fn search_root (& mut self)
     {
-        let allowed_duration = Duration::from_millis(self.config.max_time_ms as u64);
         let start = Instant::now();
         let mut num_sims : usize = 0;
 
-        log::debug!("Starting with {} millis and signal '{}'.", allowed_duration.as_millis(), if self.stop() { "stop" } else { "go" });
+        // Only the first searcher reports progress, so that a front-end sees one stream.
+
+        let verbose = self.config.verbose_search && self.id == 0;
+        let mut last_info = start;
+        let mut max_depth = 0;
+
+        log::debug!("Starting with {} millis and signal '{}'.", self.pool().move_time_ms, if self.stop() { "stop" } else { "go" });
 
-        while ! self.stop() && (Instant::now() - start) < allowed_duration
+        self.publish_progress(num_sims);
+
+        while ! self.should_stop(start, num_sims)
         {
+            if self.tree.len() >= self.config.arena_capacity 
+            {
+                self.prune();
+            }
+
             num_sims += 1;
-            let mut id = self.root;
 
-            if ! self.root().is_unsolved()
+            // Nodes keep only their in-actions, so the board is rebuilt along the descent.
+
+            let mut board = self.state.clone();
+
+            match self.descend(& mut board, MAX_DESCENT)
             {
-                log::debug!("Searcher solved its root position.");
-                break;
+                Ok((id, depth)) => 
+                {
+                    match self.node_immut(id).outcome 
+                    {
+                        Some(outcome) => self.backpropagate(id, outcome.value(), true),
+                        None          => 
+                        {
+                            let (value, found_leaf) = self.visit(id, & mut board);
+                            self.backpropagate(id, value, found_leaf);
+                        }
+                    };
+                    max_depth = max_depth.max(depth);
+                },
+                Err(e) => log::error!("Aborted a simulation: {:#}", e)
+            };
+
+            if num_sims.is_multiple_of(PROGRESS_INTERVAL)
+            {
+                self.publish_progress(num_sims);
             }
 
-            loop 
+            if verbose && last_info.elapsed() >= INFO_INTERVAL 
             {
-                let node = self.node(id);
-                if let Some(outcome) = node.outcome 
-                {
-                    self.backpropagate(id, outcome.value(), true);
-                    break;
-                }
-                else if node.is_unvisited()
-                {
-                    let (value, found_leaf) = self.visit(id);
-                    self.backpropagate(id, value, found_leaf);
-                    break;
-                }
-                else 
-                {
-                    id = self.continuation(id);
-                }
+                self.print_info(max_depth);
+                last_info = Instant::now();
             }
         }
 
+        if verbose 
+        {
+            self.print_info(max_depth);
+        }
+
         self.num_sims = num_sims;
+        self.publish_progress(num_sims);
         self.pool().set_stop_requirement(true);
     }
 
+    ///
+    /// Determines whether the search that started at the given time and has run the given 
+    /// number of simulations should stop: when the pool requests a stop, the root is solved, 
+    /// the soft time limit has passed and the best move is settled, the hard time limit has 
+    /// passed, or the simulation or node budget is spent. A ponder is not limited by time.
+    ///
+    pub fn should_stop (& self, start: Instant, num_sims: usize) -> bool 
+    {
+        let soft_limit = Duration::from_millis(self.pool().move_time_ms as u64);
+        let hard_limit = Duration::from_millis(self.pool().hard_time_ms.max(self.pool().move_time_ms) as u64);
+
+        if self.stop()
+        {
+            return true;
+        }
+
+        if ! self.node_immut(self.root).is_unsolved()
+        {
+            log::debug!("Searcher solved its root position.");
+            return true;
+        }
+
+        let out_of_time = match start.elapsed()
+        {
+            elapsed if elapsed >= hard_limit => true,
+            elapsed if elapsed >= soft_limit => self.is_settled(),
+            _                                => false
+        };
+
+        (! self.pondering() && out_of_time)
+            || num_sims >= self.config.max_sims 
+            || self.tree.len() >= self.config.max_nodes
+    }
+
     ///
     /// Determines whether to stop.
     ///
-    pub fn stop (& mut self) -> bool 
+    pub fn stop (& self) -> bool 
     {
         self.pool().stop.load(Ordering::SeqCst)
     }
 
     ///
-    /// Visits the given node, expanding it if necessary, and returns its value 
-    /// as well as whether the position is solved in this subtree.
+    /// Returns a copy of the subtree under the given node, renumbered so that the node 
+    /// is the root at index 0. The copy is breadth-first, so each node's children stay 
+    /// contiguous.
+    ///
+    pub fn subtree (& self, id: NodeID) -> Vec<Node>
+    {
+        self.compact(id, 0.0)
+    }
+
+    ///
+    /// Returns the line formed by repeatedly following the most-visited child from the 
+    /// given node, up to the given depth.
+    ///
+    pub fn variation (& self, id: NodeID, depth: usize) -> Vec<Tetromino>
+    {
+        let mut line = Vec::new();
+        let mut id = id;
+
+        while line.len() < depth && self.node_immut(id).is_visited()
+        {
+            let best = self.children_of_immut(id).iter()
+                .filter(|child| child.n > 0.0)
+                .max_by(|a, b| a.n.total_cmp(& b.n).then(b.in_action.cmp(& a.in_action)));
+
+            match best 
+            {
+                Some(child) => 
+                {
+                    line.push(child.action());
+                    id = child.id;
+                },
+                None => break
+            }
+        }
+
+        line
+    }
+
+    ///
+    /// Visits the given node, whose state is the given board, expanding it if necessary, 
+    /// and returns its value as well as whether the position is solved in this subtree.
+    /// The board is restored before returning.
     ///
-    pub fn visit (& mut self, id: NodeID) -> (f32, bool)
+    pub fn visit (& mut self, id: NodeID, game: & mut Board) -> (f32, bool)
     {
         let insertion_point = self.tree.len();
-        let node = self.node_immut(id);
-        let game = node.state.clone();
-        let (policy, value) = self.network.predict(& game);
-        let mut num_children = 0;
+        let moves = game.moves_iter().collect::<Vec<Tetromino>>();
+        let (policy, value) = self.predict(id, game, & moves);
+        let mut children = Vec::new();
         let mut any = false;
         let mut max_action = f32::NEG_INFINITY;
+        let (hash, checksum) = (self.node_immut(id).hash, self.node_immut(id).checksum);
 
-        // Add a new node for every possible move.
+        // Add a new node for every possible move; their hashes are updated from this node's 
+        // by the tiles each move covers.
 
-        for tetromino in & game.enumerate_moves()
+        for tetromino in & moves 
         {
-            let mut next_state = game.clone();
-            let _ = next_state.place_tetromino(& tetromino);
+            let (child_hash, child_checksum) = (game.zobrist_hash_after(hash, tetromino), game.checksum_after(checksum, tetromino));
+            let token = match game.make(& tetromino)
+            {
+                Ok(token) => token,
+                Err(_)    => continue
+            };
+            let next_state = & * game;
+            let score = next_state.score() as f32 * next_state.to_move().value() as f32;
             let over = ! next_state.has_moves();
             let outcome = match over 
             {
-                // Transform the score to be in the player to move's perspective.
-                // If it is positive, this player won, otherwise they lost.
+                // Transform the final score to be in the perspective of the player to move 
+                // in the child, as for every solved node. If it is positive, this player won, 
+                // otherwise they lost.
                 
                 true =>
                 {
                     any = true;
-                    Some(<Outcome as From<f32>>::from(game.score() as f32 * game.to_move().value() as f32))
+                    Some(<Outcome as From<f32>>::from(score))
                 },
                 false => None
             };
             let action : usize = <Tetromino as Into<usize>>::into(tetromino.clone());
-            let pred = 
-                (
-                policy[action] 
-                + next_state.score() as f32 * next_state.to_move().value() as f32) / 2.0
-                ;
+            let pred = (policy[action] + score) / 2.0;
             max_action = max_action.max(pred);
-            let mut child = Node::new(self.tree.len(), Some(id), & next_state, outcome, action, pred);
-            child.v = next_state.score() as f32 * next_state.to_move().value() as f32;
+            let mut child = Node::new(0, Some(id), child_hash, child_checksum, outcome, action, pred);
+            child.v = score;
+
+            // A position that another searcher, or another line, has solved is solved here too.
+
+            if child.outcome.is_none()
+            {
+                child.outcome = self.table.get(child.hash, child.checksum).and_then(|entry| entry.outcome);
+                any |= child.outcome.is_some();
+            }
+            children.push(child);
+
+            game.unmake(token);
+        }
+
+        // Order the children by prior, so that progressive widening admits the most 
+        // promising children first.
+
+        children.sort_by(|a, b| b.p.total_cmp(& a.p));
+        let num_children = children.len();
+
+        for mut child in children
+        {
+            child.id = self.tree.len();
             self.tree.push(child);
-            num_children += 1;
         }
 
         // Mark this node as visited, linking its children references into the tree.
@@ -470,6 +1100,247 @@ impl Searcher
             child.p /= total;
         }
 
+        if id == self.root && self.config.root_noise 
+        {
+            self.add_noise(id);
+        }
+
+        // Count this node's prior towards its parent's explored total, which the first-play 
+        // urgency of its unexplored siblings is reduced by.
+
+        let node = self.node_immut(id);
+        if let (Some(parent), true) = (node.parent, node.is_visited())
+        {
+            let p = node.p;
+            self.node(parent).explored += p;
+        }
+
         (value, any)
     }
 }
+
+#[cfg(test)]
+mod tests 
+{
+    use super::*;
+
+    use crate::mcts::evaluator::tests::Uniform;
+
+    ///
+    /// An evaluator that counts its predictions, which it delegates to a uniform evaluator.
+    ///
+    #[derive(Clone, Debug, Default)]
+    struct Counting (Arc<AtomicUsize>);
+
+    impl Evaluator for Counting 
+    {
+        fn predict (& self, board: & Board) -> ([f32; TETROMINO_RANGE], f32)
+        {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Uniform.predict(board)
+        }
+
+        fn copy (& self) -> Box<dyn Evaluator>
+        {
+            Box::new(self.clone())
+        }
+    }
+
+    ///
+    /// Returns a pool with the default configuration, which is free to search; it is boxed 
+    /// so that its searchers can keep a pointer to it.
+    ///
+    fn pool () -> Box<ThreadPool>
+    {
+        Tetromino::initialize();
+
+        let config : Config = toml::from_str("").unwrap();
+        let pool = Box::new(ThreadPool::new(& config));
+        pool.stop.store(false, Ordering::SeqCst);
+        pool
+    }
+
+    ///
+    /// Returns a searcher in the given pool, initialized on the blank board.
+    ///
+    fn searcher (pool: & mut ThreadPool) -> Searcher 
+    {
+        searcher_with(pool, & Uniform)
+    }
+
+    ///
+    /// Returns a searcher in the given pool that consults the given evaluator, initialized on 
+    /// the blank board.
+    ///
+    fn searcher_with (pool: & mut ThreadPool, evaluator: & dyn Evaluator) -> Searcher 
+    {
+        let (config, cond, table) = (pool.config.clone(), pool.cond.clone(), pool.table.clone());
+        let mut searcher = Searcher::new(pool, config, evaluator, 0, cond, table);
+        searcher.initialize(& Board::blank());
+        searcher
+    }
+
+    ///
+    /// Expands the given node of the searcher's tree with unvisited children for the given 
+    /// moves, each with the given prior, and returns their ids.
+    ///
+    fn expand (searcher: & mut Searcher, id: NodeID, moves: & [MoveID], p: f32) -> Vec<NodeID>
+    {
+        let first = searcher.tree.len();
+        for (i, & action) in moves.iter().enumerate()
+        {
+            searcher.tree.push(Node::new(first + i, Some(id), 0, 0, None, action, p));
+        }
+        searcher.node(id).visit(first, moves.len());
+        (first .. first + moves.len()).collect()
+    }
+
+    #[test]
+    fn continuation_breaks_ties_by_lowest_move ()
+    {
+        let mut pool = pool();
+        let mut searcher = searcher(& mut pool);
+
+        let root = searcher.root;
+        searcher.node(root).n = 1.0;
+        expand(& mut searcher, root, & [7, 3], 0.5);
+
+        let best = searcher.continuation(root);
+        assert_eq!(searcher.node_immut(best).in_action, 3);
+    }
+
+    #[test]
+    fn expansion_counts_towards_explored_prior ()
+    {
+        let mut pool = pool();
+        let mut searcher = searcher(& mut pool);
+        let mut board = Board::blank();
+        searcher.visit(0, & mut board);
+
+        for child in [1, 3]
+        {
+            let token = board.make(& searcher.node_immut(child).action()).unwrap();
+            searcher.visit(child, & mut board);
+            board.unmake(token);
+        }
+
+        let explored : f32 = searcher.children_of_immut(0).iter().filter(|c| c.is_visited()).map(|c| c.p).sum();
+        assert!(explored > 0.0);
+        assert_eq!(searcher.node_immut(0).explored, explored);
+    }
+
+    #[test]
+    fn expansion_reuses_stored_prediction ()
+    {
+        let mut pool = pool();
+        let evaluator = Counting::default();
+        let mut a = searcher_with(& mut pool, & evaluator);
+        let mut b = searcher_with(& mut pool, & evaluator);
+
+        a.visit(0, & mut Board::blank());
+        b.visit(0, & mut Board::blank());
+        assert_eq!(evaluator.0.load(Ordering::SeqCst), 1);
+
+        let priors = |searcher: & Searcher| searcher.children_of_immut(0).iter().map(|child| (child.in_action, child.p)).collect::<Vec<(MoveID, f32)>>();
+        assert_eq!(priors(& a), priors(& b));
+    }
+
+    #[test]
+    fn widening_admits_children_as_visits_grow ()
+    {
+        let mut pool = pool();
+        let mut searcher = searcher(& mut pool);
+        searcher.visit(0, & mut Board::blank());
+        assert_eq!(searcher.num_widened(0), searcher.node_immut(0).num_children);
+
+        searcher.config.pw_c = 1.0;
+        searcher.config.pw_alpha = 0.5;
+        assert_eq!(searcher.num_widened(0), 1);
+
+        let mut widened = vec![];
+        for _ in 0 .. 16
+        {
+            searcher.backpropagate(0, 0.0, false);
+            widened.push(searcher.num_widened(0));
+        }
+        assert!(widened.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert_eq!(widened.last(), Some(& 4));
+    }
+
+    #[test]
+    fn should_stop_after_hard_time_limit ()
+    {
+        let mut pool = pool();
+        let searcher = searcher(& mut pool);
+        searcher.pool().move_time_ms = 1;
+        searcher.pool().hard_time_ms = 1;
+
+        let now = Instant::now();
+        assert!(! searcher.should_stop(now + Duration::from_secs(1), 0));
+        assert!(searcher.should_stop(now - Duration::from_millis(10), 0));
+    }
+
+    #[test]
+    fn should_stop_after_sim_budget ()
+    {
+        let mut pool = pool();
+        let mut searcher = searcher(& mut pool);
+        searcher.config.max_sims = 10;
+
+        assert!(! searcher.should_stop(Instant::now(), 9));
+        assert!(searcher.should_stop(Instant::now(), 10));
+    }
+
+    #[test]
+    fn descent_guards_against_cycles ()
+    {
+        let mut pool = pool();
+        let mut searcher = searcher(& mut pool);
+
+        // The root's only child lists itself as its own child.
+
+        let action : MoveID = Board::blank().enumerate_moves().into_iter().next().unwrap().into();
+        let child = expand(& mut searcher, 0, & [action], 1.0)[0];
+        searcher.node(child).visit(child, 1);
+        searcher.node(0).n = 2.0;
+        searcher.node(child).n = 1.0;
+
+        let error = searcher.descend(& mut Board::blank(), 2).unwrap_err();
+        assert!(format!("{:#}", error).contains("cycle"));
+        assert!(searcher.descend(& mut Board::blank(), MAX_DESCENT).is_err());
+    }
+
+    #[test]
+    fn should_stop_after_node_budget ()
+    {
+        let mut pool = pool();
+        let mut searcher = searcher(& mut pool);
+        searcher.config.max_nodes = 2;
+
+        assert!(! searcher.should_stop(Instant::now(), 0));
+        expand(& mut searcher, 0, & [1], 1.0);
+        assert!(searcher.should_stop(Instant::now(), 0));
+    }
+
+    #[test]
+    fn should_stop_on_solved_root ()
+    {
+        let mut pool = pool();
+        let mut searcher = searcher(& mut pool);
+
+        assert!(! searcher.should_stop(Instant::now(), 0));
+        searcher.node(0).solve(Outcome::Win(1.0));
+        assert!(searcher.should_stop(Instant::now(), 0));
+    }
+
+    #[test]
+    fn should_stop_on_external_stop ()
+    {
+        let mut pool = pool();
+        let searcher = searcher(& mut pool);
+
+        assert!(! searcher.should_stop(Instant::now(), 0));
+        searcher.pool().set_stop_requirement(true);
+        assert!(searcher.should_stop(Instant::now(), 0));
+    }
+}
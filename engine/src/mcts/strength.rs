@@ -0,0 +1,61 @@
+
+use crate::config::*;
+
+use utils::error::*;
+
+///
+/// The strongest level, at which the engine searches as configured.
+///
+pub const MAX_LEVEL : usize = 5;
+
+///
+/// A playing strength, which limits the engine's search: the time and simulations of each
+/// search, whether noise is added at the root, whether the policy's move is played without
+/// a search, and the temperature with which the move is sampled from the root's visits on
+/// every ply; a temperature of zero always plays the best move.
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Strength
+{
+    pub level: usize,
+    pub max_time_ms: usize,
+    pub hard_time_ms: usize,
+    pub max_sims: usize,
+    pub root_noise: bool,
+    pub policy_only: bool,
+    pub temperature: f32
+}
+
+impl Strength
+{
+    ///
+    /// Returns the strength of the given level, from 0 to MAX_LEVEL, derived from the given
+    /// configuration. Level 0 plays the policy's move instantly; the levels in between cap
+    /// the configured search, and add noise and sampling at the weakest levels; the strongest
+    /// level searches as configured.
+    ///
+    pub fn from_level (level: usize, config: & MCTSConfig) -> Result<Strength>
+    {
+        let (time_ms, sims, root_noise, policy_only, temperature) = match level
+        {
+            0         => (config.max_time_ms, config.max_sims, false, true, 0.0),
+            1         => (100, 64, true, false, 1.0),
+            2         => (250, 256, true, false, 0.5),
+            3         => (500, 1024, false, false, 0.25),
+            4         => (1000, 4096, false, false, 0.0),
+            MAX_LEVEL => (config.max_time_ms, config.max_sims, config.root_noise, config.policy_only, 0.0),
+            _         => return Err(error!("The strength must be a level from 0 to {}, but is {}.", MAX_LEVEL, level))
+        };
+
+        // Capped levels never extend an unsettled search past their time.
+
+        let max_time_ms = time_ms.min(config.max_time_ms);
+        let hard_time_ms = match level
+        {
+            MAX_LEVEL => config.hard_time_ms,
+            _         => max_time_ms
+        };
+
+        Ok(Strength { level, max_time_ms, hard_time_ms, max_sims: sims.min(config.max_sims), root_noise, policy_only, temperature })
+    }
+}
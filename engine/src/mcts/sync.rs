@@ -51,6 +51,14 @@ pub struct Latch
     latch: Condvar
 }
 
+impl Default for Latch 
+{
+    fn default () -> Latch 
+    {
+        Latch::new()
+    }
+}
+
 impl Latch 
 {
     ///
@@ -2,7 +2,6 @@
 #![allow(mutable_transmutes)]
 
 use crate::config::*;
-use crate::neural::network::Network;
 
 use lits::{Board, Tetromino};
 
@@ -13,32 +12,94 @@ use std::mem;
 use std::ptr;
 use std::ptr::NonNull;
 use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::thread;
 use std::thread::JoinHandle;
 
+use super::evaluator::Evaluator;
 use super::node::{Outcome, MoveID};
 use super::searcher::*;
 use super::sync::*;
+use super::transposition::TranspositionTable;
 
 use tabled::{Table, Tabled};
 
 use utils::*;
 use utils::notate::Notate;
 
+///
+/// How close a move's eval must be to the best eval for a randomized tiebreak to 
+/// consider it equal.
+///
+pub const TIEBREAK_EPSILON: f32 = 1.0e-4;
+
 ///
 /// A stats object that is printed into the summary table.
 ///
-#[derive(Clone, Debug, Tabled, PartialEq)]
+/// Solved moves carry an infinite eval, which is serialized as the tagged 
+/// string "+inf" or "-inf" since JSON has no representation for infinity,
+/// and a signed margin, which breaks ties between equally solved moves.
+///
+#[derive(Clone, Debug, Tabled, PartialEq, Serialize, Deserialize)]
 pub struct SearcherStats
 {
     pub tetromino: String,
     pub visits: f32,
     pub prob: f32,
+    #[serde(with = "eval_format")]
     pub eval: f32,
+    pub margin: f32,
     pub components: i32
 }
 
+///
+/// Serializes evals such that the solved sentinels survive a round trip.
+///
+pub mod eval_format 
+{
+    use utils::{Deserialize, Deserializer, Serializer};
+    use utils::de::Error;
+
+    ///
+    /// The wire representation of an eval.
+    ///
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Repr 
+    {
+        Finite(f32),
+        Tagged(String)
+    }
+
+    pub fn serialize<S> (eval: & f32, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        if eval.is_infinite()
+        {
+            serializer.serialize_str(if eval.is_sign_positive() { "+inf" } else { "-inf" })
+        }
+        else 
+        {
+            serializer.serialize_f32(* eval)
+        }
+    }
+
+    pub fn deserialize<'de, D> (deserializer: D) -> Result<f32, D::Error>
+        where D: Deserializer<'de>
+    {
+        match Repr::deserialize(deserializer)?
+        {
+            Repr::Finite(eval) => Ok(eval),
+            Repr::Tagged(tag)  => match tag.as_str()
+            {
+                "+inf" | "inf" => Ok(f32::INFINITY),
+                "-inf"         => Ok(f32::NEG_INFINITY),
+                _              => Err(D::Error::custom(format!("Invalid eval tag '{}'.", tag)))
+            }
+        }
+    }
+}
+
 impl std::cmp::PartialOrd for SearcherStats
 {
     fn partial_cmp (& self, other: & SearcherStats) -> Option<std::cmp::Ordering>
@@ -47,6 +108,21 @@ impl std::cmp::PartialOrd for SearcherStats
     }
 }
 
+///
+/// A candidate move in the analysis of a search: its visits and mean prior over the roots of 
+/// every thread's tree, its mean eval in the perspective of the player to move, which is 
+/// infinite once the move is solved, and its principal variation, starting with the move.
+///
+#[derive(Clone, Debug)]
+pub struct Candidate 
+{
+    pub tetromino: Tetromino,
+    pub visits: f32,
+    pub prior: f32,
+    pub eval: f32,
+    pub pv: Vec<Tetromino>
+}
+
 ///
 /// The resource manager for the threads that make up an MCTS search pool.
 ///
@@ -57,20 +133,91 @@ pub struct ThreadPool
 
     pub state: Board,
     pub best_move: MoveID,
+    pub best_eval: f32,
+    pub visits: Vec<(MoveID, f32)>,
+    pub pv: Vec<Tetromino>,
+    pub rng: u64,
+    pub move_time_ms: usize,
+    pub hard_time_ms: usize,
+    pub started: std::time::Instant,
+    pub sampling: bool,
+    pub info: bool,
+    pub cancel: Arc<AtomicBool>,
 
     pub threads: Vec<UnsafeCell<* mut Searcher>>,
     pub handles: Vec<JoinHandle<()>>,
+    pub table: Arc<TranspositionTable>,
     
     pub cond: Arc<Latch>,
     pub stop: AtomicBool,
+    pub pondering: AtomicBool,
+    pub epoch: AtomicUsize
 }
 
 impl ThreadPool 
 {
+    ///
+    /// Returns up to the given number of the most visited moves at the combined roots of 
+    /// each thread's tree, as left by the last search, with their principal variations.
+    ///
+    pub fn analysis (& self, n: usize) -> Vec<Candidate>
+    {
+        // Alongside each candidate are its summed value and prior, and the number of trees 
+        // that visited it.
+
+        let mut movemap : HashMap<MoveID, (Candidate, f32, f32, f32)> = HashMap::new();
+
+        self.threads.iter()
+            .map(|handle| unsafe { & (** handle.get()) })
+            .for_each(
+                |thread|
+                {
+                    for child in thread.children_of_immut(thread.root).iter().filter(|child| child.n > 0.0)
+                    {
+                        let (candidate, value, prior, trees) = movemap.entry(child.in_action)
+                            .or_insert_with(|| (Candidate { tetromino: child.action(), visits: 0.0, prior: 0.0, eval: 0.0, pv: Vec::new() }, 0.0, 0.0, 0.0));
+
+                        candidate.visits += child.n;
+                        * value -= child.v;
+                        * prior += child.p;
+                        * trees += 1.0;
+
+                        if let Some(outcome) = child.outcome.map(|outcome| outcome.next())
+                        {
+                            candidate.eval = match outcome 
+                            {
+                                Outcome::Win(_)  => f32::INFINITY,
+                                Outcome::Loss(_) => f32::NEG_INFINITY
+                            };
+                        }
+                    }
+                }
+            );
+
+        let mut candidates = movemap.into_values()
+            .map(
+                |(mut candidate, value, prior, trees)|
+                {
+                    if candidate.eval.is_finite()
+                    {
+                        candidate.eval = value / candidate.visits;
+                    }
+                    candidate.prior = prior / trees;
+                    candidate.pv = self.principal_variation(candidate.tetromino.clone().into());
+                    candidate
+                }
+            )
+            .collect::<Vec<Candidate>>();
+
+        candidates.sort_by(|a, b| b.visits.total_cmp(& a.visits).then(b.eval.total_cmp(& a.eval)));
+        candidates.truncate(n);
+        candidates
+    }
+
     ///
     /// Attaches a new thread to the thread pool and starts it.
     ///
-    pub fn attach_one (& mut self, policy: & Network)
+    pub fn attach_one (& mut self, policy: & dyn Evaluator)
     {
         unsafe 
         {
@@ -82,7 +229,7 @@ impl ThreadPool
             let cond_variable = self.cond.clone();
             let pool : * mut ThreadPool = self;
 
-            ptr::write(searcher_ptr, Searcher::new(pool, self.config.clone(), policy, searcher_id, cond_variable));
+            ptr::write(searcher_ptr, Searcher::new(pool, self.config.clone(), policy, searcher_id, cond_variable, self.table.clone()));
 
             self.threads.push(UnsafeCell::new(searcher_ptr));
             let searcher_handle = SearcherHandle { ptr: UnsafeCell::new(searcher_ptr) };
@@ -96,7 +243,6 @@ impl ThreadPool
                     let s_handle = searcher_handle;
                     let thread = & mut ** s_handle.ptr.get();
                     log::debug!("Dereferenced search pointer.");
-                    thread.cond_variable.lock();
                     thread.idle();
                 }
             ).unwrap();
@@ -147,24 +293,74 @@ impl ThreadPool
     ///
     pub fn launch (& mut self, state: & Board) 
     {
-        log::info!("Search started on position '{}'.", state.notate());
+        self.begin(state);
+        self.wait_for_time();
+        self.conclude(state);
+    }
+
+    ///
+    /// Wakes every thread to search the given position, and returns once they have all started.
+    ///
+    pub fn begin (& mut self, state: & Board)
+    {
+        log::info!("Search started on position '{}' ({}).", state.notate(), state.fingerprint());
 
         self.set_stop_requirement(false);
+        self.started = std::time::Instant::now();
+        let epoch = self.epoch.fetch_add(1, Ordering::SeqCst) + 1;
 
         self.cond.set();
-        self.wait_for(SearcherEvent::Start);
 
-        thread::sleep(std::time::Duration::from_millis(self.config.mcts.max_time_ms as u64));
-        self.set_stop_requirement(true);
+        // A search may finish before the others start, so each searcher is waited on to 
+        // take up this search rather than to be observed searching.
 
-        self.cond.lock();
-        self.wait_for(SearcherEvent::Finish);
+        while self.threads.iter().any(|handle| unsafe { (** handle.get()).epoch.load(Ordering::SeqCst) } != epoch)
+        {
+            thread::yield_now();
+        }
+    }
+
+    ///
+    /// Combines the progress that each searcher has published into the move with the most 
+    /// visits over every tree, and its eval in the perspective of the player to move. The 
+    /// eval is the visit-weighted mean of the searchers' values, unless a searcher has solved 
+    /// the move, in which case it is the solved value.
+    ///
+    pub fn combine_progress (progress: & [Progress]) -> Option<(MoveID, f32)>
+    {
+        let mut moves : HashMap<MoveID, (f32, f32, Option<f32>)> = HashMap::new();
+        for entry in progress 
+        {
+            if let Some(best) = entry.best 
+            {
+                let (visits, value, solved) = moves.entry(best).or_insert((0.0, 0.0, None));
+                * visits += entry.visits;
+                match entry.value.is_infinite()
+                {
+                    true  => * solved = Some(entry.value),
+                    false => * value += entry.value * entry.visits
+                };
+            }
+        }
+
+        moves.into_iter()
+            .max_by(|(a, (x, _, _)), (b, (y, _, _))| x.total_cmp(y).then(b.cmp(a)))
+            .map(|(mv, (visits, value, solved))| (mv, solved.unwrap_or(value / visits)))
+    }
+
+    ///
+    /// Stops every thread, and collects the best move in the position from 
+    /// the combined roots of each thread's tree.
+    ///
+    pub fn conclude (& mut self, state: & Board)
+    {
+        self.halt();
 
         let mut movemap : HashMap<MoveID, SearcherStats> = HashMap::new();
         for mv in & self.state.enumerate_moves()
         {
             let id : usize = mv.clone().into();
-            movemap.insert(id, SearcherStats { tetromino: mv.notate(), visits: 0.0, prob: 0.0, eval: 0.0, components: 0 });
+            movemap.insert(id, SearcherStats { tetromino: mv.notate(), visits: 0.0, prob: 0.0, eval: 0.0, margin: 0.0, components: 0 });
         }
 
         self.threads.iter()
@@ -183,10 +379,15 @@ impl ThreadPool
                             entry.prob = ((entry.components as f32 * entry.prob) + child.p) / (entry.components as f32 + 1.0);
                             entry.components += 1;
 
-                            entry.eval = match child.outcome.unwrap() 
+                            // A child's outcome is in the perspective of its player to move, which 
+                            // is the opponent at the root.
+
+                            let outcome = child.outcome.unwrap().next();
+                            entry.margin = outcome.margin();
+                            entry.eval = match outcome 
                             {
-                                Outcome::Win  => f32::INFINITY,
-                                Outcome::Loss => f32::NEG_INFINITY
+                                Outcome::Win(_)  => f32::INFINITY,
+                                Outcome::Loss(_) => f32::NEG_INFINITY
                             };
                         }
                         else 
@@ -201,12 +402,41 @@ impl ThreadPool
             );
 
         let mut movevec = movemap.into_values().into_iter().collect::<Vec<SearcherStats>>();
-        movevec.sort_by(|a, b| std::primitive::f32::total_cmp(& b.eval, & a.eval));
+        movevec.sort_by(|a, b| std::primitive::f32::total_cmp(& b.eval, & a.eval).then(std::primitive::f32::total_cmp(& b.margin, & a.margin)));
+        if self.config.mcts.tiebreak_randomize 
+        {
+            self.tiebreak(& mut movevec);
+        }
+
+        // Early in a selfplay game, the move is sampled by visits for the sake of diversity.
+
+        let ply = state.num_coloured_tiles() / 4;
+        if self.sampling && ply < self.config.selfplay.temperature_moves 
+        {
+            self.sample(& mut movevec);
+        }
 
         self.best_move = Tetromino::parse(& movevec.first().unwrap().tetromino).unwrap().into();
+        self.best_eval = movevec.first().unwrap().eval;
+        self.visits = movevec.iter()
+            .map(|stats| (Tetromino::parse(& stats.tetromino).unwrap().into(), stats.visits))
+            .collect();
+        self.pv = self.principal_variation(self.best_move);
         self.print_move_table(& movevec);
 
-        log::info!("Search ended on position '{}'.", state.notate());
+        log::info!("Search ended on position '{}'.", state.fingerprint());
+    }
+
+    ///
+    /// Stops every thread and waits for them to finish, without collecting a move.
+    ///
+    pub fn halt (& mut self)
+    {
+        self.pondering.store(false, Ordering::SeqCst);
+        self.set_stop_requirement(true);
+
+        self.cond.lock();
+        self.wait_for(SearcherEvent::Finish);
     }
 
     ///
@@ -219,13 +449,26 @@ impl ThreadPool
             config: config.clone(),
             state: Board::blank(),
             best_move: 0,
+            best_eval: 0.0,
+            visits: Vec::new(),
+            pv: Vec::new(),
+            rng: config.seed,
+            move_time_ms: config.mcts.max_time_ms,
+            hard_time_ms: config.mcts.max_time_ms,
+            started: std::time::Instant::now(),
+            sampling: false,
+            info: false,
+            cancel: Arc::new(AtomicBool::new(false)),
 
             threads: Vec::new(),
             handles: Vec::new(),
+            table: Arc::new(TranspositionTable::new(config.mcts.tt_size)),
 
             cond: Arc::new(Latch::new()),
 
-            stop: AtomicBool::new(true)
+            stop: AtomicBool::new(true),
+            pondering: AtomicBool::new(false),
+            epoch: AtomicUsize::new(0)
         };
 
         // Lock all conditions.
@@ -235,33 +478,191 @@ impl ThreadPool
         pool
     }
 
+    ///
+    /// Advances the pool's seeded generator by one step of splitmix64, returning its output.
+    ///
+    pub fn next_random (& mut self) -> u64 
+    {
+        self.rng = self.rng.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.rng;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    ///
+    /// Starts a search of the given position that runs until it is concluded or halted, 
+    /// rather than for the configured time.
+    ///
+    pub fn ponder (& mut self, state: & Board)
+    {
+        self.pondering.store(true, Ordering::SeqCst);
+        self.begin(state);
+    }
+
+    ///
+    /// Converts an ongoing ponder into a real search: the search continues for the time 
+    /// limits set on the pool, counted from now, and then the best move is collected.
+    ///
+    pub fn ponder_hit (& mut self, state: & Board)
+    {
+        // The searchers time their search from the start of the ponder, so the limits are 
+        // extended by the time already pondered, which was spent on the opponent's clock.
+
+        let pondered = self.started.elapsed().as_millis() as usize;
+        self.move_time_ms += pondered;
+        self.hard_time_ms += pondered;
+        self.pondering.store(false, Ordering::SeqCst);
+
+        self.wait_for_time();
+        self.conclude(state);
+    }
+
+    ///
+    /// Returns the expected line after the given move at the root: the move itself, then the 
+    /// principal variation beneath it in the tree that visited it most.
+    ///
+    pub fn principal_variation (& self, mv: MoveID) -> Vec<Tetromino>
+    {
+        let best = self.threads.iter()
+            .map(|handle| unsafe { & (** handle.get()) })
+            .filter_map(|thread| thread.children_of_immut(thread.root).iter().find(|child| child.in_action == mv).map(|child| (thread, child)))
+            .filter(|(_, child)| child.n > 0.0)
+            .max_by(|(_, a), (_, b)| a.n.total_cmp(& b.n));
+
+        let mut line = vec![mv.into()];
+        if let Some((thread, child)) = best 
+        {
+            line.extend(thread.variation(child.id, MAX_DESCENT));
+        }
+        line
+    }
+
     ///
     /// Logs the move table formed by combining the roots of 
-    /// each thread's move pool.
+    /// each thread's move pool, followed by the principal variation.
     ///
     pub fn print_move_table (& self, movevec: & Vec<SearcherStats>)
     {
         let mut movevec = movevec.clone();
-        movevec.resize(20, SearcherStats { tetromino: "".to_owned(), eval: 0.0, prob: 0.0, visits: 0.0, components: 0 });
+        movevec.resize(20, SearcherStats { tetromino: "".to_owned(), eval: 0.0, prob: 0.0, visits: 0.0, margin: 0.0, components: 0 });
 
         let total_sims : usize = self.threads.iter()
             .map(|handle| unsafe { & (** handle.get()) })
             .map(|thread| thread.num_sims)
             .sum();
 
-        log::info!("MCTS eval table ({} simulations) for '{}':\n{}", total_sims, self.state.notate(), Table::new(movevec).with(tabled::Style::psql()).to_string());
+        let pv = self.pv.iter().map(|tetromino| tetromino.notate()).collect::<Vec<String>>().join(" ");
+
+        log::info!("MCTS eval table ({} simulations) for '{}':\n{}\nPrincipal variation: {}", total_sims, self.state.notate(), Table::new(movevec).with(tabled::Style::psql()).to_string(), pv);
     }
 
 
+    ///
+    /// Emits a line of the search's progress for a front-end, as in `progress_line`, if the 
+    /// searchers have published any progress.
+    ///
+    pub fn print_progress (& self, elapsed: std::time::Duration)
+    {
+        if let Some(line) = self.progress_line(elapsed)
+        {
+            println!("{}\n", line);
+        }
+    }
+
+    ///
+    /// Forms a line of the search's progress for a front-end, of the form 
+    /// '= 0 info move <m> eval <e> nodes <n> nps <r> time <ms>', combining the progress that
+    /// each searcher has published: the move is the one with the most visits over every tree,
+    /// its eval is in X's perspective, and the nodes are the simulations run so far. The id 
+    /// 0 is never used by a request, so the line cannot be mistaken for a response.
+    ///
+    pub fn progress_line (& self, elapsed: std::time::Duration) -> Option<String>
+    {
+        let progress = self.threads.iter()
+            .map(|handle| unsafe { * (** handle.get()).progress.lock().unwrap() })
+            .collect::<Vec<Progress>>();
+
+        ThreadPool::combine_progress(& progress).map(|(mv, value)| 
+        {
+            let nodes : usize = progress.iter().map(|entry| entry.sims).sum();
+            let nps = (nodes as f64 / elapsed.as_secs_f64().max(1.0e-3)) as usize;
+
+            // A solved eval is written as '+inf' or '-inf', as in eval_format.
+
+            let eval = match value * self.state.to_move().value() as f32
+            {
+                eval if eval.is_infinite() => (if eval.is_sign_positive() { "+inf" } else { "-inf" }).to_owned(),
+                eval                       => eval.to_string()
+            };
+
+            format!("= 0 info move {} eval {} nodes {} nps {} time {}", Tetromino::from(mv).notate(), eval, nodes, nps, elapsed.as_millis())
+        })
+    }
+
+    ///
+    /// Moves a move sampled in proportion to its visit count, raised to the inverse of the 
+    /// selfplay temperature, to the front of the sorted move list. Solved wins are always 
+    /// played, and solved losses are never sampled.
+    ///
+    pub fn sample (& mut self, movevec: & mut [SearcherStats])
+    {
+        let temperature = self.config.selfplay.temperature;
+        if temperature <= 0.0 || movevec.first().is_none_or(|best| best.eval == f32::INFINITY)
+        {
+            return;
+        }
+
+        let weights = movevec.iter()
+            .map(|stats| match stats.eval == f32::NEG_INFINITY
+            {
+                true  => 0.0,
+                false => (stats.visits as f64).powf(1.0 / temperature as f64)
+            })
+            .collect::<Vec<f64>>();
+
+        let total : f64 = weights.iter().sum();
+        if ! total.is_normal()
+        {
+            return;
+        }
+
+        let mut target = (self.next_random() >> 11) as f64 / (1u64 << 53) as f64 * total;
+        let mut choice = 0;
+        for (i, weight) in weights.iter().enumerate()
+        {
+            choice = i;
+            if target < * weight 
+            {
+                break;
+            }
+            target -= weight;
+        }
+
+        // Rounding may leave the walk past the last move with any weight.
+
+        while weights[choice] == 0.0 && choice > 0 
+        {
+            choice -= 1;
+        }
+
+        movevec.swap(0, choice);
+    }
+
     ///
     /// Unsafely sets the number of threads.
     ///
-    pub fn set_num_threads (& mut self, num: usize, policy: & Network)
+    pub fn set_num_threads (& mut self, num: usize, policy: & dyn Evaluator)
     {
         if num > 0 
         {
             self.wait_for(SearcherEvent::Finish);
             self.kill();
+
+            // Killing the threads sets the latch to wake them; it is locked again before the 
+            // new threads start, so that they wait for the next search.
+
+            self.cond.lock();
             for _ in 0 .. num 
             {
                 self.attach_one(policy);
@@ -277,6 +678,27 @@ impl ThreadPool
         self.stop.store(to, Ordering::SeqCst);
     }
 
+    ///
+    /// Moves a random choice among the moves tied with the best move to the front of the 
+    /// sorted move list; the choice is drawn from the pool's seeded generator, so that a 
+    /// given seed always makes the same choices.
+    ///
+    pub fn tiebreak (& mut self, movevec: & mut [SearcherStats])
+    {
+        let best = match movevec.first()
+        {
+            Some(best) => best.clone(),
+            None       => return
+        };
+
+        let tied = movevec.iter()
+            .take_while(|stats| (stats.eval == best.eval || (stats.eval - best.eval).abs() <= TIEBREAK_EPSILON) && stats.margin == best.margin)
+            .count();
+
+        let z = self.next_random();
+        movevec.swap(0, (z % tied as u64) as usize);
+    }
+
     ///
     /// Waits for the conditions of every thread matching the filter 
     /// to evaluate to the same value.
@@ -287,4 +709,92 @@ impl ThreadPool
             .map(|handle| unsafe { & (** handle.get()) })
             .for_each(|thread| { thread.search_status.wait(event.clone().into()); });
     }
+
+    ///
+    /// Sleeps until the search time of this move, up to its hard limit, has passed since the 
+    /// search started, waking early if the searchers stop on their own, for example by 
+    /// settling on a move after the soft limit, exhausting a budget or solving the position. 
+    /// With search info on, the progress of the search is reported at intervals meanwhile. A 
+    /// cancellation, which may be raised from another thread, stops the search at once, and is 
+    /// consumed by doing so.
+    ///
+    pub fn wait_for_time (& mut self)
+    {
+        let allowed_duration = std::time::Duration::from_millis(self.hard_time_ms.max(self.move_time_ms) as u64);
+        let start = self.started;
+        let mut last_info = std::time::Instant::now();
+
+        while ! self.stop.load(Ordering::SeqCst) && start.elapsed() < allowed_duration
+        {
+            if self.cancel.swap(false, Ordering::SeqCst)
+            {
+                log::info!("Search cancelled after {} millis.", start.elapsed().as_millis());
+                self.set_stop_requirement(true);
+                break;
+            }
+
+            thread::sleep(std::time::Duration::from_millis(1));
+
+            if self.info && last_info.elapsed() >= INFO_INTERVAL 
+            {
+                self.print_progress(start.elapsed());
+                last_info = std::time::Instant::now();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests 
+{
+    use super::*;
+
+    ///
+    /// Returns the move a pool with the given seed puts first among eight moves tied on eval.
+    ///
+    fn tiebreak_choice (seed: u64) -> String 
+    {
+        let mut config : Config = toml::from_str("").unwrap();
+        config.seed = seed;
+
+        let mut movevec = (0 .. 8)
+            .map(|i| SearcherStats { tetromino: format!("m{}", i), visits: 10.0, prob: 0.125, eval: 0.5, margin: 0.0, components: 0 })
+            .collect::<Vec<SearcherStats>>();
+
+        let mut pool = ThreadPool::new(& config);
+        pool.tiebreak(& mut movevec);
+        movevec[0].tetromino.clone()
+    }
+
+    #[test]
+    fn eval_format_round_trips_solved_evals ()
+    {
+        let stats = [f32::INFINITY, f32::NEG_INFINITY, 0.25]
+            .map(|eval| SearcherStats { tetromino: "L[00,01,02,10]".to_owned(), visits: 10.0, prob: 0.5, eval, margin: 3.0, components: 1 });
+
+        let json = serde_json::to_string(& stats).unwrap();
+        assert!(json.contains("\"+inf\"") && json.contains("\"-inf\""));
+        assert_eq!(serde_json::from_str::<Vec<SearcherStats>>(& json).unwrap(), stats);
+    }
+
+    #[test]
+    fn progress_keeps_solved_evals ()
+    {
+        let progress = |best, visits, value| Progress { best: Some(best), visits, value, sims: 0 };
+
+        let solved = [progress(3, 10.0, f32::INFINITY), progress(3, 30.0, 0.25), progress(5, 20.0, 0.5)];
+        assert_eq!(ThreadPool::combine_progress(& solved), Some((3, f32::INFINITY)));
+
+        let unsolved = [progress(3, 10.0, 0.5), progress(3, 30.0, 0.25), progress(5, 20.0, f32::NEG_INFINITY)];
+        assert_eq!(ThreadPool::combine_progress(& unsolved), Some((3, 0.3125)));
+    }
+
+    #[test]
+    fn tiebreak_depends_only_on_seed ()
+    {
+        (0 .. 16).for_each(|seed| assert_eq!(tiebreak_choice(seed), tiebreak_choice(seed)));
+
+        let choices = (0 .. 16).map(tiebreak_choice).collect::<std::collections::BTreeSet<String>>();
+        assert!(choices.len() > 1);
+    }
 }
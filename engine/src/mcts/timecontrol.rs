@@ -0,0 +1,112 @@
+use lits::{Board, Colour};
+
+use super::config::Config;
+
+///
+/// The least time kept in reserve on the clock, so that a move is never sent too late.
+///
+pub const SAFETY_MARGIN_MS : usize = 50;
+
+///
+/// How many times the soft limit a move may search for on the clock, if its best move 
+/// is still unsettled when the soft limit passes.
+///
+pub const HARD_FACTOR : usize = 3;
+
+///
+/// The time limits of a move: the search stops at the soft limit if its best move is 
+/// settled, and otherwise at the hard limit.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Budget 
+{
+    pub soft_ms: usize,
+    pub hard_ms: usize
+}
+
+///
+/// The engine's game clock, which divides the remaining time between the moves the 
+/// engine is still expected to make. Without a clock, every move searches for the 
+/// configured maximum time, and up to the configured hard time if it is unsettled.
+///
+#[derive(Clone, Copy, Debug)]
+pub struct TimeControl 
+{
+    pub remaining_ms: Option<usize>,
+    pub increment_ms: usize,
+    pub max_time_ms: usize,
+    pub hard_time_ms: usize
+}
+
+impl TimeControl 
+{
+    ///
+    /// Returns the time limits of a search of the given position. The remaining time is split 
+    /// evenly over the engine's remaining moves, which is half of the pieces left, so that 
+    /// moves get more time as the game goes on; the increment is spent in full. The soft limit 
+    /// never exceeds the configured maximum time, and the hard limit never exceeds the configured 
+    /// hard time; neither exceeds the time on the clock.
+    ///
+    pub fn budget (& self, position: & Board) -> Budget 
+    {
+        let hard_cap = self.hard_time_ms.max(self.max_time_ms);
+
+        let remaining = match self.remaining_ms 
+        {
+            Some(remaining) => remaining,
+            None            => return Budget { soft_ms: self.max_time_ms, hard_ms: hard_cap }
+        };
+
+        let pieces : usize = [Colour::L, Colour::I, Colour::T, Colour::S].iter().map(|colour| position.remaining_of(colour)).sum();
+        let moves_left = pieces.div_ceil(2).max(1);
+
+        let usable = remaining.saturating_sub(SAFETY_MARGIN_MS);
+        let soft_ms = (usable / moves_left + self.increment_ms).min(usable).min(self.max_time_ms).max(1);
+        let hard_ms = (soft_ms * HARD_FACTOR).min(usable).min(hard_cap).max(soft_ms);
+
+        Budget { soft_ms, hard_ms }
+    }
+
+    ///
+    /// Records a move that took the given time, debiting the clock and crediting the increment.
+    ///
+    pub fn debit (& mut self, elapsed_ms: usize)
+    {
+        if let Some(remaining) = self.remaining_ms.as_mut()
+        {
+            * remaining = remaining.saturating_sub(elapsed_ms) + self.increment_ms;
+        }
+    }
+
+    ///
+    /// Creates the clock described by the given configuration.
+    ///
+    pub fn new (config: & Config) -> TimeControl 
+    {
+        let remaining_ms = match config.clock_ms 
+        {
+            0     => None,
+            clock => Some(clock)
+        };
+
+        TimeControl { remaining_ms, increment_ms: config.increment_ms, max_time_ms: config.max_time_ms, hard_time_ms: config.hard_time_ms }
+    }
+
+    ///
+    /// Sets the time remaining on the clock, as reported by the controller.
+    ///
+    pub fn set_remaining (& mut self, remaining_ms: usize)
+    {
+        self.remaining_ms = Some(remaining_ms);
+    }
+
+    ///
+    /// Sets the time control of the game: the main time on the clock, of which none means an 
+    /// untimed game, and the increment credited after each move.
+    ///
+    pub fn set_settings (& mut self, main_ms: Option<usize>, increment_ms: usize)
+    {
+        self.remaining_ms = main_ms;
+        self.increment_ms = increment_ms;
+    }
+}
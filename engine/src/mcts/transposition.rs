@@ -0,0 +1,207 @@
+
+use std::sync::{Arc, Mutex};
+
+use super::node::{MoveID, Outcome};
+
+///
+/// The number of independently-locked shards in a table, so that searchers on different
+/// threads rarely wait on each other.
+///
+pub const NUM_SHARDS : usize = 64;
+
+///
+/// The merged statistics of every node, in every searcher's tree, that holds the same
+/// position, along with its outcome once some searcher has solved it.
+///
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Entry
+{
+    pub hash: u64,
+    pub checksum: u32,
+    pub n: f32,
+    pub v: f32,
+    pub outcome: Option<Outcome>
+}
+
+///
+/// The evaluator's prediction for a position: the prior of each legal move, and the value 
+/// in the perspective of the player to move.
+///
+#[derive(Clone, Debug, Default)]
+pub struct Prediction 
+{
+    pub priors: Vec<(MoveID, f32)>,
+    pub value: f32
+}
+
+///
+/// A slot of a table: the statistics of a position, with the evaluator's prediction for it 
+/// once some searcher has expanded it. The prediction is kept apart from the statistics so 
+/// that reading the statistics stays cheap.
+///
+#[derive(Clone, Debug)]
+struct Slot 
+{
+    entry: Entry,
+    prediction: Option<Arc<Prediction>>
+}
+
+///
+/// A fixed-size table of position statistics, indexed by Zobrist hash, so that a position
+/// reached by different move orders, or by different searchers, shares its statistics, 
+/// and is only evaluated once. A slot holds one position at a time; a colliding position replaces it.
+///
+/// The table is shared between the searchers of a pool; its slots are split into shards,
+/// each behind its own lock.
+///
+#[derive(Debug)]
+pub struct TranspositionTable
+{
+    shards: Vec<Mutex<Vec<Option<Slot>>>>,
+    per_shard: usize
+}
+
+impl TranspositionTable
+{
+    ///
+    /// Forgets every position in this table.
+    ///
+    pub fn clear (& self)
+    {
+        for shard in & self.shards
+        {
+            shard.lock().unwrap().iter_mut().for_each(|entry| * entry = None);
+        }
+    }
+
+    ///
+    /// Returns the statistics of the given position, if it is in the table.
+    ///
+    pub fn get (& self, hash: u64, checksum: u32) -> Option<Entry>
+    {
+        let (shard, slot) = self.index(hash)?;
+        let shard = self.shards[shard].lock().unwrap();
+
+        shard[slot].as_ref().map(|slot| slot.entry).filter(|entry| entry.hash == hash && entry.checksum == checksum)
+    }
+
+    ///
+    /// Returns the shard and the slot within it of the given hash, or None if the table
+    /// has no slots.
+    ///
+    fn index (& self, hash: u64) -> Option<(usize, usize)>
+    {
+        match self.per_shard
+        {
+            0 => None,
+            _ => Some(((hash % NUM_SHARDS as u64) as usize, ((hash / NUM_SHARDS as u64) % self.per_shard as u64) as usize))
+        }
+    }
+
+    ///
+    /// Applies the given change to the entry of the given position, starting from empty
+    /// statistics if the position is not in the table.
+    ///
+    fn modify<F> (& self, hash: u64, checksum: u32, change: F)
+        where F: FnOnce (& mut Slot)
+    {
+        let (shard, slot) = match self.index(hash)
+        {
+            Some(index) => index,
+            None        => return
+        };
+        let mut shard = self.shards[shard].lock().unwrap();
+
+        let mut entry = match shard[slot].take()
+        {
+            Some(entry) if entry.entry.hash == hash && entry.entry.checksum == checksum => entry,
+            _ => Slot { entry: Entry { hash, checksum, ..Entry::default() }, prediction: None }
+        };
+        change(& mut entry);
+
+        shard[slot] = Some(entry);
+    }
+
+    ///
+    /// Creates a table with (about) the given number of slots; a table without slots stores nothing.
+    ///
+    pub fn new (size: usize) -> TranspositionTable
+    {
+        let per_shard = size.div_ceil(NUM_SHARDS);
+        TranspositionTable { shards: (0 .. NUM_SHARDS).map(|_| Mutex::new(vec![None; per_shard])).collect(), per_shard }
+    }
+
+    ///
+    /// Returns the evaluator's prediction for the given position, if it is in the table.
+    ///
+    pub fn prediction (& self, hash: u64, checksum: u32) -> Option<Arc<Prediction>>
+    {
+        let (shard, slot) = self.index(hash)?;
+        let shard = self.shards[shard].lock().unwrap();
+
+        shard[slot].as_ref()
+            .filter(|slot| slot.entry.hash == hash && slot.entry.checksum == checksum)
+            .and_then(|slot| slot.prediction.clone())
+    }
+
+    ///
+    /// Records the outcome of the given position, which some searcher has solved.
+    ///
+    pub fn solve (& self, hash: u64, checksum: u32, outcome: Outcome)
+    {
+        self.modify(hash, checksum, |slot| slot.entry.outcome = Some(outcome));
+    }
+
+    ///
+    /// Records the evaluator's prediction for the given position, so that no searcher needs 
+    /// to evaluate it again.
+    ///
+    pub fn store_prediction (& self, hash: u64, checksum: u32, prediction: Prediction)
+    {
+        self.modify(hash, checksum, |slot| slot.prediction = Some(Arc::new(prediction)));
+    }
+
+    ///
+    /// Adds a visit with the given value to the statistics of the given position.
+    ///
+    pub fn update (& self, hash: u64, checksum: u32, value: f32)
+    {
+        self.modify(hash, checksum, |slot| { slot.entry.n += 1.0; slot.entry.v += value; });
+    }
+}
+
+#[cfg(test)]
+mod tests 
+{
+    use super::*;
+
+    use crate::mcts::node::Node;
+
+    use lits::{Board, Tetromino};
+
+    #[test]
+    fn checksum_distinguishes_hash_collision ()
+    {
+        Tetromino::initialize();
+
+        let a = Board::blank();
+        let mut b = Board::blank();
+        b.place_tetromino(& a.enumerate_moves().into_iter().next().unwrap()).unwrap();
+        assert_ne!(a.checksum(), b.checksum());
+
+        // Force the two boards onto the same hash.
+
+        let hash = a.zobrist_hash();
+        let node = Node::new(0, None, hash, a.checksum(), None, 0, 0.0);
+        assert!(node.matches(hash, a.checksum()));
+        assert!(! node.matches(hash, b.checksum()));
+
+        let table = TranspositionTable::new(NUM_SHARDS);
+        table.update(hash, a.checksum(), 1.0);
+        assert!(table.get(hash, a.checksum()).is_some());
+        assert!(table.get(hash, b.checksum()).is_none());
+
+        table.update(hash, b.checksum(), - 1.0);
+        assert_eq!(table.get(hash, b.checksum()).map(|entry| entry.n), Some(1.0));
+    }
+}
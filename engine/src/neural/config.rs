@@ -1,5 +1,6 @@
 
 use utils::{Serialize, Deserialize};
+use utils::error::*;
 
 ///
 /// A configuration for the neural network policy agent.
@@ -19,6 +20,9 @@ pub struct Config
     #[serde(default = "best")]
     pub best: String,
 
+    #[serde(default = "ensemble")]
+    pub ensemble: Vec<String>,
+
     #[serde(default = "learning_rate")]
     pub learning_rate: f32,
 
@@ -26,7 +30,40 @@ pub struct Config
     pub exp: f32,
 
     #[serde(default = "epochs")]
-    pub epochs: i32
+    pub epochs: i32,
+
+    #[serde(default = "device")]
+    pub device: String,
+
+    #[serde(default = "checkpoint_every_epochs")]
+    pub checkpoint_every_epochs: i32,
+
+    #[serde(default = "grad_clip")]
+    pub grad_clip: Option<f64>,
+
+    #[serde(default = "batch_size")]
+    pub batch_size: usize,
+
+    #[serde(default = "batch_wait_us")]
+    pub batch_wait_us: u64,
+
+    #[serde(default = "replay_dir")]
+    pub replay_dir: String,
+
+    #[serde(default = "replay_capacity")]
+    pub replay_capacity: usize,
+
+    #[serde(default = "replay_shard_size")]
+    pub replay_shard_size: usize,
+
+    #[serde(default = "train_samples")]
+    pub train_samples: usize,
+
+    #[serde(default = "train_batch_size")]
+    pub train_batch_size: usize,
+
+    #[serde(default = "augment_symmetries")]
+    pub augment_symmetries: bool
 }
 
 impl Default for Config 
@@ -39,13 +76,56 @@ impl Default for Config
             template: template(),
             use_best: use_best(),
             best: best(),
+            ensemble: ensemble(),
             learning_rate: learning_rate(),
             exp: loss_exp(),
-            epochs: epochs()
+            epochs: epochs(),
+            device: device(),
+            checkpoint_every_epochs: checkpoint_every_epochs(),
+            grad_clip: grad_clip(),
+            batch_size: batch_size(),
+            batch_wait_us: batch_wait_us(),
+            replay_dir: replay_dir(),
+            replay_capacity: replay_capacity(),
+            replay_shard_size: replay_shard_size(),
+            train_samples: train_samples(),
+            train_batch_size: train_batch_size(),
+            augment_symmetries: augment_symmetries()
         }
     }
 }
 
+impl Config 
+{
+    ///
+    /// Checks that this configuration describes a trainable network.
+    ///
+    pub fn validate (& self) -> Result<()>
+    {
+        if self.learning_rate.is_nan() || self.learning_rate <= 0.0
+        {
+            return Err(error!("neural.learning_rate must be positive, but is {}.", self.learning_rate));
+        }
+
+        if self.epochs < 1 
+        {
+            return Err(error!("neural.epochs must be at least 1, but is {}.", self.epochs));
+        }
+
+        if self.batch_size < 1 
+        {
+            return Err(error!("neural.batch_size must be at least 1, but is {}.", self.batch_size));
+        }
+
+        if self.train_batch_size < 1 
+        {
+            return Err(error!("neural.train_batch_size must be at least 1, but is {}.", self.train_batch_size));
+        }
+
+        Ok(())
+    }
+}
+
 fn path () -> String 
 {
     "models".to_owned()
@@ -66,6 +146,11 @@ fn best () -> String
     "best.pt".to_owned()
 }
 
+fn ensemble () -> Vec<String> 
+{
+    Vec::new()
+}
+
 fn learning_rate () -> f32 
 {
     0.00001
@@ -80,3 +165,58 @@ fn epochs () -> i32
 {
     20
 }
+
+fn device () -> String 
+{
+    "auto".to_owned()
+}
+
+fn checkpoint_every_epochs () -> i32 
+{
+    0
+}
+
+fn grad_clip () -> Option<f64> 
+{
+    None
+}
+
+fn batch_size () -> usize 
+{
+    1
+}
+
+fn batch_wait_us () -> u64 
+{
+    200
+}
+
+fn replay_dir () -> String 
+{
+    "replay".to_owned()
+}
+
+fn replay_capacity () -> usize 
+{
+    1 << 20
+}
+
+fn replay_shard_size () -> usize 
+{
+    4096
+}
+
+fn train_samples () -> usize 
+{
+    8192
+}
+
+fn train_batch_size () -> usize 
+{
+    64
+}
+
+fn augment_symmetries () -> bool 
+{
+    true
+}
@@ -0,0 +1,112 @@
+
+use crate::mcts::evaluator::Evaluator;
+
+use lits::Board;
+use lits::tetromino::TETROMINO_RANGE;
+
+///
+/// A collection of evaluators, normally networks, whose predictions are averaged.
+///
+/// Each member's policy is renormalized over the legal moves before
+/// averaging, so that members with differently-scaled outputs contribute
+/// equally to the combined policy.
+///
+#[derive(Debug)]
+pub struct Ensemble
+{
+    members: Vec<Box<dyn Evaluator>>
+}
+
+impl Evaluator for Ensemble
+{
+    fn predict (& self, board: & Board) -> ([f32; TETROMINO_RANGE], f32)
+    {
+        Ensemble::predict(self, board)
+    }
+
+    fn copy (& self) -> Box<dyn Evaluator>
+    {
+        Box::new(Ensemble::copy(self))
+    }
+}
+
+impl Ensemble
+{
+    ///
+    /// Creates an exact copy of this ensemble.
+    ///
+    pub fn copy (& self) -> Ensemble
+    {
+        Ensemble { members: self.members.iter().map(|member| member.copy()).collect() }
+    }
+
+    ///
+    /// Creates a new ensemble from the given evaluators.
+    ///
+    pub fn new (members: Vec<Box<dyn Evaluator>>) -> Ensemble
+    {
+        Ensemble { members }
+    }
+
+    ///
+    /// Given an input board, returns the averaged policy vector and value estimation.
+    ///
+    pub fn predict (& self, board: & Board) -> ([f32; TETROMINO_RANGE], f32)
+    {
+        let mut policy = [0.0; TETROMINO_RANGE];
+        let mut value = 0.0;
+
+        if self.members.is_empty()
+        {
+            return (policy, value);
+        }
+
+        let weight = 1.0 / self.members.len() as f32;
+
+        for member in & self.members
+        {
+            let (member_policy, member_value) = member.predict(board);
+
+            // The member policy is already masked against the legal moves, so
+            // normalizing over its sum normalizes over the legal moves.
+
+            let total : f32 = member_policy.iter().sum();
+            if total > 0.0
+            {
+                for (combined, p) in policy.iter_mut().zip(member_policy.iter())
+                {
+                    * combined += weight * p / total;
+                }
+            }
+
+            value += weight * member_value;
+        }
+
+        (policy, value)
+    }
+}
+
+#[cfg(test)]
+mod tests 
+{
+    use super::*;
+
+    use crate::mcts::evaluator::tests::Uniform;
+
+    use lits::Tetromino;
+
+    #[test]
+    fn identical_members_match_a_single_model ()
+    {
+        Tetromino::initialize();
+
+        let board = Board::blank();
+        let ensemble = Ensemble::new(vec![Box::new(Uniform), Box::new(Uniform)]);
+
+        let (single_policy, single_value) = Uniform.predict(& board);
+        let (policy, value) = ensemble.predict(& board);
+
+        assert!((value - single_value).abs() < 1.0e-6);
+        assert!(policy.iter().zip(single_policy.iter()).all(|(p, q)| (p - q).abs() < 1.0e-6));
+    }
+}
@@ -6,14 +6,15 @@ use tch::Tensor;
 ///
 /// Represents a core memory of (si, pi, z0).
 ///
-/// The policy is trained against the mask, and the value is 
-/// trained against the end result of the game.
+/// The policy is trained against the root visit distribution of 
+/// the search in this position, and the value is trained against 
+/// the end result of the game.
 ///
 #[derive(Debug)]
 pub struct Memory 
 {
     pub board: Board,
-    pub policy_valid: Tensor,
+    pub policy: Tensor,
     pub end_result: Tensor
 }
 
@@ -1,6 +1,15 @@
 
 pub mod config;
+#[cfg(feature = "neural")]
+pub mod ensemble;
+#[cfg(feature = "neural")]
 pub mod input;
+#[cfg(feature = "neural")]
 pub mod memory;
+#[cfg(feature = "neural")]
 pub mod network;
+#[cfg(feature = "neural")]
+pub mod replay;
+#[cfg(feature = "neural")]
+pub mod server;
 
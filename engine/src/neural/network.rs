@@ -1,18 +1,61 @@
 
 use crate::config::*;
+use crate::mcts::evaluator::Evaluator;
 
 use lits::board::Board;
 use lits::outcome::Outcome;
 use lits::tetromino::{Tetromino, TETROMINO_RANGE};
 
+use super::ensemble::Ensemble;
 use super::input::*;
-use super::memory::*;
+use super::replay::{ReplayBuffer, ReplayEntry, ReplayPosition};
 
-use tch::{Device, IndexOp, Tensor};
+use std::path::Path;
+
+use tch::{Device, IndexOp, Kind, Tensor};
 use tch::jit::{IValue, TrainableCModule};
-use tch::nn::{OptimizerConfig, Sgd, VarStore};
+use tch::nn::{Optimizer, OptimizerConfig, Sgd, VarStore};
 
 use utils::error::{error, Context, Result};
+use utils::{Serialize, Deserialize, log};
+use utils::notate::Notate;
+
+///
+/// The average losses over one epoch of training.
+///
+#[derive(Clone, Copy, Debug, Default, Serialize)]
+pub struct EpochStats 
+{
+    pub epoch: i32,
+    pub policy_loss: f64,
+    pub value_loss: f64
+}
+
+///
+/// The result of a training run: the number of memories trained on, the losses of the final 
+/// epoch, and the average losses of every epoch.
+///
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct TrainStats 
+{
+    pub samples: usize,
+    pub policy_loss: f64,
+    pub value_loss: f64,
+    pub steps: usize,
+    pub epochs: Vec<EpochStats>
+}
+
+///
+/// The state of a network's optimizer that persists between training runs. The optimizer is 
+/// plain SGD without momentum, which keeps no per-parameter state, so its learning rate and 
+/// the number of steps taken describe it completely.
+///
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct OptimizerState 
+{
+    pub learning_rate: f32,
+    pub steps: usize
+}
 
 ///
 /// A network that functions simultaneously as a policy and state head.
@@ -36,7 +79,20 @@ pub struct Network
     config: NeuralConfig,
     vs: VarStore,
     model: TrainableCModule,
-    mem: Vec<Memory>
+    replay: Option<ReplayBuffer>
+}
+
+impl Evaluator for Network
+{
+    fn predict (& self, board: & Board) -> ([f32; TETROMINO_RANGE], f32)
+    {
+        Network::predict(self, board)
+    }
+
+    fn copy (& self) -> Box<dyn Evaluator>
+    {
+        Box::new(Network::copy(self))
+    }
 }
 
 impl Network 
@@ -55,6 +111,28 @@ impl Network
         return Tetromino::from(indices[0] as usize);
     }
 
+    ///
+    /// Returns the name of the checkpoint saved after the given epoch of training, or none 
+    /// if checkpointing is disabled or not due after that epoch.
+    ///
+    fn checkpoint_name (config: & NeuralConfig, epoch: i32) -> Option<String>
+    {
+        let every = config.checkpoint_every_epochs;
+        match every > 0 && epoch % every == 0 
+        {
+            true  => Some(format!("checkpoint-{}.pt", epoch)),
+            false => None
+        }
+    }
+
+    ///
+    /// Returns this network's configuration.
+    ///
+    pub fn config (& self) -> & NeuralConfig 
+    {
+        & self.config
+    }
+
     ///
     /// Creates an exact copy of this network.
     ///
@@ -62,7 +140,7 @@ impl Network
     {
         let config = self.config.clone();
         
-        let mut vs = VarStore::new(Device::cuda_if_available());
+        let mut vs = VarStore::new(self.device());
         vs.copy(& self.vs).unwrap();
 
         let tmp_dir = tempfile::tempdir().unwrap();
@@ -70,9 +148,29 @@ impl Network
         self.model.save(& tmp_path).unwrap();
         let model = TrainableCModule::load(& tmp_path, vs.root()).unwrap();
 
-        let mem = Vec::new();
+        Network { config, vs, model, replay: None }
+    }
+
+    ///
+    /// Returns the device this network's weights live on.
+    ///
+    pub fn device (& self) -> Device 
+    {
+        self.vs.device()
+    }
 
-        Network { config, vs, model, mem }
+    ///
+    /// Creates an ensemble by loading each of the given artifact files with 
+    /// its corresponding configuration.
+    ///
+    pub fn ensemble (configs_and_artifacts: & [(NeuralConfig, String)]) -> Result<Ensemble>
+    {
+        let mut members = Vec::with_capacity(configs_and_artifacts.len());
+        for (config, artifact) in configs_and_artifacts
+        {
+            members.push(Box::new(Network::from_artifact(config, artifact)?) as Box<dyn Evaluator>);
+        }
+        Ok(Ensemble::new(members))
     }
 
     ///
@@ -80,7 +178,7 @@ impl Network
     ///
     pub fn forward (& self, input: Tensor) -> (Tensor, Tensor)
     {
-        let ivalue_tuple = self.model.forward_is(& [IValue::from(input)]).unwrap();
+        let ivalue_tuple = self.model.forward_is(& [IValue::from(input.to_device(self.device()))]).unwrap();
         let (policy, values) = <(Tensor, Tensor)>::try_from(ivalue_tuple).unwrap();
         (policy, values)
     }
@@ -90,15 +188,8 @@ impl Network
     ///
     pub fn from_artifact (config: & NeuralConfig, artifact: & str) -> Result<Network>
     {
-        let vs = VarStore::new(Device::cuda_if_available());
-        let mem = vec![];
-        let artifact_path = std::env::current_dir()?.join(& config.path).join("trained").join(& artifact).to_str().unwrap().to_owned();
-        let model = tch::TrainableCModule::load(& artifact_path, vs.root()).context(format!("Failed to load model file from '{}'.", & artifact_path))?;
-
-        let mut net = Network { config: config.clone(), vs, model, mem };
-        net.model.set_eval();
-
-        Ok(net)
+        let artifact_path = std::env::current_dir()?.join(& config.path).join("trained").join(& artifact);
+        Network::from_file(config, & artifact_path)
     }
 
     ///
@@ -109,17 +200,31 @@ impl Network
         Network::from_artifact(config, & config.best)
     }
 
+    ///
+    /// Creates a network by loading the model file at the given path.
+    ///
+    pub fn from_file (config: & NeuralConfig, path: & Path) -> Result<Network>
+    {
+        let vs = VarStore::new(Network::select_device(config)?);
+        let artifact_path = path.to_str().unwrap().to_owned();
+        let model = tch::TrainableCModule::load(& artifact_path, vs.root()).context(format!("Failed to load model file from '{}'.", & artifact_path))?;
+
+        let mut net = Network { config: config.clone(), vs, model, replay: None };
+        net.model.set_eval();
+
+        Ok(net)
+    }
+
     ///
     /// Creates a brand-new network from the template file.
     ///
     pub fn from_template (config: & NeuralConfig) -> Result<Network> 
     {
-        let vs = VarStore::new(Device::cuda_if_available());
-        let mem = vec![];
+        let vs = VarStore::new(Network::select_device(config)?);
         let template_path = std::env::current_dir()?.join(& config.path).join(& config.template).to_str().unwrap().to_owned();
         let model = tch::TrainableCModule::load(& template_path, vs.root()).context(format!("Failed to load template file from '{}'.", & template_path))?;
 
-        let mut net = Network { config: config.clone(), vs, model, mem };
+        let mut net = Network { config: config.clone(), vs, model, replay: None };
         net.model.set_eval();
 
         Ok(net)
@@ -137,48 +242,66 @@ impl Network
     ///
     pub fn predict (& self, board: & Board) -> ([f32; TETROMINO_RANGE], f32)
     {
-        let input : Tensor = Input::from(board.clone()).0;
-        let (policy, values) = self.forward(input);
-
-        // Extract the policy data by masking it against the set of valid 
-        // moves in this state.
-
-        let mut mask : [f32; TETROMINO_RANGE] = [0.0; TETROMINO_RANGE];
-        for tetromino in board.enumerate_moves()
-        {
-            let idx = <lits::Tetromino as Into<usize>>::into(tetromino.clone());
-            mask[idx] = 1.0;
-        }
-        
-        let mut policy_data = [0.0; TETROMINO_RANGE];
-        policy.copy_data::<f32>(& mut policy_data, TETROMINO_RANGE);
+        self.predict_batch(std::slice::from_ref(board)).pop().unwrap()
+    }
 
-        for i in 0 .. TETROMINO_RANGE 
+    ///
+    /// Given a batch of input boards, returns the policy vector and value estimation of 
+    /// each, in order, from a single forward pass.
+    ///
+    pub fn predict_batch (& self, boards: & [Board]) -> Vec<([f32; TETROMINO_RANGE], f32)>
+    {
+        if boards.is_empty()
         {
-            policy_data[i] *= mask[i];
+            return Vec::new();
         }
 
-        // Extract the value prediction. 
-        
-        let mut value_data = [0.0; 1];
-        values.copy_data::<f32>(& mut value_data, 1);
-        let value = value_data[0];
+        let inputs = boards.iter().map(|board| Input::from(board.clone()).0).collect::<Vec<Tensor>>();
+        let (policy, values) = self.forward(Tensor::cat(& inputs, 0));
 
-        (policy_data as [f32; TETROMINO_RANGE], value)
+        boards.iter().enumerate().map(
+            |(row, board)|
+            {
+                // Extract the policy data by masking it against the set of valid 
+                // moves in this state.
+
+                let mut mask : [f32; TETROMINO_RANGE] = [0.0; TETROMINO_RANGE];
+                for tetromino in board.enumerate_moves()
+                {
+                    let idx = <lits::Tetromino as Into<usize>>::into(tetromino.clone());
+                    mask[idx] = 1.0;
+                }
+                
+                let mut policy_data = [0.0; TETROMINO_RANGE];
+                policy.i(row as i64).copy_data::<f32>(& mut policy_data, TETROMINO_RANGE);
+
+                for i in 0 .. TETROMINO_RANGE 
+                {
+                    policy_data[i] *= mask[i];
+                }
+
+                // Extract the value prediction. 
+                
+                let mut value_data = [0.0; 1];
+                values.i(row as i64).copy_data::<f32>(& mut value_data, 1);
+                let value = value_data[0];
+
+                (policy_data, value)
+            }
+        ).collect()
     }
 
     ///
-    /// Constructs and remembers a memory. The memory is stored in terms 
-    /// of the moving player's perspective. In other words, the input 
+    /// Constructs and remembers a memory in the replay buffer, whose policy 
+    /// target is the given sparse visit distribution. The memory is stored in 
+    /// terms of the moving player's perspective. In other words, the input 
     /// tensor sets player tiles of that player to 1 and opposing tiles to 
     /// -1, and the end result is 1 if and only if the optimizing player 
     /// won the game.
     ///
-    pub fn remember (& mut self, board: & Board, result: & Outcome)
+    pub fn remember (& mut self, board: & Board, policy: & [(usize, f32)], result: & Outcome) -> Result<()>
     {
-        let mut mask = [0.0; TETROMINO_RANGE];
-        board.enumerate_moves().iter().for_each(|t| { mask[<Tetromino as Into::<usize>>::into(t.clone())] = 1.0; } );
-        let policy_valid = Tensor::of_slice::<f32>(& mask);
+        let policy = policy.to_vec();
 
         let val = match result 
         {
@@ -186,10 +309,44 @@ impl Network
             Outcome::O (_) => -1.0,
             _              => 0.0,
         };
-        let end_result = Tensor::of_slice::<f32>(& [val]) * board.to_move().value();
+        let value = val * board.to_move().value() as f32;
 
-        let memory = Memory { board: board.clone(), policy_valid, end_result };
-        self.mem.push(memory);
+        self.replay()?.push(ReplayEntry { board: board.notate(), policy, value })
+    }
+
+    ///
+    /// Returns this network's replay buffer, opening it on first use.
+    ///
+    fn replay (& mut self) -> Result<& mut ReplayBuffer>
+    {
+        if self.replay.is_none()
+        {
+            let dir = std::env::current_dir()?.join(& self.config.path).join(& self.config.replay_dir);
+            self.replay = Some(ReplayBuffer::open(& dir, self.config.replay_capacity, self.config.replay_shard_size)?);
+        }
+        Ok(self.replay.as_mut().unwrap())
+    }
+
+    ///
+    /// Returns the position of the replay buffer, which is shared by every network with the 
+    /// same configuration.
+    ///
+    pub fn replay_position (& mut self) -> Result<ReplayPosition>
+    {
+        let position = self.replay()?.position();
+        self.replay = None;
+        Ok(position)
+    }
+
+    ///
+    /// Rewinds the replay buffer to the given position, discarding the memories remembered 
+    /// since.
+    ///
+    pub fn rewind_replay (& mut self, position: & ReplayPosition) -> Result<()>
+    {
+        self.replay()?.rewind(position)?;
+        self.replay = None;
+        Ok(())
     }
 
     ///
@@ -197,35 +354,204 @@ impl Network
     ///
     pub fn save (& self, group: & str, path: & str) -> Result<()> 
     {
-        let artifact_path = std::env::current_dir()?.join(& self.config.path).join("trained").join(group).join(path).to_str().unwrap().to_owned();
+        let group_path = std::env::current_dir()?.join(& self.config.path).join("trained").join(group);
+        self.save_file(& group_path.join(path))
+    }
+
+    ///
+    /// Saves this model's weights to the given path, creating its directory if needed.
+    ///
+    pub fn save_file (& self, path: & Path) -> Result<()> 
+    {
+        if let Some(dir) = path.parent()
+        {
+            std::fs::create_dir_all(dir).context(format!("Failed to create the model directory '{}'.", dir.display()))?;
+        }
+
+        let artifact_path = path.to_str().unwrap().to_owned();
         self.model.save(& artifact_path).context(error!(format!("Failed to save model to path '{}'.", & artifact_path)))?;
         Ok(())
     }
 
     ///
-    /// Trains this model on the given batch tensors of memory components.
+    /// Resolves the device requested by the config: "cpu", "cuda", or "auto", which uses
+    /// CUDA if it is available.
+    ///
+    fn select_device (config: & NeuralConfig) -> Result<Device>
+    {
+        match config.device.as_str()
+        {
+            "cpu"  => Ok(Device::Cpu),
+            "auto" => Ok(Device::cuda_if_available()),
+            "cuda" => match tch::Cuda::is_available() 
+            {
+                true  => Ok(Device::Cuda(0)),
+                false => Err(error!("The network was configured to use CUDA, but CUDA is unavailable."))
+            },
+            other  => Err(error!("Unknown device '{}'; expected one of 'cpu', 'cuda' or 'auto'.", other))
+        }
+    }
+
+    ///
+    /// Backpropagates the loss and steps the optimizer, first clipping the gradient norm to 
+    /// the given maximum if there is one.
     ///
-    pub fn train (& mut self)
+    fn step (optimizer: & mut Optimizer, loss: & Tensor, grad_clip: Option<f64>)
     {
+        match grad_clip 
+        {
+            Some(max) => optimizer.backward_step_clip_norm(loss, max),
+            None      => optimizer.backward_step(loss)
+        };
+    }
+
+    ///
+    /// Trains this model on memories sampled from its replay buffer, in shuffled minibatches,
+    /// and returns the losses. If checkpointing is enabled, the model is saved to the given 
+    /// group every so many epochs.
+    ///
+    pub fn train (& mut self, group: & str) -> TrainStats
+    {
+        let mut stats = TrainStats::default();
+
+        let (samples, augment) = (self.config.train_samples, self.config.augment_symmetries);
+        let sampled = self.replay().and_then(|replay| replay.sample(samples, augment));
+
+        // Other networks may share the buffer's directory, so it is reopened on its next use.
+
+        self.replay = None;
+        let memories = match sampled 
+        {
+            Ok(memories) if ! memories.is_empty() => memories,
+            Ok(_)                                 => 
+            {
+                log::warn!("There are no memories to train on.");
+                return stats;
+            },
+            Err(e)                                => 
+            {
+                log::error!("{:#}", e);
+                return stats;
+            }
+        };
+        stats.samples = memories.len();
+
+        // The memories are stacked once; each epoch then visits them in a new order. The 
+        // policy targets are normalized to distributions for the cross-entropy.
+
+        let device = self.device();
+        let inputs = Tensor::cat(& memories.iter().map(|mem| Input::from(mem.board.clone()).0).collect::<Vec<Tensor>>(), 0).to_device(device);
+        let policies = Tensor::stack(& memories.iter().map(|mem| & mem.policy).collect::<Vec<& Tensor>>(), 0);
+        let policies = (& policies / policies.sum_dim_intlist(& [1], true, Kind::Float).clamp_min(1.0e-8)).to_device(device);
+        let results = Tensor::stack(& memories.iter().map(|mem| & mem.end_result).collect::<Vec<& Tensor>>(), 0).to_device(device);
+
+        let n = memories.len() as i64;
+        let batch_size = self.config.train_batch_size as i64;
+
         self.model.set_train();
 
         let mut optimizer = Sgd::default().build(& self.vs, self.config.learning_rate as f64).unwrap();
 
-        for _epoch in 1 ..= self.config.epochs 
+        for epoch in 1 ..= self.config.epochs 
         {
-            for mem in & self.mem 
+            let order = Tensor::randperm(n, (Kind::Int64, device));
+            let (mut policy_total, mut value_total) = (0.0, 0.0);
+
+            let mut start = 0;
+            while start < n 
             {
-                let input = Input::from(mem.board.clone()).0;
-                let (policy, values) = self.forward(input);
+                let len = batch_size.min(n - start);
+                let batch = order.narrow(0, start, len);
+                let (policy, values) = self.forward(inputs.index_select(0, & batch));
+
+                let log_policy = policy.log_softmax(-1, Kind::Float);
+                let loss_policy = - (policies.index_select(0, & batch) * log_policy).sum_dim_intlist(& [1], false, Kind::Float).mean(Kind::Float);
+                let loss_values = (values - results.index_select(0, & batch)).abs().pow_tensor_scalar(self.config.exp as f64).mean(Kind::Float);
+                let loss = & loss_policy + & loss_values;
+                Network::step(& mut optimizer, & loss, self.config.grad_clip);
+                stats.steps += 1;
+
+                policy_total += loss_policy.double_value(& []) * len as f64;
+                value_total += loss_values.double_value(& []) * len as f64;
+                start += len;
+            }
+
+            let epoch_stats = EpochStats { epoch, policy_loss: policy_total / n as f64, value_loss: value_total / n as f64 };
+            log::info!("Epoch {} of {}: policy loss {:.4}, value loss {:.4}.", epoch, self.config.epochs, epoch_stats.policy_loss, epoch_stats.value_loss);
+            stats.epochs.push(epoch_stats);
 
-                let loss_policy = policy.cross_entropy_for_logits(& mem.policy_valid).sum(tch::Kind::Float);
-                let loss_values = (values - & mem.end_result).pow_tensor_scalar(self.config.exp as f64);
-                optimizer.backward_step(& (& loss_policy + & loss_values));
+            if let Some(name) = Network::checkpoint_name(& self.config, epoch)
+            {
+                if let Err(e) = self.save(group, & name)
+                {
+                    log::error!("{:#}", e);
+                }
             }
         }
 
-        self.mem.clear();
+        if let Some(last) = stats.epochs.last()
+        {
+            stats.policy_loss = last.policy_loss;
+            stats.value_loss = last.value_loss;
+        }
 
         self.model.set_eval();
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests 
+{
+    use super::*;
+
+    #[test]
+    fn checkpoints_follow_the_epochs ()
+    {
+        let mut config = NeuralConfig::default();
+        config.epochs = 2;
+
+        let checkpoints = |config: & NeuralConfig| (1 ..= config.epochs).filter_map(|epoch| Network::checkpoint_name(config, epoch)).collect::<Vec<String>>();
+
+        config.checkpoint_every_epochs = 1;
+        assert_eq!(checkpoints(& config), vec!["checkpoint-1.pt", "checkpoint-2.pt"]);
+
+        config.checkpoint_every_epochs = 2;
+        assert_eq!(checkpoints(& config), vec!["checkpoint-2.pt"]);
+
+        config.checkpoint_every_epochs = 0;
+        assert!(checkpoints(& config).is_empty());
+    }
+
+    #[test]
+    fn forced_cpu_device_is_used ()
+    {
+        let mut config = NeuralConfig::default();
+        config.device = "cpu".to_owned();
+
+        let vs = VarStore::new(Network::select_device(& config).unwrap());
+        assert_eq!(vs.device(), Device::Cpu);
+
+        config.device = "tpu".to_owned();
+        assert!(Network::select_device(& config).is_err());
+    }
+
+    #[test]
+    fn clipping_bounds_the_step ()
+    {
+        // The loss (w - 100)^2 has a gradient of -200 at zero, so a unit learning rate moves 
+        // the weight by the whole gradient unless its norm is clipped.
+
+        let stepped = |grad_clip: Option<f64>| 
+        {
+            let vs = VarStore::new(Device::Cpu);
+            let w = vs.root().zeros("w", & [1]);
+            let mut optimizer = Sgd::default().build(& vs, 1.0).unwrap();
+            Network::step(& mut optimizer, & (& w - 100.0).square().sum(Kind::Float), grad_clip);
+            w.double_value(& [0])
+        };
+
+        assert!((stepped(None) - 200.0).abs() < 1.0e-6);
+        assert!((stepped(Some(1.0)) - 1.0).abs() < 1.0e-6);
     }
 }
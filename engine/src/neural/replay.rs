@@ -0,0 +1,330 @@
+
+use lits::{Board, Transform};
+use lits::symmetry;
+use lits::tetromino::TETROMINO_RANGE;
+
+use super::memory::Memory;
+
+use std::collections::{BTreeMap, VecDeque};
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use tch::{Device, Kind, Tensor};
+
+use utils::{Serialize, Deserialize};
+use utils::error::*;
+use utils::log;
+use utils::notate::Notate;
+
+///
+/// A memory as it is stored on disk: the board's notation, the policy target as a sparse
+/// list of (tetromino index, weight) pairs, and the end result in the perspective of the
+/// player to move.
+///
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReplayEntry
+{
+    pub board: String,
+    pub policy: Vec<(usize, f32)>,
+    pub value: f32
+}
+
+impl ReplayEntry
+{
+    ///
+    /// Rebuilds the memory that this entry stores.
+    ///
+    pub fn to_memory (& self) -> Result<Memory>
+    {
+        let board = Board::parse(& self.board).context(format!("Invalid replay entry board '{}'.", self.board))?;
+
+        let mut policy = [0.0; TETROMINO_RANGE];
+        for & (index, weight) in & self.policy
+        {
+            match policy.get_mut(index)
+            {
+                Some(p) => * p = weight,
+                None    => return Err(error!("Invalid tetromino index {} in a replay entry.", index))
+            };
+        }
+
+        Ok(Memory { board, policy: Tensor::of_slice::<f32>(& policy), end_result: Tensor::of_slice::<f32>(& [self.value]) })
+    }
+
+    ///
+    /// Returns the image of this entry under the given symmetry of the board, whose policy 
+    /// target follows the moves to their images; the value is unchanged.
+    ///
+    pub fn transform (& self, transform: & Transform) -> Result<ReplayEntry>
+    {
+        let board = Board::parse(& self.board).context(format!("Invalid replay entry board '{}'.", self.board))?;
+        let policy = self.policy.iter().map(|& (index, weight)| (symmetry::remap(transform, index), weight)).collect();
+
+        Ok(ReplayEntry { board: board.transform(transform).notate(), policy, value: self.value })
+    }
+}
+
+///
+/// A position in a replay buffer: the id of its newest shard, if any, and the number of 
+/// entries it holds.
+///
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct ReplayPosition
+{
+    pub newest_shard: Option<u64>,
+    pub entries: usize
+}
+
+///
+/// A capped replay buffer of memories, kept on disk in a directory of numbered shards so that
+/// it survives restarts and can hold more positions than fit in memory.
+///
+/// Each shard is a file of JSON lines, one entry per line. Entries are buffered in memory until
+/// a shard's worth has accumulated, and then written as a new shard; once the buffer holds more
+/// than its capacity, the oldest shards are deleted.
+///
+#[derive(Debug)]
+pub struct ReplayBuffer
+{
+    dir: PathBuf,
+    capacity: usize,
+    shard_size: usize,
+    shards: VecDeque<(u64, usize)>,
+    pending: Vec<ReplayEntry>
+}
+
+impl Drop for ReplayBuffer
+{
+    fn drop (& mut self)
+    {
+        if let Err(e) = self.flush()
+        {
+            log::error!("{:#}", e);
+        }
+    }
+}
+
+impl ReplayBuffer
+{
+    ///
+    /// Deletes the oldest shards until the buffer is within its capacity, always keeping the
+    /// newest shard.
+    ///
+    fn evict (& mut self) -> Result<()>
+    {
+        while self.shards.len() > 1 && self.len() - self.shards[0].1 >= self.capacity
+        {
+            let (id, _) = self.shards.pop_front().unwrap();
+            let path = self.shard_path(id);
+            fs::remove_file(& path).context(format!("Failed to evict replay shard '{}'.", path.display()))?;
+        }
+        Ok(())
+    }
+
+    ///
+    /// Writes the pending entries to a new shard.
+    ///
+    pub fn flush (& mut self) -> Result<()>
+    {
+        if self.pending.is_empty()
+        {
+            return Ok(());
+        }
+
+        let id = self.shards.back().map_or(0, |(id, _)| id + 1);
+        let path = self.shard_path(id);
+        let context = format!("Failed to write replay shard '{}'.", path.display());
+
+        let mut contents = Vec::new();
+        for entry in & self.pending
+        {
+            serde_json::to_writer(& mut contents, entry).context(context.clone())?;
+            contents.write_all(b"\n").context(context.clone())?;
+        }
+        fs::write(& path, contents).context(context)?;
+
+        self.shards.push_back((id, self.pending.len()));
+        self.pending.clear();
+
+        self.evict()
+    }
+
+    ///
+    /// Determines whether this buffer holds no entries.
+    ///
+    pub fn is_empty (& self) -> bool
+    {
+        self.len() == 0
+    }
+
+    ///
+    /// Returns the number of entries in this buffer, including those not yet written.
+    ///
+    pub fn len (& self) -> usize
+    {
+        self.shards.iter().map(|(_, count)| count).sum::<usize>() + self.pending.len()
+    }
+
+    ///
+    /// Opens the replay buffer in the given directory, creating it if it does not exist, with
+    /// the given capacity in entries and number of entries per shard.
+    ///
+    pub fn open (dir: & Path, capacity: usize, shard_size: usize) -> Result<ReplayBuffer>
+    {
+        let context = format!("Failed to open the replay buffer '{}'.", dir.display());
+        fs::create_dir_all(dir).context(context.clone())?;
+
+        let mut shards = Vec::new();
+        for entry in fs::read_dir(dir).context(context.clone())?
+        {
+            let path = entry.context(context.clone())?.path();
+            let id = path.file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_prefix("shard-"))
+                .and_then(|name| name.strip_suffix(".jsonl"))
+                .and_then(|id| id.parse::<u64>().ok());
+
+            if let Some(id) = id
+            {
+                let count = BufReader::new(fs::File::open(& path).context(context.clone())?).lines().count();
+                shards.push((id, count));
+            }
+        }
+        shards.sort();
+
+        let mut buffer = ReplayBuffer { dir: dir.to_owned(), capacity, shard_size: shard_size.max(1), shards: shards.into(), pending: Vec::new() };
+        buffer.evict().context(context)?;
+
+        log::info!("Opened replay buffer '{}' with {} entries in {} shards.", dir.display(), buffer.len(), buffer.shards.len());
+        Ok(buffer)
+    }
+
+    ///
+    /// Returns the current position of this buffer, as of its last written shard.
+    ///
+    pub fn position (& self) -> ReplayPosition
+    {
+        let entries = self.len() - self.pending.len();
+        ReplayPosition { newest_shard: self.shards.back().map(|& (id, _)| id), entries }
+    }
+
+    ///
+    /// Adds an entry to this buffer, writing a new shard if enough entries are pending.
+    ///
+    pub fn push (& mut self, entry: ReplayEntry) -> Result<()>
+    {
+        self.pending.push(entry);
+        match self.pending.len() >= self.shard_size
+        {
+            true  => self.flush(),
+            false => Ok(())
+        }
+    }
+
+    ///
+    /// Reads the entries of the shard with the given id.
+    ///
+    fn read_shard (& self, id: u64) -> Result<Vec<ReplayEntry>>
+    {
+        let path = self.shard_path(id);
+        let context = format!("Failed to read replay shard '{}'.", path.display());
+
+        let file = fs::File::open(& path).context(context.clone())?;
+        BufReader::new(file).lines()
+            .map(|line| Ok(serde_json::from_str(& line?)?))
+            .collect::<Result<Vec<ReplayEntry>>>()
+            .context(context)
+    }
+
+    ///
+    /// Rewinds this buffer to the given position, deleting the shards written after it and 
+    /// discarding the pending entries. Shards evicted since cannot be restored.
+    ///
+    pub fn rewind (& mut self, position: & ReplayPosition) -> Result<()>
+    {
+        self.pending.clear();
+
+        while let Some(& (id, _)) = self.shards.back()
+        {
+            if position.newest_shard.is_some_and(|newest| id <= newest)
+            {
+                break;
+            }
+
+            self.shards.pop_back();
+            let path = self.shard_path(id);
+            fs::remove_file(& path).context(format!("Failed to rewind replay shard '{}'.", path.display()))?;
+        }
+
+        log::info!("Rewound replay buffer '{}' to {} entries.", self.dir.display(), self.len());
+        Ok(())
+    }
+
+    ///
+    /// Samples the given number of memories uniformly, with replacement, from this buffer. 
+    /// When augmenting, each sample is returned under all 8 symmetries of the board.
+    ///
+    pub fn sample (& self, n: usize, augment: bool) -> Result<Vec<Memory>>
+    {
+        if self.is_empty() || n == 0
+        {
+            return Ok(Vec::new());
+        }
+
+        let mut indices = vec![0_i64; n];
+        Tensor::randint(self.len() as i64, & [n as i64], (Kind::Int64, Device::Cpu)).copy_data(& mut indices, n);
+
+        // Group the samples by shard, so that each shard is read at most once.
+
+        let mut wanted : BTreeMap<Option<u64>, Vec<usize>> = BTreeMap::new();
+        for index in indices
+        {
+            let mut offset = index as usize;
+            let mut shard = None;
+            for & (id, count) in & self.shards
+            {
+                if offset < count
+                {
+                    shard = Some(id);
+                    break;
+                }
+                offset -= count;
+            }
+            wanted.entry(shard).or_default().push(offset);
+        }
+
+        let mut memories = Vec::with_capacity(n);
+        for (shard, offsets) in wanted
+        {
+            let entries = match shard
+            {
+                Some(id) => self.read_shard(id)?,
+                None     => self.pending.clone()
+            };
+
+            for offset in offsets
+            {
+                let entry = entries.get(offset).ok_or_else(|| error!("Replay shard {:?} is shorter than recorded.", shard))?;
+                match augment 
+                {
+                    true  => for transform in Transform::as_array()
+                    {
+                        memories.push(entry.transform(& transform)?.to_memory()?);
+                    },
+                    false => memories.push(entry.to_memory()?)
+                };
+            }
+        }
+
+        Ok(memories)
+    }
+
+    ///
+    /// Returns the path of the shard with the given id.
+    ///
+    fn shard_path (& self, id: u64) -> PathBuf
+    {
+        self.dir.join(format!("shard-{:06}.jsonl", id))
+    }
+}
@@ -0,0 +1,142 @@
+
+use crate::mcts::evaluator::Evaluator;
+
+use lits::Board;
+use lits::tetromino::TETROMINO_RANGE;
+
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::network::Network;
+
+use utils::log;
+
+///
+/// A policy vector and value estimation, as returned by an evaluator.
+///
+pub type Prediction = ([f32; TETROMINO_RANGE], f32);
+
+///
+/// A leaf to evaluate, along with the channel on which to return its prediction.
+///
+#[derive(Debug)]
+struct Request
+{
+    board: Board,
+    reply: Sender<Prediction>
+}
+
+///
+/// An evaluator that forwards each prediction to an inference server, and waits for
+/// the server to return it. Copies share the same server.
+///
+#[derive(Clone, Debug)]
+pub struct BatchedEvaluator
+{
+    requests: Sender<Request>
+}
+
+impl Evaluator for BatchedEvaluator
+{
+    fn predict (& self, board: & Board) -> Prediction
+    {
+        let (reply, prediction) = channel();
+        let request = Request { board: board.clone(), reply };
+
+        match self.requests.send(request).ok().and_then(|()| prediction.recv().ok())
+        {
+            Some(prediction) => prediction,
+            None             =>
+            {
+                log::error!("The inference server is gone; evaluating '{}' as neutral.", board.fingerprint());
+                ([0.0; TETROMINO_RANGE], 0.0)
+            }
+        }
+    }
+
+    fn copy (& self) -> Box<dyn Evaluator>
+    {
+        Box::new(self.clone())
+    }
+}
+
+///
+/// A server that owns a network on a background thread and evaluates the leaves requested
+/// by every searcher in batches, so that the device runs a single forward pass for many
+/// leaves rather than one for each.
+///
+/// A batch is evaluated once it holds the configured number of leaves, or once the configured
+/// wait has passed since its first leaf arrived; since each searcher waits on its leaf, a batch
+/// holds at most one leaf per searcher thread. The server stops once every evaluator that
+/// sends to it is dropped.
+///
+pub struct InferenceServer
+{
+    network: Network,
+    requests: Receiver<Request>,
+    batch_size: usize,
+    batch_wait: Duration
+}
+
+impl InferenceServer
+{
+    ///
+    /// Evaluates batches of requests until every sender is dropped.
+    ///
+    fn run (self)
+    {
+        while let Ok(first) = self.requests.recv()
+        {
+            let mut batch = vec![first];
+            let deadline = Instant::now() + self.batch_wait;
+
+            while batch.len() < self.batch_size
+            {
+                match self.requests.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                {
+                    Ok(request) => batch.push(request),
+                    Err(_)      => break
+                }
+            }
+
+            let (boards, replies) : (Vec<Board>, Vec<Sender<Prediction>>) = batch.into_iter()
+                .map(|request| (request.board, request.reply))
+                .unzip();
+
+            for (reply, prediction) in replies.into_iter().zip(self.network.predict_batch(& boards))
+            {
+                let _ = reply.send(prediction);
+            }
+        }
+
+        log::debug!("Inference server stopped.");
+    }
+
+    ///
+    /// Returns an evaluator for the given network: if its configuration batches more than
+    /// one leaf, the network is moved onto a new inference server, and otherwise it is used
+    /// directly.
+    ///
+    pub fn serve (network: Network) -> Box<dyn Evaluator>
+    {
+        let batch_size = network.config().batch_size;
+        let batch_wait = Duration::from_micros(network.config().batch_wait_us);
+
+        if batch_size <= 1
+        {
+            return Box::new(network);
+        }
+
+        let (requests, receiver) = channel();
+        let server = InferenceServer { network, requests: receiver, batch_size, batch_wait };
+
+        thread::Builder::new()
+            .name("InferenceServer".to_owned())
+            .spawn(move || server.run())
+            .expect("Failed to start the inference server.");
+
+        log::info!("Started an inference server with batches of up to {} leaves.", batch_size);
+        Box::new(BatchedEvaluator { requests })
+    }
+}
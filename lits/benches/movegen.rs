@@ -0,0 +1,76 @@
+
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use lits::*;
+
+use std::hint::black_box;
+
+///
+/// Benchmarks move generation on the fixture at the given index, with the move cache 
+/// busted so that each iteration generates from scratch.
+///
+fn enumerate_moves (c: & mut Criterion, name: & str, idx: usize)
+{
+    Tetromino::initialize();
+    let mut board = bench_fixtures()[idx].clone();
+
+    c.bench_function(name, |b| b.iter(|| 
+    {
+        board.cache_bust();
+        black_box(board.enumerate_moves())
+    }));
+}
+
+fn enumerate_moves_blank (c: & mut Criterion)
+{
+    enumerate_moves(c, "enumerate_moves_blank", 0);
+}
+
+fn enumerate_moves_mid (c: & mut Criterion)
+{
+    enumerate_moves(c, "enumerate_moves_mid", 1);
+}
+
+fn enumerate_moves_full (c: & mut Criterion)
+{
+    enumerate_moves(c, "enumerate_moves_full", 2);
+}
+
+fn place_undo (c: & mut Criterion)
+{
+    Tetromino::initialize();
+    let mut board = bench_fixtures()[1].clone();
+    let moves = board.enumerate_moves().into_iter().collect::<Vec<Tetromino>>();
+
+    c.bench_function("place_undo", |b| b.iter(|| 
+    {
+        for tetromino in & moves 
+        {
+            board.place_tetromino(tetromino).unwrap();
+            board.undo_tetromino(tetromino).unwrap();
+        }
+    }));
+}
+
+fn clone_mid (c: & mut Criterion)
+{
+    Tetromino::initialize();
+    let board = bench_fixtures()[1].clone();
+
+    c.bench_function("clone_mid", |b| b.iter(|| black_box(board.clone())));
+}
+
+fn count_moves_mid (c: & mut Criterion)
+{
+    Tetromino::initialize();
+    let mut board = bench_fixtures()[1].clone();
+
+    c.bench_function("count_moves_mid", |b| b.iter(|| 
+    {
+        board.cache_bust();
+        black_box(board.count_moves())
+    }));
+}
+
+criterion_group!(benches, enumerate_moves_blank, enumerate_moves_mid, enumerate_moves_full, place_undo, clone_mid, count_moves_mid);
+criterion_main!(benches);
@@ -0,0 +1,174 @@
+
+use super::point::Point;
+
+///
+/// The tiles of a board.
+///
+const FULL : u128 = (1 << 100) - 1;
+
+///
+/// The tiles in the first row of each column, where j = 0.
+///
+const FIRST_ROW : u128 = 0x0401_0040_1004_0100_4010_0401;
+
+///
+/// The tiles in the last row of each column, where j = 9.
+///
+const LAST_ROW : u128 = FIRST_ROW << 9;
+
+///
+/// A set of tiles on a 10x10 board, packed into the low 100 bits of an integer, where the 
+/// tile (i, j) is the bit 10i + j.
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Bitboard(pub u128);
+
+impl Bitboard 
+{
+    ///
+    /// Returns the mask of the given tile.
+    ///
+    pub fn bit (i: usize, j: usize) -> u128 
+    {
+        1 << (10 * i + j)
+    }
+
+    ///
+    /// Determines whether the given tile is in this set.
+    ///
+    pub fn contains (& self, i: usize, j: usize) -> bool 
+    {
+        self.0 & Bitboard::bit(i, j) != 0
+    }
+
+    ///
+    /// Returns the set of the given points, if they are all on the board.
+    ///
+    pub fn from_points (points: & [Point]) -> Option<Bitboard>
+    {
+        let mut bits = Bitboard::empty();
+        for point in points 
+        {
+            if ! point.in_bounds()
+            {
+                return None;
+            }
+            bits.insert(point.x() as usize, point.y() as usize);
+        }
+        Some(bits)
+    }
+
+    ///
+    /// Returns the number of tiles in this set.
+    ///
+    pub fn count (& self) -> usize 
+    {
+        self.0.count_ones() as usize
+    }
+
+    ///
+    /// Returns the empty set.
+    ///
+    pub fn empty () -> Bitboard 
+    {
+        Bitboard(0)
+    }
+
+    ///
+    /// Adds the given tile to this set.
+    ///
+    pub fn insert (& mut self, i: usize, j: usize)
+    {
+        self.0 |= Bitboard::bit(i, j);
+    }
+
+    ///
+    /// Determines whether this set shares a tile with the other.
+    ///
+    pub fn intersects (& self, other: Bitboard) -> bool 
+    {
+        self.0 & other.0 != 0
+    }
+
+    ///
+    /// Determines whether this set has no tiles.
+    ///
+    pub fn is_empty (& self) -> bool 
+    {
+        self.0 == 0
+    }
+
+    ///
+    /// Returns the tiles orthogonally adjacent to this set, that are not in it.
+    ///
+    pub fn neighbours (& self) -> Bitboard 
+    {
+        let up    = (self.0 << 1) & ! FIRST_ROW;
+        let down  = (self.0 >> 1) & ! LAST_ROW;
+        let right = self.0 << 10;
+        let left  = self.0 >> 10;
+
+        Bitboard((up | down | right | left) & FULL & ! self.0)
+    }
+
+    ///
+    /// Returns the least tile (i, j) in this set, ordered as points are, if any.
+    ///
+    pub fn lowest (& self) -> Option<(usize, usize)>
+    {
+        match self.0
+        {
+            0    => None,
+            bits => 
+            {
+                let index = bits.trailing_zeros() as usize;
+                Some((index / 10, index % 10))
+            }
+        }
+    }
+
+    ///
+    /// Removes the given tile from this set.
+    ///
+    pub fn remove (& mut self, i: usize, j: usize)
+    {
+        self.0 &= ! Bitboard::bit(i, j);
+    }
+
+    ///
+    /// Returns the lowest corner (i, j) of every 2-by-2 square of tiles in this set.
+    ///
+    pub fn square_corners (& self) -> Bitboard 
+    {
+        let b = self.0;
+        Bitboard(b & (b >> 1) & (b >> 10) & (b >> 11) & ! LAST_ROW & FULL)
+    }
+
+    ///
+    /// Returns the tiles in this set that are not in the other.
+    ///
+    pub fn without (& self, other: Bitboard) -> Bitboard 
+    {
+        Bitboard(self.0 & ! other.0)
+    }
+}
+
+impl std::ops::BitAnd for Bitboard 
+{
+    type Output = Bitboard;
+
+    fn bitand (self, other: Bitboard) -> Bitboard 
+    {
+        Bitboard(self.0 & other.0)
+    }
+}
+
+impl std::ops::BitOr for Bitboard 
+{
+    type Output = Bitboard;
+
+    fn bitor (self, other: Bitboard) -> Bitboard 
+    {
+        Bitboard(self.0 | other.0)
+    }
+}
@@ -1,12 +1,18 @@
 
+use lazy_static::lazy_static;
+
 use std::borrow::Borrow;
 use std::cell::RefCell;
 use std::collections::{BTreeMap, BTreeSet};
 
+use super::bitboard::Bitboard;
 use super::colour::Colour;
 use super::outcome::Outcome;
+use super::piecepool::PiecePool;
 use super::player::Player;
 use super::point::Point;
+use super::rules::{PlacementError, PlacementRules};
+use super::symmetry;
 use super::tetromino::Tetromino;
 use super::transform::Transform;
 
@@ -14,16 +20,35 @@ use utils::error::Context;
 use utils::notate::Notate;
 use utils::*;
 
+///
+/// A record of a move made with `Board::make`, holding the prior state of every attach 
+/// point the move could have changed, so that `Board::unmake` restores the board exactly 
+/// without recomputing its attach points.
+///
+#[derive(Clone, Debug)]
+pub struct UndoToken 
+{
+    tetromino: Tetromino,
+    attach_points: Vec<(Point, Option<BTreeSet<Colour>>)>
+}
+
 ///
 /// Represents a game board in the game The Battle of LITS. A game board is a 10x10 grid
 /// of tiles.
 ///
-#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+/// The tiles are stored as bitboards, with one set of tiles per player and per colour, 
+/// so that boards are cheap to copy and to query.
+///
+/// Only the game state is serialized; the attach points and move caches are derived 
+/// from it, so they are recomputed on deserialization rather than stored. For the same 
+/// reason, boards compare by their game state alone.
+///
+#[derive(Clone, Debug)]
 pub struct Board 
 {
-    score_tiles: Vec<Vec<Player>>,
-    piece_tiles: Vec<Vec<Colour>>,
-    pieces_remaining: Vec<usize>,
+    score_bits: [Bitboard; 2],
+    piece_bits: [Bitboard; 4],
+    pieces_remaining: PiecePool,
     attach_points: BTreeMap<Point, BTreeSet<Colour>>,
     to_move: Player,
 
@@ -31,6 +56,98 @@ pub struct Board
     has_moves: RefCell<Option<bool>>
 }
 
+///
+/// The serialized form of a board, as grids of tiles.
+///
+#[derive(Serialize)]
+#[serde(rename = "Board")]
+struct BoardRef
+{
+    score_tiles: Vec<Vec<Player>>,
+    piece_tiles: Vec<Vec<Colour>>,
+    pieces_remaining: PiecePool,
+    to_move: Player
+}
+
+///
+/// The serialized form of a board, from which the rest is recomputed.
+///
+#[derive(Deserialize)]
+#[serde(rename = "Board")]
+struct BoardRepr
+{
+    score_tiles: Vec<Vec<Player>>,
+    piece_tiles: Vec<Vec<Colour>>,
+    pieces_remaining: PiecePool,
+    to_move: Player
+}
+
+impl Serialize for Board 
+{
+    fn serialize<S> (& self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        let repr = BoardRef 
+        { 
+            score_tiles: self.score_grid(), 
+            piece_tiles: self.piece_grid(), 
+            pieces_remaining: self.pieces_remaining, 
+            to_move: self.to_move 
+        };
+        repr.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Board 
+{
+    fn deserialize<D> (deserializer: D) -> std::result::Result<Board, D::Error>
+        where D: Deserializer<'de>
+    {
+        let repr = BoardRepr::deserialize(deserializer)?;
+
+        if repr.score_tiles.len() != 10 || repr.score_tiles.iter().any(|column| column.len() != 10)
+            || repr.piece_tiles.len() != 10 || repr.piece_tiles.iter().any(|column| column.len() != 10)
+        {
+            return Err(de::Error::custom("Expected a 10x10 board."));
+        }
+
+        Ok(Board::with_pool(& repr.score_tiles, & repr.piece_tiles, repr.pieces_remaining, repr.to_move))
+    }
+}
+
+lazy_static! 
+{
+    ///
+    /// The Zobrist keys, indexed by tile and tile state for the first 1500 keys, then 
+    /// by colour and remaining count for the next 24 keys, with a final key for the 
+    /// player to move. The keys are generated by a fixed splitmix64 sequence, so 
+    /// that hashes are stable across runs.
+    ///
+    static ref ZOBRIST_KEYS : Vec<u64> = splitmix_keys(0x4c49_5453_4c49_5453);
+
+    ///
+    /// The checksum keys, indexed as the Zobrist keys are but generated from another 
+    /// splitmix64 sequence, so that a checksum is independent of the hash.
+    ///
+    static ref CHECKSUM_KEYS : Vec<u32> = splitmix_keys(0x7374_696c_7374_696c).into_iter().map(|key| (key >> 32) as u32).collect();
+}
+
+///
+/// Returns a key for every feature of a board, generated by a splitmix64 sequence from 
+/// the given state.
+///
+fn splitmix_keys (mut state: u64) -> Vec<u64>
+{
+    (0 .. 100 * 15 + 4 * 6 + 1).map(|_| 
+    {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }).collect()
+}
+
 impl notate::Notate for Board 
 {
     fn notate (& self) -> String 
@@ -46,10 +163,7 @@ impl notate::Notate for Board
         }
         boardstr += & ",".to_string();
 
-        for archetype in vec![Colour::L, Colour::I, Colour::T, Colour::S]
-        {
-            boardstr += & self.pieces_remaining[archetype.as_index()].to_string();
-        }
+        boardstr += & self.pieces_remaining.notate();
         boardstr += & ",".to_string();
 
         boardstr += & self.to_move().notate();
@@ -91,17 +205,7 @@ impl notate::Notate for Board
             _   => return Err(error::error!("Expected a comma separating the board and piece counts.")).context(context.clone())
         };
 
-        let mut piece_pool = Vec::new();
-        for archetype in [Colour::L, Colour::I, Colour::T, Colour::S]
-        {
-            let idx = 101 + archetype.as_index();
-            let remaining = (& uncompressed[idx ..= idx]).parse::<usize>().context(context.clone())?;
-            match remaining 
-            {
-                0 ..= 5 => piece_pool.push(remaining),
-                _       => return Err(error::error!("Invalid number of remaining pieces {} for type '{}'.", remaining, archetype.notate())).context(context.clone()) 
-            };
-        }
+        let piece_pool = PiecePool::parse(& uncompressed[101 .. 105]).context(context.clone())?;
 
         match & uncompressed[105 ..= 105]
         {
@@ -116,7 +220,46 @@ impl notate::Notate for Board
             _ => return Err(error::error!("The player to move cannot be null.")).context(context.clone())
         };
 
-        Board::new(& score_tiles, & piece_tiles, & piece_pool, who_to_move)
+        Ok(Board::with_pool(& score_tiles, & piece_tiles, piece_pool, who_to_move))
+    }
+}
+
+impl std::hash::Hash for Board 
+{
+    ///
+    /// Hashes the observable state of the board by its Zobrist hash, which is equal for 
+    /// equal boards; the attach points and move caches are derived, so they are skipped.
+    ///
+    fn hash<H: std::hash::Hasher> (& self, state: & mut H)
+    {
+        state.write_u64(self.zobrist_hash());
+    }
+}
+
+impl std::cmp::Ord for Board 
+{
+    fn cmp (& self, other: & Board) -> std::cmp::Ordering 
+    {
+        (& self.score_bits, & self.piece_bits, & self.pieces_remaining, self.to_move)
+            .cmp(& (& other.score_bits, & other.piece_bits, & other.pieces_remaining, other.to_move))
+    }
+}
+
+impl std::cmp::PartialEq for Board 
+{
+    fn eq (& self, other: & Board) -> bool 
+    {
+        self.cmp(other) == std::cmp::Ordering::Equal
+    }
+}
+
+impl std::cmp::Eq for Board {}
+
+impl std::cmp::PartialOrd for Board 
+{
+    fn partial_cmp (& self, other: & Board) -> Option<std::cmp::Ordering> 
+    {
+        Some(self.cmp(other))
     }
 }
 
@@ -129,10 +272,10 @@ impl std::fmt::Display for Board
             let j = 9 - j;
             for i in 0 ..= 9
             {
-                match self.piece_tiles[i][j]
+                match self.piece_at(i, j)
                 {
-                    Colour::None => write!(f, "{}", self.score_tiles[i][j]),
-                    _            => write!(f, "{}", self.piece_tiles[i][j])
+                    Colour::None => write!(f, "{}", self.score_at(i, j)),
+                    colour       => write!(f, "{}", colour)
                 }?;
             }
             write!(f, "\n")?;
@@ -140,10 +283,10 @@ impl std::fmt::Display for Board
 
         write!(
             f, "{} {} {} {}  {} {} {} {} \n",
-            Colour::L, self.pieces_remaining[Colour::L.as_index()],
-            Colour::I, self.pieces_remaining[Colour::I.as_index()], 
-            Colour::T, self.pieces_remaining[Colour::T.as_index()],
-            Colour::S, self.pieces_remaining[Colour::S.as_index()]
+            Colour::L, self.pieces_remaining.get(& Colour::L),
+            Colour::I, self.pieces_remaining.get(& Colour::I), 
+            Colour::T, self.pieces_remaining.get(& Colour::T),
+            Colour::S, self.pieces_remaining.get(& Colour::S)
         )?;
 
         Ok(())
@@ -159,9 +302,9 @@ impl Board
     {
         let mut board = Board 
         { 
-            score_tiles: vec![vec![Player::None; 10]; 10],
-            piece_tiles: vec![vec![Colour::None; 10]; 10],
-            pieces_remaining: vec![5; 4],
+            score_bits: [Bitboard::empty(); 2],
+            piece_bits: [Bitboard::empty(); 4],
+            pieces_remaining: PiecePool::full(),
             attach_points: BTreeMap::new(),
             to_move: Player::X,
             move_cache: RefCell::new(None),
@@ -196,20 +339,7 @@ impl Board
     {
         self.attach_points.clear();
 
-        let mut is_empty = true;
-
-        for i in 0 .. 10 
-        {
-            for j in 0 .. 10 
-            {
-                if self.piece_tiles[i][j] != Colour::None 
-                {
-                    is_empty = false;
-                }
-            }
-        }
-
-        if ! is_empty
+        if ! self.is_empty()
         {
             for i in 0 .. 10 
             {
@@ -221,11 +351,12 @@ impl Board
                     // then compute the colourset and add the attach point if and only if the colourset 
                     // is non-empty.
 
-                    if self.piece_tiles[point.x() as usize][point.y() as usize] == Colour::None 
-                        && point.neighbours_on_board().iter().any(|& p| self.piece_tiles[p.x() as usize][p.y() as usize] != Colour::None)
+                    let neighbours = self.neighbour_colours(& point);
+
+                    if self.is_free(& point) && ! neighbours.is_empty()
                     {
                         let mut colourset : BTreeSet<Colour> = BTreeSet::from([Colour::L, Colour::I, Colour::T, Colour::S]);
-                        point.neighbours_on_board().iter().for_each(|& p| { colourset.remove(& self.piece_tiles[p.x() as usize][p.y() as usize]); });
+                        neighbours.iter().for_each(|colour| { colourset.remove(colour); });
                         if ! colourset.is_empty()
                         {
                             self.attach_points.insert(point, colourset);
@@ -248,12 +379,30 @@ impl Board
         }
     }
 
+    ///
+    /// Returns a small checksum of this board, computed independently of its Zobrist
+    /// hash, so that a hash match can be confirmed cheaply before comparing boards.
+    ///
+    pub fn checksum (& self) -> u32 
+    {
+        self.features().into_iter().fold(0, |checksum, feature| checksum ^ CHECKSUM_KEYS[feature])
+    }
+
+    ///
+    /// Returns the checksum of the board after playing the given tetromino, given this 
+    /// board's checksum; only the features that the tetromino changes are visited.
+    ///
+    pub fn checksum_after (& self, checksum: u32, tetromino: & Tetromino) -> u32 
+    {
+        self.features_changed(tetromino).into_iter().fold(checksum, |checksum, feature| checksum ^ CHECKSUM_KEYS[feature])
+    }
+
     ///
     /// Returns the colour at the given tile.
     ///
     pub fn colour_at (& self, i: i32, j: i32) -> Colour 
     {
-        self.piece_tiles[i as usize][j as usize]
+        self.piece_at(i as usize, j as usize)
     }
 
     ///
@@ -261,7 +410,8 @@ impl Board
     ///
     pub fn cycle_colour (& mut self, i: i32, j: i32)
     {
-        self.piece_tiles[i as usize][j as usize] = self.piece_tiles[i as usize][j as usize].next_and_none();
+        let (i, j) = (i as usize, j as usize);
+        self.set_piece_at(i, j, self.piece_at(i, j).next_and_none());
     }
 
     ///
@@ -269,224 +419,466 @@ impl Board
     ///
     pub fn cycle_player (& mut self, i: i32, j: i32)
     {
-        self.score_tiles[i as usize][j as usize] = self.score_tiles[i as usize][j as usize].next_and_none();
-        self.score_tiles[(9 - i) as usize][(9 - j) as usize] = match self.score_tiles[i as usize][j as usize]
+        let (i, j) = (i as usize, j as usize);
+        self.set_score_at(i, j, self.score_at(i, j).next_and_none());
+        self.set_score_at(9 - i, 9 - j, match self.score_at(i, j)
         {
             Player::None => Player::None,
             Player::X    => Player::O,
             Player::O    => Player::X
-        };
+        });
     }
 
     ///
-    /// Returns all possible moves in this position.
+    /// Returns every tile whose piece colour differs between this board and the other, 
+    /// along with its colour on this board and its colour on the other.
     ///
-    pub fn enumerate_moves (& self) -> BTreeSet<Tetromino>
+    pub fn diff (& self, other: & Board) -> Vec<(Point, Colour, Colour)>
     {
-        if let Some(cache) = self.move_cache.borrow().clone()
-        {
-            return cache;
-        }
+        let mut changes = Vec::new();
 
-        let mut result : BTreeSet<Tetromino> = BTreeSet::new();
-
-        let available_colours = [Colour::L, Colour::I, Colour::T, Colour::S].into_iter()
-            .filter(|& c| self.pieces_remaining[c.as_index()] > 0)
-            .collect::<BTreeSet<Colour>>();
-
-        for (attach, colours) in & self.attach_points
+        for i in 0 .. 10 
         {
-            for anchor in attach.get_potential_anchors()
+            for j in 0 .. 10 
             {
-                for colour in colours.intersection(& available_colours)
+                let (old, new) = (self.piece_at(i, j), other.piece_at(i, j));
+                if old != new 
                 {
-                    for tetromino in Tetromino::get_reference_tetromino(& colour, & anchor).enumerate_transforms()
-                    {
-                        if self.validate_tetromino(& tetromino).is_ok()
-                        {
-                            result.insert(tetromino);
-                        }
-                    }
+                    changes.push((Point::new(i as i32, j as i32), old, new));
                 }
             }
         }
 
-        * self.move_cache.borrow_mut() = Some(result.clone());
-        * self.has_moves.borrow_mut() = Some(! result.is_empty());
+        changes
+    }
 
-        result
+    ///
+    /// Returns the number of legal moves in this position, without collecting them unless 
+    /// they are already cached.
+    ///
+    pub fn count_moves (& self) -> usize 
+    {
+        if let Some(cache) = self.move_cache.borrow().as_ref()
+        {
+            return cache.len();
+        }
+
+        self.moves_iter().count()
     }
 
     ///
-    /// Determines whether any more moves are possible in this position.
+    /// Returns all possible moves in this position.
     ///
-    pub fn has_moves (& self) -> bool 
+    pub fn enumerate_moves (& self) -> BTreeSet<Tetromino>
     {
-        if let Some(status) = self.has_moves.borrow().clone()
+        if let Some(cache) = self.move_cache.borrow().clone()
         {
-            return status;
+            return cache;
         }
 
-        let mut has = false;
+        let result : BTreeSet<Tetromino> = self.moves_iter().collect();
+
+        * self.move_cache.borrow_mut() = Some(result.clone());
+        * self.has_moves.borrow_mut() = Some(! result.is_empty());
+
+        result
+    }
 
-        let available_colours = [Colour::L, Colour::I, Colour::T, Colour::S].into_iter()
-            .filter(|& c| self.pieces_remaining[c.as_index()] > 0)
-            .collect::<BTreeSet<Colour>>();
+    ///
+    /// Returns the index of the key of every feature of this board: the state of each 
+    /// non-empty tile, the remaining count of each colour, and whether O is to move.
+    ///
+    fn features (& self) -> Vec<usize>
+    {
+        let mut features = Vec::new();
 
-        'outer: for (attach, colours) in & self.attach_points
+        for i in 0 .. 10 
         {
-            for anchor in attach.get_potential_anchors()
+            for j in 0 .. 10 
             {
-                for colour in colours.intersection(& available_colours)
+                let state = 5 * self.score_at(i, j).as_index_null() + self.piece_at(i, j).as_index_null();
+                if state != 0
                 {
-                    for tetromino in Tetromino::get_reference_tetromino(& colour, & anchor).enumerate_transforms()
-                    {
-                        if self.validate_tetromino(& tetromino).is_ok()
-                        {
-                            has = true;
-                            break 'outer;
-                        }
-                    }
+                    features.push(15 * (10 * i + j) + state);
                 }
             }
         }
 
-        * self.has_moves.borrow_mut() = Some(has);
-        has
+        for archetype in [Colour::L, Colour::I, Colour::T, Colour::S]
+        {
+            features.push(1500 + 6 * archetype.as_index() + self.pieces_remaining.get(& archetype));
+        }
+
+        if self.to_move == Player::O 
+        {
+            features.push(1524);
+        }
+
+        features
     }
 
     ///
-    /// Returns a new board with the given state.
+    /// Returns the index of the key of every feature that playing the given tetromino 
+    /// adds to or removes from this board: the covered tiles before and after, the count 
+    /// of the tetromino's colour before and after, and the player to move.
     ///
-    pub fn new (score_tiles: & Vec<Vec<Player>>, piece_tiles: & Vec<Vec<Colour>>, remaining: & Vec<usize>, to_move: Player) -> Result<Board>
+    fn features_changed (& self, tetromino: & Tetromino) -> Vec<usize>
     {
-        let context = "Failed to create a new board.";
-        let score_tiles = score_tiles.clone();
-        let piece_tiles = piece_tiles.clone();
-        let pieces_remaining = remaining.clone();
-        let attach_points = BTreeMap::new();
-        let move_cache = RefCell::new(None);
-        let has_moves = RefCell::new(None);
-        
-        for archetype in [Colour::L, Colour::I, Colour::T, Colour::S]
+        let colour = tetromino.colour();
+        let mut features = Vec::with_capacity(11);
+
+        for p in tetromino.points_real()
         {
-            let num = pieces_remaining[archetype.as_index()];
-            match num 
+            let (i, j) = (p.x() as usize, p.y() as usize);
+            let score = 5 * self.score_at(i, j).as_index_null();
+            let before = score + self.piece_at(i, j).as_index_null();
+            if before != 0 
             {
-                0 ..= 5 => {},
-                _       => return Err(error::error!("Invalid number of remaining pieces {} for colour '{}'.", num, archetype.notate()))
-                            .context(context.clone())
+                features.push(15 * (10 * i + j) + before);
             }
+            features.push(15 * (10 * i + j) + score + colour.as_index_null());
         }
 
-        let mut b = Board { score_tiles, piece_tiles, pieces_remaining, attach_points, to_move, move_cache, has_moves };
-        b.calculate_attach_points_from_scratch();
-        Ok(b)
+        let remaining = self.pieces_remaining.get(& colour);
+        features.push(1500 + 6 * colour.as_index() + remaining);
+        features.push(1500 + 6 * colour.as_index() + remaining.saturating_sub(1));
+        features.push(1524);
+
+        features
     }
 
     ///
-    /// Returns the hexadecimal notation for the tile.
+    /// Returns a short identifier for this position for use in logs, made of the first 
+    /// 8 hex digits of its Zobrist hash and the number of pieces played, e.g. "1a2b3c4d@7".
     ///
-    pub fn notate_tile (& self, i: i32, j: i32) -> String 
+    pub fn fingerprint (& self) -> String 
     {
-        let value = 5 * self.player_at(i, j).as_index_null() + self.colour_at(i, j).as_index_null();
-        ["0", "1", "2", "3", "4", "5", "6", "7", "8", "9", "a", "b", "c", "d", "e", "f"].get(value).unwrap().to_string()
+        format!("{:08x}@{}", self.zobrist_hash() >> 32, 4 * PiecePool::MAX - self.pieces_remaining.total())
     }
 
     ///
-    /// Parses the tile.
+    /// Creates a board from 10 rows of 10 tiles each, written in the tile notation; the 
+    /// first row is the top of the board (y = 9), matching the board's display.
     ///
-    pub fn parse_tile (s: & str) -> Result<(Player, Colour)>
+    pub fn from_grid (rows: & [& str], remaining: [usize; 4], to_move: Player) -> Result<Board>
     {
-        match s 
+        let context = "Failed to create a board from a grid.";
+
+        if rows.len() != 10 
         {
-            "0" => Ok((Player::None, Colour::None)),
-            "1" => Ok((Player::None, Colour::L)),
-            "2" => Ok((Player::None, Colour::I)),
-            "3" => Ok((Player::None, Colour::T)),
-            "4" => Ok((Player::None, Colour::S)),
-            "5" => Ok((Player::X,    Colour::None)),
-            "6" => Ok((Player::X,    Colour::L)),
-            "7" => Ok((Player::X,    Colour::I)),
-            "8" => Ok((Player::X,    Colour::T)),
-            "9" => Ok((Player::X,    Colour::S)),
-            "a" => Ok((Player::O,    Colour::None)),
-            "b" => Ok((Player::O,    Colour::L)),
-            "c" => Ok((Player::O,    Colour::I)),
-            "d" => Ok((Player::O,    Colour::T)),
-            "e" => Ok((Player::O,    Colour::S)),
-            _   => Err(error::error!(format!("Invalid notation '{}' for tile.", s)))
+            return Err(error::error!("Expected 10 rows, but got {}.", rows.len())).context(context);
+        }
+
+        let mut score_tiles : Vec<Vec<Player>> = vec![vec![Player::None; 10]; 10];
+        let mut piece_tiles : Vec<Vec<Colour>> = vec![vec![Colour::None; 10]; 10];
+
+        for (r, row) in rows.iter().enumerate()
+        {
+            let tiles = row.chars().collect::<Vec<char>>();
+            if tiles.len() != 10 
+            {
+                return Err(error::error!("Expected 10 tiles in row {}, but got {}.", r, tiles.len())).context(context);
+            }
+
+            for (i, tile) in tiles.iter().enumerate()
+            {
+                let (score, piece) = Board::parse_tile(& tile.to_string()).context(context)?;
+                score_tiles[i][9 - r] = score;
+                piece_tiles[i][9 - r] = piece;
+            }
         }
+
+        Board::new(& score_tiles, & piece_tiles, & remaining.to_vec(), to_move).context(context)
     }
 
     ///
-    /// Places the tetromino, provided it is a legal move, and updates the attach points 
-    /// on this board.
+    /// Determines whether any more moves are possible in this position.
     ///
-    pub fn place_tetromino (& mut self, tetromino: & Tetromino) -> Result<()>
+    pub fn has_moves (& self) -> bool 
     {
-        // Check if the tetromino is valid in the position.
+        if let Some(status) = self.has_moves.borrow().clone()
+        {
+            return status;
+        }
 
-        let context = notate!("Failed to play tetromino '{}' in position '{}'.", tetromino, self);
-        self.validate_tetromino(tetromino).context(context.clone())?;
+        let has = self.moves_iter().next().is_some();
 
-        // Play the tetromino.
+        * self.has_moves.borrow_mut() = Some(has);
+        has
+    }
 
-        self.pieces_remaining[tetromino.colour().as_index()] -= 1;
-        let points = tetromino.points_real();
-        points.iter().for_each(|& p| { self.piece_tiles[p.x() as usize][p.y() as usize] = tetromino.colour(); } );
-        self.to_move = self.to_move.next();
+    ///
+    /// Infers the move that takes this board to the given board, provided that the only 
+    /// difference between them is a single tetromino that is legal in this position.
+    ///
+    pub fn infer_move (& self, after: & Board) -> Option<Tetromino>
+    {
+        let changes = self.diff(after);
+        let colour = changes.first()?.2;
 
-        // Update the attach points, using the real points as hints.
+        if changes.len() != 4 || changes.iter().any(|& (_, old, new)| old != Colour::None || new != colour)
+        {
+            return None;
+        }
 
-        self.cache_bust();
-        self.update_attach_points_add(tetromino);
+        let points = changes.iter().map(|& (point, _, _)| point).collect::<Vec<Point>>();
+        let tetromino = Tetromino::from_points_with_colour(& colour, & points).ok()?;
 
-        Ok(())
+        self.validate_tetromino(& tetromino).ok().map(|_| tetromino)
     }
 
     ///
-    /// Returns the player at the given tile.
+    /// Determines whether there are no pieces on this board, in which case every
+    /// tile is an attach point.
     ///
-    pub fn player_at (& self, i: i32, j: i32) -> Player 
+    pub fn is_empty (& self) -> bool 
     {
-        self.score_tiles[i as usize][j as usize]
+        self.num_coloured_tiles() == 0
     }
 
     ///
-    /// Determines whether the given real point attaches.
+    /// Determines whether no piece covers the given point.
     ///
-    pub fn point_attach_exists (& self, point: & Point) -> bool 
+    fn is_free (& self, point: & Point) -> bool 
     {
-        self.attach_points.contains_key(& point) 
+        ! self.occupied().contains(point.x() as usize, point.y() as usize)
     }
 
     ///
-    /// Determines whether the given real point attaches to a tile of the same colour.
+    /// Returns the number of legal moves of each colour in this position, in LITS order.
     ///
-    pub fn point_attach_same_colour (& self, point: & Point, colour: & Colour) -> bool 
+    pub fn legal_move_counts (& self) -> [usize; 4]
     {
-        for neighbour in point.neighbours_on_board()
-        {
-            if self.piece_tiles[neighbour.x() as usize][neighbour.y() as usize] == * colour
-            {
-                return true;
-            }
-        }
-        false
+        let mut counts = [0; 4];
+        self.enumerate_moves().iter().for_each(|tetromino| counts[tetromino.colour().as_index()] += 1);
+        counts
     }
 
     ///
-    /// Prints this board's attach points.
+    /// Plays the given tetromino, like `place_tetromino`, and returns a token that `unmake` 
+    /// uses to take the move back cheaply.
     ///
-    pub fn print_attach_points (& self)
+    pub fn make (& mut self, tetromino: & Tetromino) -> Result<UndoToken>
     {
-        for j in 0 ..= 9
+        // The first piece on the board recomputes every attach point, while any other
+        // piece only changes the attach points on and around it.
+
+        let touched : Vec<Point> = match self.is_empty()
         {
-            let j = 9 - j;
-            let mut linestr = "".to_owned();
-            for i in 0 ..= 9 
-            {
+            true  => (0 .. 10).flat_map(|i| (0 .. 10).map(move |j| Point::new(i, j))).collect(),
+            false => tetromino.points_real().into_iter().chain(tetromino.get_attaches()).collect()
+        };
+        let attach_points = touched.into_iter().map(|p| (p, self.attach_points.get(& p).cloned())).collect();
+
+        self.place_tetromino(tetromino)?;
+
+        Ok(UndoToken { tetromino: tetromino.clone(), attach_points })
+    }
+
+    ///
+    /// Returns the number of uncovered scoring tiles X leads by, which is negative when O leads; 
+    /// unlike score(), this is not normalized by the number of uncovered tiles.
+    ///
+    pub fn margin (& self) -> i32 
+    {
+        let occupied = self.occupied();
+        let x = self.score_bits[Player::X.as_index()].without(occupied).count() as i32;
+        let o = self.score_bits[Player::O.as_index()].without(occupied).count() as i32;
+
+        x - o
+    }
+
+    ///
+    /// Returns a lazy iterator over the legal moves in this position, in no particular order.
+    ///
+    /// Candidates are generated around each attach point; since a legal move is generated 
+    /// at every attach point it covers, it is only yielded at the least of them, so each 
+    /// move appears exactly once without collecting the moves into a set.
+    ///
+    pub fn moves_iter (& self) -> impl Iterator<Item = Tetromino> + '_
+    {
+        let mut attach_bits = Bitboard::empty();
+        self.attach_points.keys().for_each(|p| attach_bits.insert(p.x() as usize, p.y() as usize));
+
+        self.attach_points.iter()
+            .flat_map(move |(attach, colours)| 
+            {
+                attach.get_potential_anchors().into_iter()
+                    .flat_map(move |anchor| 
+                    {
+                        colours.iter()
+                            .filter(|colour| self.pieces_remaining.get(colour) > 0)
+                            .flat_map(move |colour| Tetromino::get_reference_tetromino(colour, & anchor).enumerate_transforms())
+                    })
+                    .filter(move |tetromino| 
+                    {
+                        let first = Bitboard::from_points(& tetromino.points_real()).and_then(|bits| (bits & attach_bits).lowest());
+                        first == Some((attach.x() as usize, attach.y() as usize))
+                    })
+            })
+            .filter(|tetromino| self.violation(tetromino, PlacementRules::standard()).is_none())
+    }
+
+    ///
+    /// Returns the colours of the pieces orthogonally adjacent to the given point.
+    ///
+    pub fn neighbour_colours (& self, point: & Point) -> BTreeSet<Colour>
+    {
+        point.neighbours_on_board().iter()
+            .map(|& p| self.piece_at(p.x() as usize, p.y() as usize))
+            .filter(|& colour| colour != Colour::None)
+            .collect()
+    }
+
+    ///
+    /// Returns a new board with the given state.
+    ///
+    pub fn new (score_tiles: & Vec<Vec<Player>>, piece_tiles: & Vec<Vec<Colour>>, remaining: & Vec<usize>, to_move: Player) -> Result<Board>
+    {
+        let context = "Failed to create a new board.";
+        let pieces_remaining = PiecePool::new(remaining).context(context)?;
+        Ok(Board::with_pool(score_tiles, piece_tiles, pieces_remaining, to_move))
+    }
+
+    ///
+    /// Returns the number of tiles covered by pieces, including any setup pieces.
+    ///
+    pub fn num_coloured_tiles (& self) -> usize 
+    {
+        self.occupied().count()
+    }
+
+    ///
+    /// Returns the hexadecimal notation for the tile.
+    ///
+    pub fn notate_tile (& self, i: i32, j: i32) -> String 
+    {
+        let value = 5 * self.player_at(i, j).as_index_null() + self.colour_at(i, j).as_index_null();
+        ["0", "1", "2", "3", "4", "5", "6", "7", "8", "9", "a", "b", "c", "d", "e", "f"].get(value).unwrap().to_string()
+    }
+
+    ///
+    /// Returns the tiles covered by pieces.
+    ///
+    fn occupied (& self) -> Bitboard 
+    {
+        self.piece_bits[0] | self.piece_bits[1] | self.piece_bits[2] | self.piece_bits[3]
+    }
+
+    ///
+    /// Parses the tile.
+    ///
+    pub fn parse_tile (s: & str) -> Result<(Player, Colour)>
+    {
+        match s 
+        {
+            "0" => Ok((Player::None, Colour::None)),
+            "1" => Ok((Player::None, Colour::L)),
+            "2" => Ok((Player::None, Colour::I)),
+            "3" => Ok((Player::None, Colour::T)),
+            "4" => Ok((Player::None, Colour::S)),
+            "5" => Ok((Player::X,    Colour::None)),
+            "6" => Ok((Player::X,    Colour::L)),
+            "7" => Ok((Player::X,    Colour::I)),
+            "8" => Ok((Player::X,    Colour::T)),
+            "9" => Ok((Player::X,    Colour::S)),
+            "a" => Ok((Player::O,    Colour::None)),
+            "b" => Ok((Player::O,    Colour::L)),
+            "c" => Ok((Player::O,    Colour::I)),
+            "d" => Ok((Player::O,    Colour::T)),
+            "e" => Ok((Player::O,    Colour::S)),
+            _   => Err(error::error!(format!("Invalid notation '{}' for tile.", s)))
+        }
+    }
+
+    ///
+    /// Returns the colour of the piece covering the given tile.
+    ///
+    fn piece_at (& self, i: usize, j: usize) -> Colour 
+    {
+        [Colour::L, Colour::I, Colour::T, Colour::S].into_iter()
+            .find(|colour| self.piece_bits[colour.as_index()].contains(i, j))
+            .unwrap_or(Colour::None)
+    }
+
+    ///
+    /// Returns the grid of piece colours, indexed by column and then by row.
+    ///
+    fn piece_grid (& self) -> Vec<Vec<Colour>>
+    {
+        (0 .. 10).map(|i| (0 .. 10).map(|j| self.piece_at(i, j)).collect()).collect()
+    }
+
+    ///
+    /// Places the tetromino, provided it is a legal move, and updates the attach points 
+    /// on this board.
+    ///
+    pub fn place_tetromino (& mut self, tetromino: & Tetromino) -> Result<()>
+    {
+        self.place_tetromino_with_rules(tetromino, PlacementRules::standard())
+    }
+
+    ///
+    /// Plays the given tetromino on this board, provided it is valid under the given rules.
+    ///
+    pub fn place_tetromino_with_rules (& mut self, tetromino: & Tetromino, rules: PlacementRules) -> Result<()>
+    {
+        // Check if the tetromino is valid in the position.
+
+        let context = notate!("Failed to play tetromino '{}' in position '{}'.", tetromino, self);
+        self.validate_tetromino_with_rules(tetromino, rules).context(context.clone())?;
+
+        // Play the tetromino.
+
+        let colour = tetromino.colour();
+        self.pieces_remaining.set(& colour, self.pieces_remaining.get(& colour) - 1)?;
+        let points = tetromino.points_real();
+        points.iter().for_each(|& p| self.set_piece_at(p.x() as usize, p.y() as usize, colour));
+        self.to_move = self.to_move.next();
+
+        // Update the attach points, using the real points as hints.
+
+        self.cache_bust();
+        self.update_attach_points_add(tetromino);
+
+        Ok(())
+    }
+
+    ///
+    /// Returns the player at the given tile.
+    ///
+    pub fn player_at (& self, i: i32, j: i32) -> Player 
+    {
+        self.score_at(i as usize, j as usize)
+    }
+
+    ///
+    /// Determines whether the given real point attaches.
+    ///
+    pub fn point_attach_exists (& self, point: & Point) -> bool 
+    {
+        self.attach_points.contains_key(& point) 
+    }
+
+    ///
+    /// Determines whether the given real point attaches to a tile of the same colour.
+    ///
+    pub fn point_attach_same_colour (& self, point: & Point, colour: & Colour) -> bool 
+    {
+        self.neighbour_colours(point).contains(colour)
+    }
+
+    ///
+    /// Prints this board's attach points.
+    ///
+    pub fn print_attach_points (& self)
+    {
+        for j in 0 ..= 9
+        {
+            let j = 9 - j;
+            let mut linestr = "".to_owned();
+            for i in 0 ..= 9 
+            {
                 linestr += & match self.attach_points.contains_key(& Point::new(i, j))
                 {
                     true  => format!("{}", Player::X),
@@ -498,12 +890,91 @@ impl Board
         println!("");
     }
 
+    ///
+    /// Returns a blank board with the given number of scoring tiles for each player, drawing
+    /// randomness from the given generator. 
+    ///
+    /// Without a pairing symmetry, the tiles are placed on distinct tiles uniformly at random.
+    /// With one, which must be its own inverse, each X tile is paired with an O tile on its 
+    /// image, so that the setup is balanced between the players; tiles that the symmetry fixes
+    /// are left blank.
+    ///
+    pub fn random_setup<R> (rng: & mut R, tiles_per_player: usize, pairing: Option<& Transform>) -> Result<Board>
+        where R: FnMut () -> u64
+    {
+        let context = "Failed to generate a random setup.";
+
+        // Each candidate is a pair of tiles, one for each player; without a symmetry, any two
+        // tiles may form a pair.
+
+        let index = |point: & Point| (10 * point.x() + point.y()) as usize;
+        let mut pairs = match pairing 
+        {
+            Some(transform) => 
+            {
+                let mut pairs = Vec::new();
+                for i in 0 .. 10 
+                {
+                    for j in 0 .. 10 
+                    {
+                        let point = Point::new(i, j);
+                        let image = symmetry::transform_point(transform, & point);
+                        if symmetry::transform_point(transform, & image) != point
+                        {
+                            return Err(error::error!("The symmetry {:?} is not its own inverse.", transform)).context(context);
+                        }
+                        if index(& point) < index(& image)
+                        {
+                            pairs.push((index(& point), index(& image)));
+                        }
+                    }
+                }
+                pairs
+            },
+            None            => (0 .. 50).map(|i| (2 * i, 2 * i + 1)).collect()
+        };
+
+        if tiles_per_player > pairs.len()
+        {
+            return Err(error::error!("There is room for {} scoring tiles per player, not {}.", pairs.len(), tiles_per_player)).context(context);
+        }
+
+        // Without a symmetry, shuffling all 100 tiles and then pairing them up is uniform.
+
+        let mut tiles = pairs.iter().flat_map(|& (a, b)| [a, b]).collect::<Vec<usize>>();
+        if pairing.is_none()
+        {
+            for i in (1 .. tiles.len()).rev()
+            {
+                tiles.swap(i, (rng() % (i as u64 + 1)) as usize);
+            }
+            pairs = tiles.chunks(2).map(|pair| (pair[0], pair[1])).collect();
+        }
+
+        let mut board = Board::blank();
+        for i in 0 .. tiles_per_player 
+        {
+            let j = i + (rng() % (pairs.len() - i) as u64) as usize;
+            pairs.swap(i, j);
+
+            let (x, o) = match rng() % 2 
+            {
+                0 => pairs[i],
+                _ => (pairs[i].1, pairs[i].0)
+            };
+            board.set_scoring_tile(x / 10, x % 10, & Player::X);
+            board.set_scoring_tile(o / 10, o % 10, & Player::O);
+        }
+
+        Ok(board)
+    }
+
     ///
     /// Gets the number of tetrominos of the given colour remaining to be played.
     ///
     pub fn remaining_of (& self, colour: & Colour) -> usize 
     {
-        self.pieces_remaining[colour.as_index()]
+        self.pieces_remaining.get(colour)
     }
 
     ///
@@ -545,20 +1016,64 @@ impl Board
     ///
     pub fn score (& self) -> f64 
     {
-        let mut sum = 0.0;
-        let mut diff = 0.0;
-        for i in 0 .. 10 
+        let (x, o) = self.score_breakdown();
+        x - o
+    }
+
+    ///
+    /// Returns the player scoring the given tile.
+    ///
+    fn score_at (& self, i: usize, j: usize) -> Player 
+    {
+        [Player::X, Player::O].into_iter()
+            .find(|player| self.score_bits[player.as_index()].contains(i, j))
+            .unwrap_or(Player::None)
+    }
+
+    ///
+    /// Returns the exposed scoring value of each player as an (X, O) pair, on the 
+    /// same scale as score(), so that their difference is the score of this board.
+    ///
+    pub fn score_breakdown (& self) -> (f64, f64)
+    {
+        let occupied = self.occupied();
+        let sum = (100 - occupied.count()) as f64;
+        let x = self.score_bits[Player::X.as_index()].without(occupied).count() as f64;
+        let o = self.score_bits[Player::O.as_index()].without(occupied).count() as f64;
+
+        (x / (sum + 0.1), o / (sum + 0.1))
+    }
+
+    ///
+    /// Returns the grid of scoring players, indexed by column and then by row.
+    ///
+    fn score_grid (& self) -> Vec<Vec<Player>>
+    {
+        (0 .. 10).map(|i| (0 .. 10).map(|j| self.score_at(i, j)).collect()).collect()
+    }
+
+    ///
+    /// Sets the colour of the piece covering the given tile.
+    ///
+    fn set_piece_at (& mut self, i: usize, j: usize, colour: Colour)
+    {
+        self.piece_bits.iter_mut().for_each(|bits| bits.remove(i, j));
+        if colour != Colour::None 
         {
-            for j in 0 .. 10 
-            {
-                if self.piece_tiles[i][j] == Colour::None 
-                {
-                    diff += self.score_tiles[i][j].value();
-                    sum += 1.0;
-                }
-            }
+            self.piece_bits[colour.as_index()].insert(i, j);
+        }
+    }
+
+    ///
+    /// Sets the player scoring the given tile.
+    ///
+    fn set_score_at (& mut self, i: usize, j: usize, player: Player)
+    {
+        self.score_bits.iter_mut().for_each(|bits| bits.remove(i, j));
+        if player != Player::None 
+        {
+            self.score_bits[player.as_index()].insert(i, j);
         }
-        diff / (sum + 0.1)
     }
 
     ///
@@ -566,7 +1081,30 @@ impl Board
     ///
     pub fn set_scoring_tile (& mut self, i: usize, j: usize, player: & Player)
     {
-        * self.score_tiles.get_mut(i).unwrap().get_mut(j).unwrap() = * player;
+        self.set_score_at(i, j, * player);
+    }
+
+    ///
+    /// Returns this board with the players swapped: every scoring tile changes hands, 
+    /// and the other player is to move. The pieces are left intact, so the score negates.
+    ///
+    pub fn swap_players (& self) -> Board 
+    {
+        let mut board = self.clone();
+
+        board.score_bits.swap(0, 1);
+        board.to_move = board.to_move.next();
+
+        board
+    }
+
+    ///
+    /// Returns the lowest corner of every 2-by-2 square that covers a tile in the given set.
+    ///
+    fn squares_touching (bits: Bitboard) -> Bitboard 
+    {
+        let b = bits.0;
+        Bitboard(b | (b >> 1) | (b >> 10) | (b >> 11))
     }
 
     ///
@@ -574,6 +1112,11 @@ impl Board
     ///
     pub fn tetromino_attach_forms_o (& self, points: & Vec<Point>) -> bool 
     {
+        if let Some(bits) = Bitboard::from_points(points)
+        {
+            return (self.occupied() | bits).square_corners().intersects(Board::squares_touching(bits));
+        }
+
         // Normalize the points, and take the anchor position as if the points are 
         // contained in a bounding box with padding size 1.
         
@@ -590,12 +1133,9 @@ impl Board
             for j in 0 .. 6 
             {
                 let here = Point::new(i, j) + anchor;
-                if here.in_bounds()
+                if here.in_bounds() && ! self.is_free(& here)
                 {
-                    if self.piece_tiles[here.x() as usize][here.y() as usize] != Colour::None
-                    {
-                        grid[i as usize][j as usize] = true;
-                    }
+                    grid[i as usize][j as usize] = true;
                 }
             }
         }
@@ -621,7 +1161,7 @@ impl Board
     ///
     pub fn tetromino_exists (& self, tetromino: & Tetromino) -> bool 
     {
-        tetromino.points_real().iter().all(|& p| self.piece_tiles[p.x() as usize][p.y() as usize] == tetromino.colour())
+        tetromino.points_real().iter().all(|& p| self.piece_at(p.x() as usize, p.y() as usize) == tetromino.colour())
     }
 
     ///
@@ -641,7 +1181,7 @@ impl Board
 
         let context = format!("Failed to undo tetromino.");
 
-        let _ = self.pieces_remaining[tetromino.colour().as_index()] < 5
+        let _ = self.pieces_remaining.get(& tetromino.colour()) < PiecePool::MAX
             || return Err(error::error!(notate!("There are no '{}'s on the board.", tetromino.colour()))).context(context.clone());
 
         let _ = self.tetromino_exists(tetromino)
@@ -649,9 +1189,10 @@ impl Board
 
         // Remove the piece.
 
-        self.pieces_remaining[tetromino.colour().as_index()] += 1;
+        let colour = tetromino.colour();
+        self.pieces_remaining.set(& colour, self.pieces_remaining.get(& colour) + 1).context(context.clone())?;
         let points = tetromino.points_real();
-        points.iter().for_each(|& p| { self.piece_tiles[p.x() as usize][p.y() as usize] = Colour::None; } );
+        points.iter().for_each(|& p| self.set_piece_at(p.x() as usize, p.y() as usize, Colour::None));
         self.to_move = self.to_move.next();
 
         // Update the attach points.
@@ -662,6 +1203,28 @@ impl Board
         Ok(())
     }
 
+    ///
+    /// Takes back the move recorded in the given token, which must be the last move made
+    /// on this board.
+    ///
+    pub fn unmake (& mut self, token: UndoToken)
+    {
+        let colour = token.tetromino.colour();
+        self.pieces_remaining.set(& colour, self.pieces_remaining.get(& colour) + 1).expect("Unmade a move that was not made.");
+        token.tetromino.points_real().iter().for_each(|& p| self.set_piece_at(p.x() as usize, p.y() as usize, Colour::None));
+        self.to_move = self.to_move.next();
+
+        self.cache_bust();
+        for (point, colourset) in token.attach_points 
+        {
+            match colourset 
+            {
+                Some(colourset) => { self.attach_points.insert(point, colourset); },
+                None            => { self.attach_points.remove(& point); }
+            };
+        }
+    }
+
     ///
     /// Updates the attach points on this board given the hinting points that were 
     /// added in a placement.
@@ -670,10 +1233,10 @@ impl Board
     {
         // Remove all attach points that overlap with the played piece.
 
-        if self.pieces_remaining.iter().sum::<usize>() == 19 
+        if self.num_coloured_tiles() == tetromino.points_real().len()
         {
-            // Then we need to recalculate, because the first move is either a blank 
-            // board (which has full attach points) or has a special position.
+            // Then this was the first piece on the board, so we need to recalculate, 
+            // because a blank board has full attach points.
 
             self.calculate_attach_points_from_scratch();
         }
@@ -690,7 +1253,7 @@ impl Board
             // because the tetromino played here could not neighbour its own colour.
 
             let new_attaches = tetromino.get_attaches().into_iter()
-                .filter(|& p| self.piece_tiles[p.x() as usize][p.y() as usize] == Colour::None)
+                .filter(|& p| self.is_free(& p))
                 .collect::<BTreeSet<Point>>();
 
             for new_attach in & new_attaches
@@ -719,7 +1282,7 @@ impl Board
     ///
     pub fn update_attach_points_sub (& mut self, tetromino: & Tetromino)
     {
-        if self.pieces_remaining.iter().sum::<usize>() == 20 
+        if self.is_empty()
         {
             self.calculate_attach_points_from_scratch();
         }
@@ -732,15 +1295,15 @@ impl Board
             // itself.
 
             let created_attaches = tetromino.get_attaches().into_iter()
-                .filter(|& p| self.piece_tiles[p.x() as usize][p.y() as usize] == Colour::None)
+                .filter(|& p| self.is_free(& p))
                 .collect::<BTreeSet<Point>>();
             
             for old_attach in & created_attaches
             {
-                if old_attach.neighbours_on_board().iter().any(|& p| self.piece_tiles[p.x() as usize][p.y() as usize] != Colour::None)
+                if old_attach.neighbours_on_board().iter().any(|& p| ! self.is_free(& p))
                 {
                     let mut colourset : BTreeSet<Colour> = BTreeSet::from([Colour::L, Colour::I, Colour::T, Colour::S]);
-                    old_attach.neighbours_on_board().iter().for_each(|& p| { colourset.remove(& self.piece_tiles[p.x() as usize][p.y() as usize]); });
+                    old_attach.neighbours_on_board().iter().for_each(|& p| { colourset.remove(& self.piece_at(p.x() as usize, p.y() as usize)); });
                     
                     self.attach_points.remove(old_attach);
                     self.attach_points.insert(* old_attach, colourset);
@@ -758,10 +1321,10 @@ impl Board
 
             for point in & tetromino.points_real()
             {
-                if point.neighbours_on_board().iter().any(|& p| self.piece_tiles[p.x() as usize][p.y() as usize] != Colour::None)
+                if point.neighbours_on_board().iter().any(|& p| ! self.is_free(& p))
                 {
                     let mut colourset : BTreeSet<Colour> = BTreeSet::from([Colour::L, Colour::I, Colour::T, Colour::S]);
-                    point.neighbours_on_board().iter().for_each(|& p| { colourset.remove(& self.piece_tiles[p.x() as usize][p.y() as usize]); });
+                    point.neighbours_on_board().iter().for_each(|& p| { colourset.remove(& self.piece_at(p.x() as usize, p.y() as usize)); });
                     
                     self.attach_points.remove(point);
                     self.attach_points.insert(* point, colourset);
@@ -774,30 +1337,426 @@ impl Board
     /// Determines whether playing the given tetromino is valid in this state.
     ///
     pub fn validate_tetromino (& self, tetromino: & Tetromino) -> Result<()>
+    {
+        self.validate_tetromino_with_rules(tetromino, PlacementRules::standard())
+    }
+
+    ///
+    /// Determines whether the given tetromino is valid in this position under the given rules.
+    ///
+    pub fn validate_tetromino_with_rules (& self, tetromino: & Tetromino, rules: PlacementRules) -> Result<()>
     {
         let context = "Failed to validate tetromino.";
 
+        match self.violation(tetromino, rules)
+        {
+            Some(reason) => Err(reason).context(context),
+            None         => Ok(())
+        }
+    }
+
+    ///
+    /// Returns the first rule that the given tetromino breaks in this position, if any; 
+    /// this is the allocation-free core of validation, so move generation calls it directly.
+    ///
+    pub fn violation (& self, tetromino: & Tetromino, rules: PlacementRules) -> Option<PlacementError>
+    {
         let points = tetromino.points_real();
         let colour = tetromino.colour();
 
-        let _ = self.pieces_remaining[colour.as_index()] > 0 
-            || return Err(error::error!("No more copies.")).context(context.clone());
+        if self.pieces_remaining.get(& colour) == 0 
+        {
+            return Some(PlacementError::OutOfPieces);
+        }
 
-        let _ = points.iter().all(|& p| p.in_bounds()) 
-            || return Err(error::error!("Not in bounds.")).context(context.clone());
+        let bits = match Bitboard::from_points(& points)
+        {
+            Some(bits) => bits,
+            None       => return Some(PlacementError::OutOfBounds)
+        };
        
-        let _ = ! points.iter().any(|& p| self.piece_tiles[p.x() as usize][p.y() as usize] != Colour::None)
-            || return Err(error::error!("Overlaps an existing piece.")).context(context.clone());
+        if bits.intersects(self.occupied())
+        {
+            return Some(PlacementError::Overlaps);
+        }
 
-        let _ = points.iter().any(|& p| self.point_attach_exists(& p))
-            || return Err(error::error!("No attach point.")).context(context.clone());
+        if ! points.iter().any(|p| self.point_attach_exists(p))
+        {
+            return Some(PlacementError::NoAttach);
+        }
 
-        let _ = ! points.iter().any(|& p| self.point_attach_same_colour(& p, & colour))
-            || return Err(error::error!("Attaches to same colour.")).context(context.clone()); 
+        if rules.forbid_same_colour && bits.neighbours().intersects(self.piece_bits[colour.as_index()])
+        {
+            return Some(PlacementError::SameColourAdjacent);
+        }
         
-        let _ = ! self.tetromino_attach_forms_o(& points)
-            || return Err(error::error!("Forms a 2-by-2 square.")).context(context.clone());
+        if rules.forbid_squares && (self.occupied() | bits).square_corners().intersects(Board::squares_touching(bits))
+        {
+            return Some(PlacementError::FormsSquare);
+        }
 
-        Ok(())
+        None
+    }
+
+    ///
+    /// Returns a new board with the given state and piece pool.
+    ///
+    pub fn with_pool (score_tiles: & Vec<Vec<Player>>, piece_tiles: & Vec<Vec<Colour>>, pieces_remaining: PiecePool, to_move: Player) -> Board
+    {
+        let attach_points = BTreeMap::new();
+        let move_cache = RefCell::new(None);
+        let has_moves = RefCell::new(None);
+
+        let mut b = Board { score_bits: [Bitboard::empty(); 2], piece_bits: [Bitboard::empty(); 4], pieces_remaining, attach_points, to_move, move_cache, has_moves };
+        for i in 0 .. 10 
+        {
+            for j in 0 .. 10 
+            {
+                b.set_score_at(i, j, score_tiles[i][j]);
+                b.set_piece_at(i, j, piece_tiles[i][j]);
+            }
+        }
+        b.calculate_attach_points_from_scratch();
+        b
+    }
+
+    ///
+    /// Returns the Zobrist hash of this board, which covers the tiles, the piece 
+    /// pool and the player to move. Equal boards always hash equally.
+    ///
+    pub fn zobrist_hash (& self) -> u64 
+    {
+        self.features().into_iter().fold(0, |hash, feature| hash ^ ZOBRIST_KEYS[feature])
+    }
+
+    ///
+    /// Returns the Zobrist hash of the board after playing the given tetromino, given this 
+    /// board's hash; only the features that the tetromino changes are visited.
+    ///
+    pub fn zobrist_hash_after (& self, hash: u64, tetromino: & Tetromino) -> u64 
+    {
+        self.features_changed(tetromino).into_iter().fold(hash, |hash, feature| hash ^ ZOBRIST_KEYS[feature])
+    }
+}
+
+#[cfg(test)]
+mod tests 
+{
+    use super::*;
+
+    ///
+    /// Plays a random game from the blank board with the given seed, calling the given 
+    /// function with the board before each move and the move.
+    ///
+    fn random_game<F> (seed: u64, mut f: F)
+        where F: FnMut (& mut Board, & Tetromino)
+    {
+        Tetromino::initialize();
+
+        let mut state = seed;
+        let mut board = Board::blank();
+        while board.has_moves()
+        {
+            let moves = board.enumerate_moves().into_iter().collect::<Vec<Tetromino>>();
+            state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            let tetromino = moves[(state >> 33) as usize % moves.len()].clone();
+
+            f(& mut board, & tetromino);
+            board.place_tetromino(& tetromino).unwrap();
+        }
+    }
+
+    #[test]
+    fn incremental_hashes_match_full_hashes ()
+    {
+        for seed in 0 .. 4 
+        {
+            random_game(seed, |board, tetromino| 
+            {
+                let (hash, checksum) = (board.zobrist_hash_after(board.zobrist_hash(), tetromino), board.checksum_after(board.checksum(), tetromino));
+
+                let mut after = board.clone();
+                after.place_tetromino(tetromino).unwrap();
+                assert_eq!(hash, after.zobrist_hash());
+                assert_eq!(checksum, after.checksum());
+            });
+        }
+    }
+
+    #[test]
+    fn unmake_restores_board_exactly ()
+    {
+        for seed in 0 .. 4 
+        {
+            let mut boards = Vec::new();
+            let mut moves = Vec::new();
+
+            random_game(seed, |board, tetromino| 
+            {
+                let mut before = board.clone();
+                before.cache_bust();
+                boards.push(before);
+                moves.push(tetromino.clone());
+            });
+
+            let mut board = Board::blank();
+            let mut tokens = moves.iter().map(|tetromino| board.make(tetromino).unwrap()).collect::<Vec<UndoToken>>();
+
+            while let Some(token) = tokens.pop()
+            {
+                board.unmake(token);
+                assert_eq!(board, boards[tokens.len()]);
+            }
+        }
+    }
+
+    #[test]
+    fn equal_positions_share_a_map_entry ()
+    {
+        Tetromino::initialize();
+
+        let tetromino = Board::blank().enumerate_moves().into_iter().next().unwrap();
+        let (mut a, mut b) = (Board::blank(), Board::blank());
+        a.place_tetromino(& tetromino).unwrap();
+        b.place_tetromino(& tetromino).unwrap();
+        a.enumerate_moves();
+
+        let mut map = std::collections::HashMap::new();
+        map.insert(a, 0);
+        map.insert(b, 1);
+        assert_eq!(map.len(), 1);
+    }
+
+    #[test]
+    fn score_breakdown_counts_exposed_tiles ()
+    {
+        Tetromino::initialize();
+
+        let mut rows = vec!["5500000a00"];
+        rows.extend(["0000000000"; 9]);
+        let mut board = Board::from_grid(& rows, [PiecePool::MAX; 4], Player::X).unwrap();
+
+        let (x, o) = board.score_breakdown();
+        assert_eq!((x, o), (2.0 / 100.1, 1.0 / 100.1));
+        assert_eq!(x - o, board.score());
+
+        // A piece over one of X's tiles leaves it unexposed, and the remaining tiles count for more.
+
+        let covered = [Point::new(0, 9), Point::new(1, 9), Point::new(7, 9)];
+        let tetromino = board.enumerate_moves().into_iter()
+            .find(|tetromino| tetromino.points_real().iter().filter(|point| covered.contains(point)).count() == 1 && tetromino.points_real().contains(& covered[0]))
+            .unwrap();
+        board.place_tetromino(& tetromino).unwrap();
+
+        let (x, o) = board.score_breakdown();
+        assert_eq!((x, o), (1.0 / 96.1, 1.0 / 96.1));
+        assert_eq!(x - o, board.score());
+    }
+
+    #[test]
+    fn attach_points_after_first_move_match_recomputation ()
+    {
+        Tetromino::initialize();
+
+        // One board has a setup piece, so the first move is not the first piece; the other 
+        // only has scoring tiles, so the first move recomputes its attach points.
+
+        let mut with_piece = vec!["0000000000"; 7];
+        with_piece.extend(["1000000000", "1000000a00", "1100000500"]);
+        let mut with_tiles = vec!["0000000000"; 7];
+        with_tiles.extend(["0000000000", "0000000a00", "0000000500"]);
+
+        for (rows, remaining) in [(with_piece, [4, 5, 5, 5]), (with_tiles, [5, 5, 5, 5])]
+        {
+            let board = Board::from_grid(& rows, remaining, Player::X).unwrap();
+            for tetromino in board.enumerate_moves()
+            {
+                let mut after = board.clone();
+                after.place_tetromino(& tetromino).unwrap();
+
+                let mut expected = after.clone();
+                expected.calculate_attach_points_from_scratch();
+                assert_eq!(after.attach_points, expected.attach_points);
+            }
+        }
+    }
+
+    #[test]
+    fn from_grid_matches_notation ()
+    {
+        Tetromino::initialize();
+
+        let mut rows = vec!["5000000000"];
+        rows.extend(["0000000000"; 6]);
+        rows.extend(["1000000000", "1000000a00", "1100000000"]);
+        let board = Board::from_grid(& rows, [4, 5, 5, 5], Player::O).unwrap();
+
+        // The notation lists the tiles column by column from the bottom, while the grid lists 
+        // them row by row from the top.
+
+        let tiles = (0 .. 10)
+            .flat_map(|x| (0 .. 10).map(move |y| (x, y)))
+            .map(|(x, y)| rows[9 - y].chars().nth(x).unwrap())
+            .collect::<String>();
+        let notation = format!("{},4555,{}", tiles, Player::O.notate());
+
+        assert_eq!(board.notate(), notation);
+        assert_eq!(Board::parse(& notation).unwrap(), board);
+    }
+
+    #[test]
+    fn neighbour_colours_collects_distinct_colours ()
+    {
+        Tetromino::initialize();
+
+        let mut rows = vec!["0000000000"; 6];
+        rows.extend(["0002000000", "1002000000", "1002000000", "1102000000"]);
+        let board = Board::from_grid(& rows, [4, 4, 5, 5], Player::X).unwrap();
+
+        assert_eq!(board.neighbour_colours(& Point::new(2, 0)), BTreeSet::from([Colour::L, Colour::I]));
+        assert_eq!(board.neighbour_colours(& Point::new(1, 1)), BTreeSet::from([Colour::L]));
+        assert_eq!(board.neighbour_colours(& Point::new(2, 3)), BTreeSet::from([Colour::I]));
+        assert!(board.neighbour_colours(& Point::new(9, 9)).is_empty());
+    }
+
+    #[test]
+    fn fingerprints_identify_positions ()
+    {
+        Tetromino::initialize();
+
+        let mut board = Board::blank();
+        let tetromino = board.enumerate_moves().into_iter().next().unwrap();
+        board.place_tetromino(& tetromino).unwrap();
+
+        let fingerprint = board.fingerprint();
+        let (hash, played) = fingerprint.split_once('@').unwrap();
+        assert_eq!((hash.len(), played), (8, "1"));
+        assert!(hash.chars().all(|c| c.is_ascii_hexdigit()));
+
+        assert_eq!(Board::parse(& board.notate()).unwrap().fingerprint(), fingerprint);
+        assert_ne!(Board::blank().fingerprint(), fingerprint);
+
+        let other = Board::blank().enumerate_moves().into_iter().nth(1).unwrap();
+        let mut elsewhere = Board::blank();
+        elsewhere.place_tetromino(& other).unwrap();
+        assert_ne!(elsewhere.fingerprint(), fingerprint);
+    }
+
+    #[test]
+    fn inferred_move_recovers_a_placed_l ()
+    {
+        Tetromino::initialize();
+
+        let mut rows = vec!["0000000000"; 7];
+        rows.extend(["1000000000", "1000000000", "1100000000"]);
+        let after = Board::from_grid(& rows, [4, 5, 5, 5], Player::O).unwrap();
+        let before = Board::blank();
+
+        let tetromino = before.infer_move(& after).unwrap();
+        assert_eq!(tetromino.colour(), Colour::L);
+
+        let mut placed = before.clone();
+        placed.place_tetromino(& tetromino).unwrap();
+        assert_eq!(placed.notate(), after.notate());
+
+        // Taking the piece away is not a move, and neither is standing still.
+
+        assert!(after.infer_move(& before).is_none());
+        assert!(before.infer_move(& before).is_none());
+    }
+
+    #[test]
+    fn serialized_boards_round_trip_mid_game ()
+    {
+        let mut checked = false;
+        random_game(0, |board, _| 
+        {
+            if board.num_coloured_tiles() == 12
+            {
+                checked = true;
+
+                let json = serde_json::to_string(board).unwrap();
+                assert!(! json.contains("attach"));
+
+                let mut parsed = serde_json::from_str::<Board>(& json).unwrap();
+                parsed.cache_bust();
+                let mut original = board.clone();
+                original.cache_bust();
+                assert_eq!(parsed, original);
+                assert_eq!(parsed.attach_points, original.attach_points);
+            }
+        });
+        assert!(checked);
+    }
+
+    #[test]
+    fn swapping_players_negates_the_score ()
+    {
+        Tetromino::initialize();
+
+        let mut rows = vec!["5500000a00"];
+        rows.extend(["0000000000"; 6]);
+        rows.extend(["1000000000", "1000000a00", "1100000005"]);
+        let board = Board::from_grid(& rows, [4, 5, 5, 5], Player::O).unwrap();
+        assert!(board.score() != 0.0);
+
+        let swapped = board.swap_players();
+        assert_eq!(swapped.score(), - board.score());
+        assert_eq!(swapped.to_move(), Player::X);
+        assert_eq!(swapped.swap_players(), board);
+    }
+
+    #[test]
+    fn legal_move_counts_include_stuck_colours ()
+    {
+        // Some colour in some game is stuck while pieces of it remain; it counts no moves.
+
+        let colours = [Colour::L, Colour::I, Colour::T, Colour::S];
+        let mut stuck = false;
+
+        for seed in 0 .. 4 
+        {
+            random_game(seed, |board, _| 
+            {
+                let mut expected = [0; 4];
+                for tetromino in (0 .. Tetromino::range()).map(Tetromino::from).filter(|tetromino| ! tetromino.is_null())
+                {
+                    if board.validate_tetromino(& tetromino).is_ok()
+                    {
+                        expected[tetromino.colour().as_index()] += 1;
+                    }
+                }
+
+                let counts = board.legal_move_counts();
+                assert_eq!(counts, expected);
+                stuck |= colours.iter().any(|colour| board.remaining_of(colour) > 0 && counts[colour.as_index()] == 0);
+            });
+        }
+
+        assert!(stuck);
+    }
+
+    #[test]
+    fn relaxed_rules_allow_squares ()
+    {
+        Tetromino::initialize();
+
+        let mut rows = vec!["0000000000"; 7];
+        rows.extend(["1000000000", "1000000000", "1100000000"]);
+        let board = Board::from_grid(& rows, [4, 5, 5, 5], Player::O).unwrap();
+
+        // An upright I beside the L fills the square in the corner.
+
+        let points = (1 .. 5).map(|y| Point::new(1, y)).collect::<Vec<Point>>();
+        let tetromino = Tetromino::from_points_with_colour(& Colour::I, & points).unwrap();
+
+        assert_eq!(board.violation(& tetromino, PlacementRules::standard()), Some(PlacementError::FormsSquare));
+        let err = board.validate_tetromino(& tetromino).unwrap_err();
+        assert_eq!(PlacementError::find(& err), Some(PlacementError::FormsSquare));
+
+        assert_eq!(board.violation(& tetromino, PlacementRules::relaxed()), None);
+        let mut relaxed = board.clone();
+        relaxed.place_tetromino_with_rules(& tetromino, PlacementRules::relaxed()).unwrap();
+        assert_eq!(relaxed.num_coloured_tiles(), 8);
     }
 }
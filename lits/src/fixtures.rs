@@ -0,0 +1,60 @@
+
+use super::board::Board;
+use super::tetromino::Tetromino;
+
+///
+/// The plies at which the benchmark boards are taken: a blank board, a midgame 
+/// board, and a nearly full board.
+///
+pub const BENCH_PLIES : [usize; 3] = [0, 6, 12];
+
+///
+/// Returns deterministic boards for benchmarking, taken at each ply in BENCH_PLIES
+/// from a game that always plays the middle legal move. The movemap must have been 
+/// initialized.
+///
+pub fn bench_fixtures () -> Vec<Board>
+{
+    let mut board = Board::blank();
+    let mut fixtures = Vec::new();
+
+    for ply in 0 ..= BENCH_PLIES[BENCH_PLIES.len() - 1]
+    {
+        if BENCH_PLIES.contains(& ply)
+        {
+            fixtures.push(board.clone());
+        }
+
+        let moves = board.enumerate_moves().into_iter().collect::<Vec<Tetromino>>();
+        if let Some(tetromino) = moves.get(moves.len() / 2)
+        {
+            board.place_tetromino(tetromino).expect("Enumerated moves are legal.");
+        }
+    }
+
+    fixtures
+}
+
+#[cfg(test)]
+mod tests 
+{
+    use super::*;
+
+    use crate::colour::Colour;
+    use crate::piecepool::PiecePool;
+
+    #[test]
+    fn fixtures_are_taken_at_bench_plies ()
+    {
+        Tetromino::initialize();
+
+        let fixtures = bench_fixtures();
+        assert_eq!(fixtures.len(), BENCH_PLIES.len());
+
+        for (board, & ply) in fixtures.iter().zip(BENCH_PLIES.iter())
+        {
+            let remaining : usize = [Colour::L, Colour::I, Colour::T, Colour::S].iter().map(|colour| board.remaining_of(colour)).sum();
+            assert_eq!(4 * PiecePool::MAX - remaining, ply);
+        }
+    }
+}
@@ -1,5 +1,12 @@
 
+use std::time::{Duration, Instant};
+
+use std::collections::BTreeSet;
+
 use super::board::Board;
+use super::colour::Colour;
+use super::notate::GameRecord;
+use super::outcome::Outcome;
 use super::player::Player;
 use super::tetromino::Tetromino;
 
@@ -36,7 +43,11 @@ use utils::*;
 /// be unable to rewind the position. It is also probably highly buggy 
 /// because we are trying to optimize attach point calculation.
 ///
-#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+/// Games compare by their boards and histories; the clock is not part of a game's 
+/// identity, so two games that reached the same position are equal however long 
+/// their players took.
+///
+#[derive(Clone, Debug)]
 pub struct Game 
 {
     // State.
@@ -48,7 +59,113 @@ pub struct Game
 
     // A helper that stops us from having to count the history stack.
 
-    to_move: Player
+    to_move: Player,
+
+    // An optional clock for timed play.
+
+    clock: Option<Clock>
+}
+
+impl std::cmp::Ord for Game 
+{
+    fn cmp (& self, other: & Game) -> std::cmp::Ordering 
+    {
+        (& self.curr_board, & self.base_board, & self.hist_stack, & self.redo_stack, self.to_move)
+            .cmp(& (& other.curr_board, & other.base_board, & other.hist_stack, & other.redo_stack, other.to_move))
+    }
+}
+
+impl std::cmp::PartialEq for Game 
+{
+    fn eq (& self, other: & Game) -> bool 
+    {
+        self.cmp(other) == std::cmp::Ordering::Equal
+    }
+}
+
+impl std::cmp::Eq for Game {}
+
+impl std::cmp::PartialOrd for Game 
+{
+    fn partial_cmp (& self, other: & Game) -> Option<std::cmp::Ordering> 
+    {
+        Some(self.cmp(other))
+    }
+}
+
+///
+/// A chess-style game clock that tracks the remaining thinking time of each player.
+///
+/// At most one player's clock runs at a time; stopping the clock debits the time 
+/// elapsed since it was started from the running player. Remaining time saturates 
+/// at zero rather than going negative.
+///
+#[derive(Clone, Debug)]
+pub struct Clock 
+{
+    remaining: [Duration; 2],
+    running: Option<(Player, Instant)>
+}
+
+impl Clock 
+{
+    ///
+    /// Returns a new stopped clock giving each player the same amount of time.
+    ///
+    pub fn new (initial: Duration) -> Clock 
+    {
+        Clock { remaining: [initial; 2], running: None }
+    }
+
+    ///
+    /// Returns the remaining time of the given player, including the time elapsed 
+    /// on their clock if it is running.
+    ///
+    pub fn remaining (& self, player: Player) -> Duration 
+    {
+        let remaining = self.remaining[player.as_index()];
+        match self.running 
+        {
+            Some((running, since)) if running == player => remaining.saturating_sub(since.elapsed()),
+            _                                           => remaining
+        }
+    }
+
+    ///
+    /// Returns the player whose clock is running, if any.
+    ///
+    pub fn running (& self) -> Option<Player>
+    {
+        self.running.map(|(player, _)| player)
+    }
+
+    ///
+    /// Starts the given player's clock, stopping the other clock if it is running.
+    ///
+    pub fn start (& mut self, player: Player)
+    {
+        self.stop();
+        self.running = Some((player, Instant::now()));
+    }
+
+    ///
+    /// Stops the running clock, debiting the elapsed time from its player, and 
+    /// returns the elapsed time.
+    ///
+    pub fn stop (& mut self) -> Duration 
+    {
+        match self.running.take()
+        {
+            Some((player, since)) => 
+            {
+                let elapsed = since.elapsed();
+                let remaining = & mut self.remaining[player.as_index()];
+                * remaining = remaining.saturating_sub(elapsed);
+                elapsed
+            },
+            None => Duration::ZERO
+        }
+    }
 }
 
 impl notate::Notate for Game 
@@ -97,7 +214,7 @@ impl notate::Notate for Game
             to_move = to_move.next();
         }
 
-        Ok(Game { base_board, curr_board, hist_stack, redo_stack, to_move })
+        Ok(Game { base_board, curr_board, hist_stack, redo_stack, to_move, clock: None })
     }
 }
 
@@ -105,6 +222,8 @@ impl Game
 {
     ///
     /// Applies the tetromino to the board if the tetromino is valid in this position.
+    /// If the game is timed, the mover's clock is debited and the opponent's clock 
+    /// is started.
     ///
     pub fn apply (& mut self, tetromino: & Tetromino) -> Result<()>
     {
@@ -112,7 +231,13 @@ impl Game
         {
             Ok(_) => 
             {
+                if let Some(clock) = self.clock.as_mut()
+                {
+                    clock.start(self.curr_board.to_move());
+                }
+
                 self.hist_stack.push(tetromino.clone());
+                self.to_move = self.curr_board.to_move();
                 if ! self.redo_stack.is_empty()
                 {
                     if self.redo_stack.last().unwrap() == tetromino 
@@ -128,11 +253,52 @@ impl Game
             },
             Err(err) => 
             {
+                // If the colour has run out, name the colours that can still be played.
+
+                let err = match self.curr_board.remaining_of(& tetromino.colour()) == 0 
+                {
+                    true  => 
+                    {
+                        let available = self.available_colours().iter().map(|colour| colour.notate()).collect::<Vec<String>>();
+                        err.context(format!("There are no '{}'s left; available colours are [{}].", tetromino.colour().notate(), available.join(", ")))
+                    },
+                    false => err
+                };
+
                 Err(err).context(notate!("Failed to apply tetromino '{}' to this game.", tetromino))
             }
         }
     }
 
+    ///
+    /// Returns the colours that can still be played in the current position; that is, 
+    /// those with pieces remaining and at least one legal move.
+    ///
+    pub fn available_colours (& self) -> BTreeSet<Colour>
+    {
+        let counts = self.curr_board.legal_move_counts();
+
+        [Colour::L, Colour::I, Colour::T, Colour::S].into_iter()
+            .filter(|colour| self.curr_board.remaining_of(colour) > 0 && counts[colour.as_index()] > 0)
+            .collect()
+    }
+
+    ///
+    /// Returns the clock of this game, if it is timed.
+    ///
+    pub fn clock (& self) -> Option<& Clock>
+    {
+        self.clock.as_ref()
+    }
+
+    ///
+    /// Returns a mutable handle on the clock of this game, if it is timed.
+    ///
+    pub fn clock_mut (& mut self) -> Option<& mut Clock>
+    {
+        self.clock.as_mut()
+    }
+
     ///
     /// Cycles the colour at a tile for setup purposes.
     ///
@@ -149,10 +315,74 @@ impl Game
         self.get_board().cycle_player(i, j);
     }
 
+    ///
+    /// Returns the ply index at which this game's history first diverges from the
+    /// other game's history, or None if the two histories are identical. Games
+    /// with differing base boards diverge immediately.
+    ///
+    pub fn first_divergence (& self, other: & Game) -> Option<usize>
+    {
+        if self.base_board != other.base_board
+        {
+            return Some(0);
+        }
+
+        let common = self.hist_stack.len().min(other.hist_stack.len());
+
+        for i in 0 .. common
+        {
+            if self.hist_stack[i] != other.hist_stack[i]
+            {
+                return Some(i);
+            }
+        }
+
+        match self.hist_stack.len() == other.hist_stack.len()
+        {
+            true  => None,
+            false => Some(common)
+        }
+    }
+
+    ///
+    /// Reconstructs a game from the given base board and a space-separated move list,
+    /// as produced by moves_notation.
+    ///
+    pub fn from_moves (base: & Board, s: & str) -> Result<Game>
+    {
+        let context = format!("Invalid move list '{}' for game.", s);
+
+        let mut curr_board = base.clone();
+        let mut hist_stack : Vec<Tetromino> = Vec::new();
+        let mut to_move = base.to_move();
+
+        for (i, notation) in s.split_whitespace().enumerate()
+        {
+            let move_context = format!("Invalid notation in move {}.", i + 1);
+
+            let tetromino = Tetromino::parse(notation).context(move_context.clone()).context(context.clone())?;
+            curr_board.place_tetromino(& tetromino).context(move_context.clone()).context(context.clone())?;
+
+            hist_stack.push(tetromino);
+            to_move = to_move.next();
+        }
+
+        Ok(Game { base_board: base.clone(), curr_board, hist_stack, redo_stack: Vec::new(), to_move, clock: None })
+    }
+
+    ///
+    /// Creates a game from the given record, replaying its moves from its setup.
+    ///
+    pub fn from_record (record: & GameRecord) -> Result<Game>
+    {
+        let moves = record.moves.iter().map(|tetromino| tetromino.notate()).collect::<Vec<String>>().join(" ");
+        Game::from_moves(& record.setup, & moves).context("Failed to replay the game record.")
+    }
+
     ///
     /// Returns the current state of the board.
     ///
-    pub fn get_board (& mut self) -> & mut Board 
+    pub fn get_board (& mut self) -> & mut Board
     {
         & mut self.curr_board
     }
@@ -181,6 +411,45 @@ impl Game
         & self.hist_stack
     }
 
+    ///
+    /// Moves through the history to the given ply, which is the number of moves played,
+    /// undoing moves or redoing undone moves as necessary. Navigating does not run the clock.
+    ///
+    pub fn goto (& mut self, ply: usize) -> Result<()>
+    {
+        let context = format!("Failed to go to ply {}.", ply);
+
+        if ply > self.hist_stack.len() + self.redo_stack.len()
+        {
+            return Err(error::error!("There are only {} moves in this game.", self.hist_stack.len() + self.redo_stack.len())).context(context);
+        }
+
+        let clock = self.clock.take();
+        let mut result = Ok(());
+
+        while result.is_ok() && self.hist_stack.len() > ply 
+        {
+            result = self.undo();
+        }
+
+        while result.is_ok() && self.hist_stack.len() < ply 
+        {
+            result = self.redo();
+        }
+
+        self.clock = clock;
+        result.context(context)
+    }
+
+    ///
+    /// Notates the moves played in this game as a single space-separated line, without 
+    /// the base board; undone moves are not included.
+    ///
+    pub fn moves_notation (& self) -> String 
+    {
+        self.hist_stack.iter().map(|tetromino| tetromino.notate()).collect::<Vec<String>>().join(" ")
+    }
+
     ///
     /// Returns a blank starting game.
     ///
@@ -192,10 +461,72 @@ impl Game
             curr_board: Board::blank(), 
             hist_stack: vec![], 
             redo_stack: vec![], 
-            to_move: Player::X 
+            to_move: Player::X,
+            clock: None
         }
     }
 
+    ///
+    /// Parses a collection of games, where each game's notation is separated from 
+    /// the next by at least one blank line.
+    ///
+    pub fn parse_many (s: & str) -> Result<Vec<Game>>
+    {
+        let context = "Invalid notation for game collection.";
+
+        let mut games = Vec::new();
+        let mut lines : Vec<& str> = Vec::new();
+
+        for line in s.lines().map(|line| line.trim()).chain([""])
+        {
+            if line.is_empty()
+            {
+                if ! lines.is_empty()
+                {
+                    let game = Game::parse(& lines.join("\n")).context(format!("Invalid notation in game {}.", games.len() + 1)).context(context)?;
+                    games.push(game);
+                    lines.clear();
+                }
+            }
+            else 
+            {
+                lines.push(line);
+            }
+        }
+
+        Ok(games)
+    }
+
+    ///
+    /// Replays the most recently undone move.
+    ///
+    pub fn redo (& mut self) -> Result<()>
+    {
+        let context = "Failed to redo the last tetromino undone in this game.";
+
+        match self.redo_stack.last().cloned()
+        {
+            Some(tetromino) => self.apply(& tetromino).context(context),
+            None            => Err(error::error!("There is no tetromino in the future.")).context(context)
+        }
+    }
+
+    ///
+    /// Replays every undone move, moving to the end of the current line.
+    ///
+    pub fn redo_all (& mut self) -> Result<()>
+    {
+        self.goto(self.hist_stack.len() + self.redo_stack.len())
+    }
+
+    ///
+    /// Sets the clock of this game; None makes the game untimed.
+    ///
+    pub fn set_clock (& mut self, clock: Option<Clock>)
+    {
+        self.clock = clock;
+    }
+
     ///
     /// Sets a tile on the game board to the given scoring tile.
     ///
@@ -213,6 +544,28 @@ impl Game
         self.to_move
     }
 
+    ///
+    /// Returns a record of this game's history, with its result if it is over; the players 
+    /// and date are unknown, for the caller to fill in.
+    ///
+    /// The result is the winner's margin in uncovered scoring tiles; a tie is won by the last 
+    /// player to move, so it is recorded as that player winning by 0.
+    ///
+    pub fn to_record (& self) -> GameRecord 
+    {
+        let mut record = GameRecord::new(& self.base_board);
+        record.moves = self.hist_stack.clone();
+        let margin = self.curr_board.margin().abs();
+        record.result = match self.curr_board.result()
+        {
+            Outcome::X(_) => format!("X+{}", margin),
+            Outcome::O(_) => format!("O+{}", margin),
+            _             => "*".to_owned()
+        };
+
+        record
+    }
+
     ///
     /// Undoes the last move played.
     ///
@@ -229,6 +582,7 @@ impl Game
 
                 self.hist_stack.pop();
                 self.redo_stack.push(tetromino);
+                self.to_move = self.curr_board.to_move();
 
                 Ok(())
             },
@@ -238,4 +592,161 @@ impl Game
             }
         }
     }
+
+    ///
+    /// Undoes every move played, moving to the base board.
+    ///
+    pub fn undo_all (& mut self) -> Result<()>
+    {
+        self.goto(0)
+    }
+}
+
+#[cfg(test)]
+mod tests 
+{
+    use super::*;
+
+    #[test]
+    fn exhausted_colour_lists_available_colours ()
+    {
+        Tetromino::initialize();
+
+        // Play an L whenever one fits, and otherwise anything else, until the Ls run out.
+
+        let mut game = Game::new();
+        let mut last_l = None;
+        while game.curr_board.remaining_of(& Colour::L) > 0 
+        {
+            let moves = game.curr_board.enumerate_moves();
+            let tetromino = moves.iter().find(|t| t.colour() == Colour::L).or(moves.iter().next()).unwrap().clone();
+            if tetromino.colour() == Colour::L 
+            {
+                last_l = Some(tetromino.clone());
+            }
+            game.apply(& tetromino).unwrap();
+        }
+
+        let available = game.available_colours();
+        assert!(! available.contains(& Colour::L));
+        assert!(! available.is_empty());
+
+        let err = format!("{:#}", game.apply(& last_l.unwrap()).unwrap_err());
+        let listed = available.iter().map(|colour| colour.notate()).collect::<Vec<String>>().join(", ");
+        assert!(err.contains(& format!("available colours are [{}]", listed)), "{}", err);
+    }
+
+    #[test]
+    fn first_divergence_follows_common_prefix ()
+    {
+        Tetromino::initialize();
+
+        let mut a = Game::new();
+        for _ in 0 .. 3 
+        {
+            let tetromino = a.curr_board.enumerate_moves().into_iter().next().unwrap();
+            a.apply(& tetromino).unwrap();
+        }
+
+        let mut b = a.clone();
+        let moves = a.curr_board.enumerate_moves().into_iter().take(2).collect::<Vec<Tetromino>>();
+        a.apply(& moves[0]).unwrap();
+        b.apply(& moves[1]).unwrap();
+
+        assert_eq!(a.first_divergence(& b), Some(3));
+        assert_eq!(a.first_divergence(& a.clone()), None);
+    }
+
+    #[test]
+    fn timed_moves_debit_the_mover ()
+    {
+        Tetromino::initialize();
+
+        let (initial, pause) = (Duration::from_secs(60), Duration::from_millis(10));
+        let mut game = Game::new();
+
+        // Pick the moves ahead of time, so that only the pauses run down the clock.
+
+        let mut scratch = game.clone();
+        let mut moves = Vec::new();
+        for _ in 0 .. 2 
+        {
+            let tetromino = scratch.curr_board.enumerate_moves().into_iter().next().unwrap();
+            scratch.apply(& tetromino).unwrap();
+            moves.push(tetromino);
+        }
+
+        let untimed = game.clone();
+        game.set_clock(Some(Clock::new(initial)));
+        game.clock_mut().unwrap().start(Player::X);
+        assert_eq!(game, untimed);
+
+        for (mover, tetromino) in [Player::X, Player::O].into_iter().zip(moves)
+        {
+            std::thread::sleep(pause);
+            game.apply(& tetromino).unwrap();
+
+            let clock = game.clock().unwrap();
+            assert_eq!(clock.running(), Some(mover.next()));
+            assert!(clock.remaining(mover) <= initial - pause);
+        }
+
+        // Each player was debited only for their own move.
+
+        let clock = game.clock().unwrap();
+        assert!(clock.remaining(Player::X) > initial - 2 * pause);
+        assert!(clock.remaining(Player::O) > initial - 2 * pause);
+    }
+
+    #[test]
+    fn move_lines_round_trip ()
+    {
+        Tetromino::initialize();
+
+        // One base is blank, and the other has a setup piece, which is not part of the line.
+
+        let mut rows = vec!["0000000000"; 7];
+        rows.extend(["1000000000", "1000000a00", "1100000500"]);
+        let setup = Board::from_grid(& rows, [4, 5, 5, 5], Player::X).unwrap();
+
+        for base in [Board::blank(), setup]
+        {
+            let mut game = Game::from_moves(& base, "").unwrap();
+            for _ in 0 .. 3 
+            {
+                let tetromino = game.curr_board.enumerate_moves().into_iter().next().unwrap();
+                game.apply(& tetromino).unwrap();
+            }
+
+            let line = game.moves_notation();
+            assert_eq!(line.split_whitespace().count(), 3);
+            assert_eq!(Game::from_moves(& base, & line).unwrap(), game);
+
+            assert!(Game::from_moves(& base, & format!("{} nonsense", line)).is_err());
+        }
+    }
+
+    #[test]
+    fn undoing_and_redoing_everything_restores_the_end ()
+    {
+        Tetromino::initialize();
+
+        let mut game = Game::new();
+        for _ in 0 .. 4 
+        {
+            let tetromino = game.curr_board.enumerate_moves().into_iter().next().unwrap();
+            game.apply(& tetromino).unwrap();
+        }
+        let end = game.clone();
+
+        game.undo_all().unwrap();
+        assert_eq!(game.curr_board, game.base_board);
+        assert!(game.get_history().is_empty());
+        assert_eq!(game.get_future().len(), 4);
+        assert_eq!(game.to_move(), Player::X);
+
+        game.redo_all().unwrap();
+        assert_eq!(game, end);
+        assert_eq!(game.curr_board.notate(), end.curr_board.notate());
+    }
 }
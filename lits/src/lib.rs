@@ -1,19 +1,33 @@
 
+pub mod bitboard;
 pub mod board;
 pub mod colour;
+#[cfg(feature = "bench")]
+pub mod fixtures;
 pub mod game;
+pub mod notate;
 pub mod outcome;
+pub mod piecepool;
 pub mod player;
 pub mod point;
+pub mod rules;
+pub mod setups;
+pub mod symmetry;
 pub mod tetromino;
 pub mod transform;
 
-pub use board::Board;
+pub use bitboard::Bitboard;
+pub use board::{Board, UndoToken};
 pub use colour::Colour;
+#[cfg(feature = "bench")]
+pub use fixtures::bench_fixtures;
 pub use game::Game;
+pub use notate::GameRecord;
 pub use outcome::Outcome;
+pub use piecepool::PiecePool;
 pub use player::Player;
 pub use point::Point;
+pub use rules::{PlacementError, PlacementRules};
 pub use tetromino::Tetromino;
 pub use transform::Transform;
 
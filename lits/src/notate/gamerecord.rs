@@ -0,0 +1,299 @@
+use std::fs;
+use std::path::Path;
+
+use crate::board::Board;
+use crate::tetromino::Tetromino;
+
+use utils::error::Context;
+use utils::notate::Notate;
+use utils::*;
+
+///
+/// A portable record of a game, with a header of metadata followed by the move list.
+///
+/// The header is a line per tag, of the form '[Tag "value"]', with the tags X and O for 
+/// the players' names, Date, Setup for the notation of the base board and Result; the 
+/// result is 'X+<margin>' or 'O+<margin>' for a finished game, where the margin counts 
+/// uncovered scoring tiles, and '*' otherwise. A game is never drawn: a tied board goes to 
+/// whoever played the last tetromino, and is recorded as 'X+0' or 'O+0'. After 
+/// a blank line, each move is notated on its own line, as in a game notation, followed by 
+/// its annotation in braces if it has one.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameRecord 
+{
+    pub x: String,
+    pub o: String,
+    pub date: String,
+    pub setup: Board,
+    pub result: String,
+    pub moves: Vec<Tetromino>,
+    pub annotations: Vec<Annotation>
+}
+
+///
+/// The optional annotation of a move in a game record: the time its player took over it,
+/// the evaluation after it in X's perspective, and a free comment.
+///
+/// An annotation is notated in braces, as in '{[%time 1532] [%eval +0.250] A sharp reply.}',
+/// where each field is omitted if it is unset; since braces delimit the annotation, they are 
+/// dropped from the comment.
+///
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Annotation 
+{
+    pub time_ms: Option<u64>,
+    pub eval: Option<f32>,
+    pub comment: Option<String>
+}
+
+impl Annotation 
+{
+    ///
+    /// Determines whether this annotation has no fields set.
+    ///
+    pub fn is_empty (& self) -> bool 
+    {
+        self.time_ms.is_none() && self.eval.is_none() && self.comment.is_none()
+    }
+}
+
+impl notate::Notate for Annotation 
+{
+    fn notate (& self) -> String 
+    {
+        let mut fields = Vec::new();
+
+        if let Some(time_ms) = self.time_ms 
+        {
+            fields.push(format!("[%time {}]", time_ms));
+        }
+        if let Some(eval) = self.eval 
+        {
+            fields.push(format!("[%eval {:+.3}]", eval));
+        }
+        if let Some(comment) = & self.comment 
+        {
+            fields.push(comment.replace(['{', '}'], ""));
+        }
+
+        format!("{{{}}}", fields.join(" "))
+    }
+
+    fn parse (s: & str) -> Result<Annotation>
+    {
+        let context = format!("Invalid annotation '{}'.", s);
+
+        let mut rest = s.trim().strip_prefix('{').and_then(|s| s.strip_suffix('}'))
+            .ok_or(error::error!("Expected the annotation to be braced.")).context(context.clone())?
+            .trim();
+
+        let mut annotation = Annotation::default();
+
+        // Fields lead the annotation; whatever follows them is the comment.
+
+        while let Some(field) = rest.strip_prefix("[%")
+        {
+            let (field, remainder) = field.split_once(']')
+                .ok_or(error::error!("Expected the field to be bracketed.")).context(context.clone())?;
+            let (name, value) = field.split_once(' ')
+                .ok_or(error::error!("Expected a field name and a value.")).context(context.clone())?;
+
+            match name 
+            {
+                "time" => annotation.time_ms = Some(value.trim().parse().context(format!("Invalid time '{}'.", value)).context(context.clone())?),
+                "eval" => annotation.eval = Some(value.trim().parse().context(format!("Invalid eval '{}'.", value)).context(context.clone())?),
+                _      => {}
+            };
+            rest = remainder.trim_start();
+        }
+
+        if ! rest.is_empty()
+        {
+            annotation.comment = Some(rest.to_owned());
+        }
+
+        Ok(annotation)
+    }
+}
+
+impl notate::Notate for GameRecord 
+{
+    fn notate (& self) -> String 
+    {
+        let mut result = String::new();
+
+        for (tag, value) in self.tags()
+        {
+            result += & format!("[{} \"{}\"]\n", tag, value);
+        }
+        result += "\n";
+
+        for (ply, tetromino) in self.moves.iter().enumerate()
+        {
+            result += & match self.annotation(ply)
+            {
+                Some(annotation) => notate!("{} {}\n", tetromino, annotation),
+                None             => notate!("{}\n", tetromino)
+            };
+        }
+
+        result
+    }
+
+    fn parse (s: & str) -> Result<GameRecord>
+    {
+        let context = "Invalid game record.";
+
+        let mut record = GameRecord::new(& Board::blank());
+        let mut lines = s.lines().map(str::trim);
+
+        // Read the header up to the first blank line; unknown tags are skipped, so that 
+        // records with extra metadata still load.
+
+        for line in lines.by_ref().take_while(|line| ! line.is_empty())
+        {
+            let (tag, value) = GameRecord::parse_tag(line).context(context)?;
+            match tag 
+            {
+                "X"      => record.x = value.to_owned(),
+                "O"      => record.o = value.to_owned(),
+                "Date"   => record.date = value.to_owned(),
+                "Setup"  => record.setup = Board::parse(value).context(context)?,
+                "Result" => record.result = value.to_owned(),
+                _        => {}
+            };
+        }
+
+        // The moves are separated by whitespace, and each may be followed by a braced annotation, 
+        // which may span lines.
+
+        let body = lines.collect::<Vec<& str>>().join("\n");
+        let mut rest = body.trim_start();
+
+        while ! rest.is_empty()
+        {
+            match rest.starts_with('{')
+            {
+                true  => 
+                {
+                    let end = rest.find('}')
+                        .ok_or(error::error!("Unterminated annotation after move {}.", record.moves.len())).context(context)?;
+                    let annotation = Annotation::parse(& rest[..= end]).context(format!("Invalid annotation of move {}.", record.moves.len())).context(context)?;
+
+                    match record.moves.len()
+                    {
+                        0 => return Err(error::error!("Found an annotation before the first move.")).context(context),
+                        n => record.annotate(n - 1, annotation)
+                    };
+                    rest = & rest[end + 1 ..];
+                },
+                false => 
+                {
+                    let end = rest.find(|c: char| c.is_whitespace() || c == '{').unwrap_or(rest.len());
+                    let tetromino = Tetromino::parse(& rest[.. end]).context(format!("Invalid notation in move {}.", record.moves.len() + 1)).context(context)?;
+
+                    record.moves.push(tetromino);
+                    rest = & rest[end ..];
+                }
+            };
+            rest = rest.trim_start();
+        }
+
+        Ok(record)
+    }
+}
+
+impl GameRecord 
+{
+    ///
+    /// Sets the annotation of the move at the given ply, counting from 0.
+    ///
+    pub fn annotate (& mut self, ply: usize, annotation: Annotation)
+    {
+        if self.annotations.len() <= ply 
+        {
+            self.annotations.resize_with(ply + 1, Annotation::default);
+        }
+        self.annotations[ply] = annotation;
+    }
+
+    ///
+    /// Returns the annotation of the move at the given ply, counting from 0, if it has one.
+    ///
+    pub fn annotation (& self, ply: usize) -> Option<& Annotation>
+    {
+        self.annotations.get(ply).filter(|annotation| ! annotation.is_empty())
+    }
+
+    ///
+    /// Loads a game record from the given file.
+    ///
+    pub fn load (path: & Path) -> Result<GameRecord>
+    {
+        let context = format!("Failed to load a game record from '{}'.", path.display());
+
+        let contents = fs::read_to_string(path).context(context.clone())?;
+        GameRecord::parse(& contents).context(context)
+    }
+
+    ///
+    /// Returns a record of a game from the given setup, with unknown players and date, 
+    /// no moves or annotations and no result.
+    ///
+    pub fn new (setup: & Board) -> GameRecord 
+    {
+        GameRecord 
+        {
+            x: "?".to_owned(),
+            o: "?".to_owned(),
+            date: "?".to_owned(),
+            setup: setup.clone(),
+            result: "*".to_owned(),
+            moves: Vec::new(),
+            annotations: Vec::new()
+        }
+    }
+
+    ///
+    /// Parses a header line of the form '[Tag "value"]' into its tag and value.
+    ///
+    fn parse_tag (line: & str) -> Result<(& str, & str)>
+    {
+        let context = format!("Invalid header line '{}'.", line);
+
+        let inner = line.strip_prefix('[').and_then(|line| line.strip_suffix(']'))
+            .ok_or(error::error!("Expected the line to be bracketed.")).context(context.clone())?;
+
+        let (tag, value) = inner.split_once(' ')
+            .ok_or(error::error!("Expected a tag and a value.")).context(context.clone())?;
+
+        let value = value.trim().strip_prefix('"').and_then(|value| value.strip_suffix('"'))
+            .ok_or(error::error!("Expected the value to be quoted.")).context(context.clone())?;
+
+        Ok((tag, value))
+    }
+
+    ///
+    /// Saves this game record to the given file.
+    ///
+    pub fn save (& self, path: & Path) -> Result<()>
+    {
+        fs::write(path, self.notate()).context(format!("Failed to save a game record to '{}'.", path.display()))
+    }
+
+    ///
+    /// Returns the header tags of this record, in order.
+    ///
+    fn tags (& self) -> Vec<(& str, String)>
+    {
+        vec!
+        [
+            ("X", self.x.clone()),
+            ("O", self.o.clone()),
+            ("Date", self.date.clone()),
+            ("Setup", self.setup.notate()),
+            ("Result", self.result.clone())
+        ]
+    }
+}
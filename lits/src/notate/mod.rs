@@ -0,0 +1,4 @@
+
+pub mod gamerecord;
+
+pub use gamerecord::{Annotation, GameRecord};
@@ -0,0 +1,136 @@
+use super::colour::Colour;
+
+use utils::error::Context;
+use utils::notate::Notate;
+use utils::*;
+
+///
+/// The number of pieces of each colour that remain to be played, indexed by colour.
+/// Each count is at most 5, the number of copies of each piece in a game.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct PiecePool([usize; 4]);
+
+impl Default for PiecePool 
+{
+    fn default () -> PiecePool 
+    {
+        PiecePool::full()
+    }
+}
+
+impl notate::Notate for PiecePool 
+{
+    fn notate (& self) -> String 
+    {
+        [Colour::L, Colour::I, Colour::T, Colour::S].iter().map(|colour| self.get(colour).to_string()).collect()
+    }
+
+    fn parse (s: & str) -> Result<PiecePool>
+    {
+        let context = format!("Invalid notation '{}' for piece pool.", s);
+
+        if s.len() != 4 
+        {
+            return Err(error::error!("Invalid length {}, expected 4.", s.len())).context(context);
+        }
+
+        let mut pool = PiecePool([0; 4]);
+        for (colour, count) in [Colour::L, Colour::I, Colour::T, Colour::S].iter().zip(s.chars())
+        {
+            let count = count.to_digit(10).ok_or_else(|| error::error!("Invalid count '{}'.", count)).context(context.clone())?;
+            pool.set(colour, count as usize).context(context.clone())?;
+        }
+
+        Ok(pool)
+    }
+}
+
+impl PiecePool 
+{
+    ///
+    /// The number of copies of each piece at the start of a game.
+    ///
+    pub const MAX : usize = 5;
+
+    ///
+    /// Returns the pool at the start of a game, with every piece remaining.
+    ///
+    pub fn full () -> PiecePool 
+    {
+        PiecePool([PiecePool::MAX; 4])
+    }
+
+    ///
+    /// Returns the number of pieces remaining of the given colour.
+    ///
+    pub fn get (& self, colour: & Colour) -> usize 
+    {
+        self.0[colour.as_index()]
+    }
+
+    ///
+    /// Creates a pool from the counts of each colour, in LITS order.
+    ///
+    pub fn new (counts: & [usize]) -> Result<PiecePool>
+    {
+        let context = "Failed to create a piece pool.";
+
+        if counts.len() != 4 
+        {
+            return Err(error::error!("Expected 4 counts, but got {}.", counts.len())).context(context);
+        }
+
+        let mut pool = PiecePool([0; 4]);
+        for (colour, & count) in [Colour::L, Colour::I, Colour::T, Colour::S].iter().zip(counts)
+        {
+            pool.set(colour, count).context(context)?;
+        }
+
+        Ok(pool)
+    }
+
+    ///
+    /// Sets the number of pieces remaining of the given colour.
+    ///
+    pub fn set (& mut self, colour: & Colour, count: usize) -> Result<()>
+    {
+        if count > PiecePool::MAX 
+        {
+            return Err(error::error!("Invalid number of remaining pieces {} for colour '{}'.", count, colour.notate()));
+        }
+
+        self.0[colour.as_index()] = count;
+        Ok(())
+    }
+
+    ///
+    /// Returns the total number of pieces remaining.
+    ///
+    pub fn total (& self) -> usize 
+    {
+        self.0.iter().sum()
+    }
+}
+
+#[cfg(test)]
+mod tests 
+{
+    use super::*;
+
+    #[test]
+    fn counts_are_bounded_and_round_trip ()
+    {
+        let mut pool = PiecePool::full();
+        assert!(pool.set(& Colour::T, PiecePool::MAX + 1).is_err());
+        assert_eq!(pool, PiecePool::full());
+
+        pool.set(& Colour::L, 0).unwrap();
+        pool.set(& Colour::S, 3).unwrap();
+        assert_eq!(pool.notate(), "0553");
+        assert_eq!(PiecePool::parse(& pool.notate()).unwrap(), pool);
+
+        assert!(PiecePool::parse("5565").is_err());
+        assert!(PiecePool::new(& [5, 5, 5, 6]).is_err());
+    }
+}
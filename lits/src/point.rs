@@ -47,6 +47,33 @@ impl notate::Notate for Point
     }
 }
 
+///
+/// Serializes points in their 2-digit notation rather than as a structure, for compact 
+/// records; use it with `#[serde(with = "lits::point::notated")]` on on-board points.
+///
+pub mod notated 
+{
+    use super::Point;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+    use serde::de::Error;
+
+    use utils::notate::Notate;
+
+    pub fn serialize<S> (point: & Point, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        serializer.serialize_str(& point.notate())
+    }
+
+    pub fn deserialize<'de, D> (deserializer: D) -> Result<Point, D::Error>
+        where D: Deserializer<'de>
+    {
+        let s = String::deserialize(deserializer)?;
+        Point::parse(& s).map_err(|e| D::Error::custom(format!("{:#}", e)))
+    }
+}
+
 impl std::ops::Add for Point 
 {
     type Output = Point;
@@ -145,3 +172,31 @@ impl Point
         self.y
     }
 }
+
+#[cfg(test)]
+mod tests 
+{
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Record 
+    {
+        #[serde(with = "notated")]
+        point: Point
+    }
+
+    #[test]
+    fn notated_points_round_trip ()
+    {
+        for (x, y) in [(0, 0), (0, 9), (3, 7), (9, 0), (9, 9)]
+        {
+            let record = Record { point: Point::new(x, y) };
+
+            let json = serde_json::to_string(& record).unwrap();
+            assert_eq!(json, format!("{{\"point\":\"{}{}\"}}", x, y));
+            assert_eq!(serde_json::from_str::<Record>(& json).unwrap(), record);
+        }
+
+        assert!(serde_json::from_str::<Record>("{\"point\":\"x1\"}").is_err());
+    }
+}
@@ -0,0 +1,73 @@
+
+use utils::*;
+
+///
+/// The placement constraints to enforce when playing a tetromino, beyond the basic 
+/// requirements that it is in bounds, does not overlap, attaches to the existing 
+/// pieces, and has a copy remaining. Variants and analysis tools may relax them.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct PlacementRules 
+{
+    pub forbid_squares: bool,
+    pub forbid_same_colour: bool
+}
+
+impl Default for PlacementRules 
+{
+    fn default () -> PlacementRules 
+    {
+        PlacementRules::standard()
+    }
+}
+
+///
+/// The reason a tetromino cannot be placed in a position; it is the root cause of the error
+/// returned by validation, so callers can recover it from the error chain to explain it.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, ErrorSpec)]
+pub enum PlacementError 
+{
+    #[error("No more copies.")]
+    OutOfPieces,
+    #[error("Not in bounds.")]
+    OutOfBounds,
+    #[error("Overlaps an existing piece.")]
+    Overlaps,
+    #[error("No attach point.")]
+    NoAttach,
+    #[error("Attaches to same colour.")]
+    SameColourAdjacent,
+    #[error("Forms a 2-by-2 square.")]
+    FormsSquare
+}
+
+impl PlacementError 
+{
+    ///
+    /// Returns the placement error at the root of the given error chain, if there is one.
+    ///
+    pub fn find (err: & error::Error) -> Option<PlacementError>
+    {
+        err.root_cause().downcast_ref::<PlacementError>().copied()
+    }
+}
+
+impl PlacementRules 
+{
+    ///
+    /// Returns the rules with every optional constraint lifted.
+    ///
+    pub fn relaxed () -> PlacementRules 
+    {
+        PlacementRules { forbid_squares: false, forbid_same_colour: false }
+    }
+
+    ///
+    /// Returns the rules of The Battle of LITS, with every constraint enforced.
+    ///
+    pub fn standard () -> PlacementRules 
+    {
+        PlacementRules { forbid_squares: true, forbid_same_colour: true }
+    }
+}
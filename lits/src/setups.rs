@@ -0,0 +1,103 @@
+
+use super::board::Board;
+use super::piecepool::PiecePool;
+use super::player::Player;
+
+use utils::error::Context;
+use utils::*;
+
+///
+/// The named setups, in alphabetical order, each as 10 rows of tiles in the tile notation
+/// of `Board::from_grid`. Every setup is balanced: each X tile is matched by an O tile on
+/// its image under a half turn about the centre of the board.
+///
+const SETUPS : [(& str, [& str; 10]); 5] =
+[
+    ("blank",
+    [
+        "0000000000",
+        "0000000000",
+        "0000000000",
+        "0000000000",
+        "0000000000",
+        "0000000000",
+        "0000000000",
+        "0000000000",
+        "0000000000",
+        "0000000000"
+    ]),
+    ("corners",
+    [
+        "0a0a000a0a",
+        "a0a000a0a0",
+        "0a0a000a0a",
+        "a0a000a0a0",
+        "0000000000",
+        "0000000000",
+        "0505000505",
+        "5050005050",
+        "0505000505",
+        "5050005050"
+    ]),
+    ("halves",
+    [
+        "0050500a0a",
+        "050050a00a",
+        "050500a0a0",
+        "50050a00a0",
+        "50500a0a00",
+        "0050500a0a",
+        "050050a00a",
+        "050500a0a0",
+        "50050a00a0",
+        "50500a0a00"
+    ]),
+    ("rings",
+    [
+        "55555aaaaa",
+        "500000000a",
+        "50555aaa0a",
+        "5050000a0a",
+        "50505a0a0a",
+        "50505a0a0a",
+        "5050000a0a",
+        "50555aaa0a",
+        "500000000a",
+        "55555aaaaa"
+    ]),
+    ("stripes",
+    [
+        "a00a00a00a",
+        "00a00a00a0",
+        "a00a00a00a",
+        "00a00a00a0",
+        "a00a00a00a",
+        "5005005005",
+        "0500500500",
+        "5005005005",
+        "0500500500",
+        "5005005005"
+    ])
+];
+
+///
+/// Returns the setup with the given name, with a full piece pool and X to move.
+///
+pub fn named (name: & str) -> Result<Board>
+{
+    let rows = SETUPS.iter()
+        .find(|(setup, _)| * setup == name)
+        .map(|(_, rows)| rows)
+        .ok_or_else(|| error::error!("There is no setup named '{}'; expected one of {}.", name, names().join(", ")))?;
+
+    let max = PiecePool::MAX;
+    Board::from_grid(rows, [max, max, max, max], Player::X).context(format!("Invalid setup '{}'.", name))
+}
+
+///
+/// Returns the names of the setups, in alphabetical order.
+///
+pub fn names () -> Vec<& 'static str>
+{
+    SETUPS.iter().map(|(name, _)| * name).collect()
+}
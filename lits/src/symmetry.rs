@@ -0,0 +1,104 @@
+
+use lazy_static::lazy_static;
+
+use std::sync::RwLock;
+
+use super::board::Board;
+use super::colour::Colour;
+use super::piecepool::PiecePool;
+use super::player::Player;
+use super::point::Point;
+use super::tetromino::{Tetromino, TETROMINO_RANGE};
+use super::transform::Transform;
+
+lazy_static!
+{
+    static ref SYMMETRY_MAP : RwLock<Vec<Vec<usize>>> = RwLock::new(Vec::new());
+}
+
+///
+/// Builds the tables that map each tetromino index to its index under each of the board's
+/// symmetries. This is called by `Tetromino::initialize`, once the tetromino indices exist.
+///
+pub fn initialize ()
+{
+    let mut map = SYMMETRY_MAP.write().unwrap();
+    map.clear();
+
+    for transform in Transform::as_array()
+    {
+        let mut table = vec![0; TETROMINO_RANGE];
+        for (index, entry) in table.iter_mut().enumerate().skip(1)
+        {
+            let tetromino = Tetromino::from(index);
+            * entry = tetromino.transform_on_board(& transform).into();
+        }
+        map.push(table);
+    }
+}
+
+///
+/// Returns the index of the tetromino with the given index after the given symmetry of the
+/// board; the null tetromino maps to itself.
+///
+pub fn remap (transform: & Transform, index: usize) -> usize
+{
+    let position = Transform::as_array().iter().position(|t| t == transform).unwrap();
+    SYMMETRY_MAP.read().unwrap()[position][index]
+}
+
+///
+/// Maps the given point on the board to its image under the given symmetry of the board,
+/// which is taken about the centre of the board.
+///
+pub fn transform_point (transform: & Transform, point: & Point) -> Point
+{
+    // In doubled coordinates, the centre of the board is the origin.
+
+    let doubled = Point::new(2 * point.x() - 9, 2 * point.y() - 9);
+    let image = transform.apply_to_point(& doubled);
+    Point::new((image.x() + 9) / 2, (image.y() + 9) / 2)
+}
+
+impl Board
+{
+    ///
+    /// Returns the image of this board under the given symmetry; the tiles move, while the
+    /// piece pool and the player to move are kept.
+    ///
+    pub fn transform (& self, transform: & Transform) -> Board
+    {
+        let mut score_tiles = vec![vec![Player::None; 10]; 10];
+        let mut piece_tiles = vec![vec![Colour::None; 10]; 10];
+
+        for i in 0 .. 10
+        {
+            for j in 0 .. 10
+            {
+                let image = transform_point(transform, & Point::new(i, j));
+                score_tiles[image.x() as usize][image.y() as usize] = self.player_at(i, j);
+                piece_tiles[image.x() as usize][image.y() as usize] = self.colour_at(i, j);
+            }
+        }
+
+        let mut pool = PiecePool::full();
+        for colour in [Colour::L, Colour::I, Colour::T, Colour::S]
+        {
+            pool.set(& colour, self.remaining_of(& colour)).unwrap();
+        }
+
+        Board::with_pool(& score_tiles, & piece_tiles, pool, self.to_move())
+    }
+}
+
+impl Tetromino
+{
+    ///
+    /// Returns the image of this tetromino under the given symmetry of the board.
+    ///
+    pub fn transform_on_board (& self, transform: & Transform) -> Tetromino
+    {
+        let points = self.points_real().iter().map(|point| transform_point(transform, point)).collect::<Vec<Point>>();
+        Tetromino::from_points_with_colour(& self.colour(), & points).unwrap()
+    }
+}
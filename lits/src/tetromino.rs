@@ -8,6 +8,7 @@ use std::sync::RwLock;
 use super::board::Board;
 use super::colour::Colour;
 use super::point::Point;
+use super::symmetry;
 use super::transform::Transform;
 
 use utils::error::Context;
@@ -36,11 +37,10 @@ impl std::fmt::Display for Tetromino
 {
     fn fmt (& self, f: & mut std::fmt::Formatter<'_>) -> std::fmt::Result 
     {
-        let p1 = self.points[0] + self.anchor;
-        let p2 = self.points[1] + self.anchor;
-        let p3 = self.points[2] + self.anchor;
-        let p4 = self.points[3] + self.anchor;
-        write!(f, "{}[{},{},{},{}]", self.colour, p1, p2, p3, p4)
+        // The colour is written as its letter rather than its emoji tile, so that
+        // tetrominos read the same in log and error text regardless of formatter.
+
+        write!(f, "{}", self.notate())
     }
 }
 
@@ -78,6 +78,9 @@ impl notate::Notate for Tetromino
                     points.push(p);
                 }
 
+                // The points may be given in any order; the tetromino is rebuilt from 
+                // its transform, so its points are always in the canonical order.
+
                 return Tetromino::from_points_with_colour(& colour, & points);
             }
             else 
@@ -174,18 +177,20 @@ impl Tetromino
 
     ///
     /// Returns a new Tetromino with the given shape, where the points are relative positive 
-    /// offsets in terms of the provided anchor, and the transform is deduced.
+    /// offsets in terms of the provided anchor, and the transform is deduced. The points 
+    /// must be exactly the shape's points, in any order; the result uses the canonical order.
     ///
     pub fn from_points_with_anchor (colour: & Colour, anchor: & Point, points: & Vec<Point>) -> Result<Tetromino>
     {
         let template = Tetromino::get_reference_tetromino(colour, anchor);
+        let given = points.iter().copied().collect::<BTreeSet<Point>>();
 
         for transformed_tetromino in template.enumerate_transforms()
         {
             let transform = transformed_tetromino.transform();
             let transformed_points = transformed_tetromino.points();
 
-            if transformed_points.iter().all( |t_p| points.contains(t_p) )
+            if points.len() == transformed_points.len() && transformed_points.iter().copied().collect::<BTreeSet<Point>>() == given
             {
                 return Ok(Tetromino { colour: * colour, anchor: * anchor, points: transformed_points.clone(), transform });
             }
@@ -298,6 +303,21 @@ impl Tetromino
 
         fwd.insert(template.clone(), 0);
         rev.insert(0, template.clone());
+
+        // The symmetry tables are built from the indices, so the maps are released first.
+
+        drop(fwd);
+        drop(rev);
+        symmetry::initialize();
+    }
+
+    ///
+    /// Determines whether the given absolute points form the shape of some colour's 
+    /// tetromino, in any transform.
+    ///
+    pub fn is_valid_shape (points: & [Point]) -> bool 
+    {
+        points.len() == 4 && Tetromino::from_points(& points.to_vec()).is_ok()
     }
 
     ///
@@ -366,3 +386,53 @@ impl Tetromino
         self.transform
     }
 }
+
+#[cfg(test)]
+mod tests 
+{
+    use super::*;
+
+    #[test]
+    fn notation_round_trips_every_transform ()
+    {
+        for colour in [Colour::L, Colour::I, Colour::T, Colour::S]
+        {
+            let template = Tetromino::get_reference_tetromino(& colour, & Point::new(4, 4));
+            for tetromino in template.enumerate_transforms()
+            {
+                assert_eq!(Tetromino::parse(& tetromino.notate()).unwrap(), tetromino);
+
+                // The points may be written in any order.
+
+                let reversed = tetromino.points_real().iter().rev().map(|p| p.notate()).collect::<Vec<String>>().join(",");
+                assert_eq!(Tetromino::parse(& format!("{}[{}]", colour.notate(), reversed)).unwrap(), tetromino);
+            }
+        }
+    }
+
+    #[test]
+    fn display_writes_the_colour_letter ()
+    {
+        for colour in [Colour::L, Colour::I, Colour::T, Colour::S]
+        {
+            let tetromino = Tetromino::get_reference_tetromino(& colour, & Point::new(4, 4));
+            let displayed = tetromino.to_string();
+
+            assert_eq!(displayed, tetromino.notate());
+            assert!(displayed.starts_with(& format!("{}[", colour.notate())));
+            assert!(! displayed.contains(& colour.to_string()));
+        }
+    }
+
+    #[test]
+    fn valid_shapes_are_lits_pieces ()
+    {
+        Tetromino::initialize();
+
+        let points = |coords: & [(i32, i32)]| coords.iter().map(|& (x, y)| Point::new(x, y)).collect::<Vec<Point>>();
+
+        assert!(Tetromino::is_valid_shape(& points(& [(3, 3), (4, 3), (4, 4), (5, 4)])));
+        assert!(! Tetromino::is_valid_shape(& points(& [(3, 3), (4, 3), (3, 4), (4, 4)])));
+        assert!(! Tetromino::is_valid_shape(& points(& [(3, 3), (4, 3), (4, 4)])));
+    }
+}
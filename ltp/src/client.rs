@@ -0,0 +1,757 @@
+
+use gtp::{Command, Response};
+use gtp::controller::Engine; 
+
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use super::command::LtpCommand;
+use utils::notate::Notate;
+use lits::*;
+use utils::*;
+
+///
+/// A wrapper around a GtpEngine controller that provides calls for 
+/// LITS text protocol communication.
+///
+/// A call to an engine command returns a unique command ID that corresponds
+/// to the request made to the engine. The caller recieves the ID and 
+/// the engine command returns without blocking. The caller can then poll 
+/// for the next response, or wait for the response to its held ID; any 
+/// response that arrives for another ID in the meantime is kept, and is 
+/// returned when that ID is waited for or polled.
+///
+/// For callers that simply want an answer, the typed requests, such as 
+/// `gen_move` or `analyze`, send a command and wait for its parsed response.
+///
+/// With search info on, the progress lines that the engine streams while it searches 
+/// are never returned as responses; the latest one is kept instead.
+///
+pub struct LtpClient
+{
+    handle: Engine,
+    pending: BTreeMap<u32, Response>,
+    info: Option<SearchInfo>
+}
+
+///
+/// A report of the engine's progress in a search: its current best move, with its eval in 
+/// X's perspective, the simulations run so far and their rate, and the time searched.
+///
+#[derive(Clone, Debug)]
+pub struct SearchInfo 
+{
+    pub tetromino: Tetromino,
+    pub eval: f32,
+    pub nodes: usize,
+    pub nps: usize,
+    pub time_ms: u64
+}
+
+///
+/// The version of the LITS text protocol that this client speaks.
+///
+pub const PROTOCOL_VERSION : u32 = 1;
+
+///
+/// A candidate move reported by an analyze-moves request: its visits and prior in the 
+/// engine's search, its eval in X's perspective, and its principal variation, which 
+/// starts with the move itself.
+///
+#[derive(Clone, Debug)]
+pub struct Candidate 
+{
+    pub tetromino: Tetromino,
+    pub visits: f32,
+    pub prior: f32,
+    pub eval: f32,
+    pub pv: Vec<Tetromino>
+}
+
+impl LtpClient 
+{
+    ///
+    /// Requests an analysis of the current game and waits up to the given time for it,
+    /// returning X's favour after each move of the game.
+    ///
+    pub fn analyze (& mut self, timeout: Duration) -> Result<Vec<f32>>
+    {
+        let id = self.cmd_analyze();
+        let response = self.wait_for(id, timeout)?;
+        LtpClient::parse_analysis_response(& response)
+    }
+
+    ///
+    /// Requests the engine's given number of most visited moves in the current position, 
+    /// and waits up to the given time for them.
+    ///
+    pub fn analyze_moves (& mut self, n: usize, timeout: Duration) -> Result<Vec<Candidate>>
+    {
+        let id = self.cmd_analyze_moves(n);
+        let response = self.wait_for(id, timeout)?;
+        LtpClient::parse_moves_response(& response)
+    }
+
+    ///
+    /// Checks that a response of the form 'blits-ltp <version> <capabilities>' to the 
+    /// initialize request with the given id reports a compatible protocol version.
+    ///
+    fn check_protocol (id: u32, response: & str) -> Result<()>
+    {
+        let context = format!("Incompatible engine response '{}' to initialize.", response);
+
+        let tokens = response.split_whitespace().collect::<Vec<& str>>();
+        if tokens.len() < 2 || tokens[0] != "blits-ltp"
+        {
+            return Err(error::error!("Expected a protocol identifier answering request {}.", id)).context(context);
+        }
+
+        let version = tokens[1].parse::<u32>().context(context.clone())?;
+        if version != PROTOCOL_VERSION 
+        {
+            return Err(error::error!("Expected protocol version {}, but the engine speaks {}.", PROTOCOL_VERSION, version)).context(context);
+        }
+
+        Ok(())
+    }
+
+    ///
+    /// Discards the latest report of the engine's progress, for example before a new search.
+    ///
+    pub fn clear_search_info (& mut self)
+    {
+        self.info = None;
+    }
+
+    ///
+    /// Requests the engine to perform an analysis on the current game, returning the 
+    /// analytical score (rather than the actual score derived from the scoring tiles) 
+    /// after each move of the game. from X's perspective.
+    ///
+    pub fn cmd_analyze (& mut self) -> u32
+    {
+        self.dispatch(LtpCommand::AnalyzePosition, & vec![])
+    }
+
+    ///
+    /// Requests the engine to search the current position without playing a move, and to 
+    /// report its given number of most visited moves, with their statistics and variations.
+    ///
+    pub fn cmd_analyze_moves (& mut self, n: usize) -> u32
+    {
+        self.dispatch(LtpCommand::AnalyzeMoves, & vec![n.to_string()])
+    }
+
+    ///
+    /// Applies the named setup as the setup position. This is a state-breaking operation,
+    /// and will halt any incoming search requests.
+    ///
+    pub fn cmd_apply_named_setup (& mut self, name: & str) -> u32
+    {
+        self.dispatch(LtpCommand::ApplyNamedSetup, & vec![name.to_owned()])
+    }
+
+    ///
+    /// Applies the given board as a setup position. This is a state-breaking operation,
+    /// and will halt any incoming search requests.
+    ///
+    pub fn cmd_apply_setup (& mut self, board: & Board) -> u32
+    {
+        self.dispatch(LtpCommand::ApplySetupPosition, & vec![board.notate()])
+    }
+
+    ///
+    /// Tells the engine to abort a genmove search early, and to return the best move found 
+    /// so far in the execution of the search tree.
+    ///
+    pub fn cmd_cancel (& mut self) -> u32
+    {
+        self.dispatch(LtpCommand::CancelSearch, & vec![])
+    }
+
+    ///
+    /// Requests the engine to find the best move for the given player. How the 
+    /// engine manages resources is a matter of engine configuration and no behaviour 
+    /// is mandated by the controller.
+    ///
+    pub fn cmd_gen_move (& mut self, who: & Player) -> u32
+    {
+        self.dispatch(LtpCommand::GenMove, & vec![who.notate()])
+    }
+
+    ///
+    /// Requests the engine to find the best move for the given player, along with 
+    /// the engine's evaluation of the position in X's perspective.
+    ///
+    pub fn cmd_gen_move_eval (& mut self, who: & Player) -> u32
+    {
+        self.dispatch(LtpCommand::GenMoveEval, & vec![who.notate()])
+    }
+
+    ///
+    /// Requests the engine's instant move for the given player: the move its policy rates 
+    /// highest, without a search.
+    ///
+    pub fn cmd_gen_move_fast (& mut self, who: & Player) -> u32
+    {
+        self.dispatch(LtpCommand::GenMoveFast, & vec![who.notate()])
+    }
+
+    ///
+    /// Starts a blank game on the engine, erasing any history. Whether or not 
+    /// the engine keeps its search trees intact is a matter of engine configuration
+    /// and no behaviour is mandated by the controller.
+    ///
+    pub fn cmd_new_game (& mut self) -> u32
+    {
+        self.dispatch(LtpCommand::NewGame, & vec![])
+    }
+
+    ///
+    /// Applies the given tetromino to the position, waiting up to the given time for the 
+    /// engine to acknowledge it. Note that despite modifying the state, provided that the 
+    /// move is legal it is not a state-breaking operation, and the engine is required to 
+    /// pivot its search tree to accomodate the state change.
+    ///
+    pub fn cmd_play (& mut self, tetromino: & Tetromino, timeout: Duration) -> Result<()>
+    {
+        self.cmd_play_many(std::slice::from_ref(tetromino), timeout)
+    }
+
+    ///
+    /// Applies the given tetrominos to the position in order, in a single command, waiting 
+    /// up to the given time for the engine to acknowledge them. Errors if the engine rejects 
+    /// one of them, naming the rejected move; the engine has played the moves before it.
+    ///
+    pub fn cmd_play_many (& mut self, tetrominos: & [Tetromino], timeout: Duration) -> Result<()>
+    {
+        let id = self.dispatch(LtpCommand::PlaceTetromino, & tetrominos.iter().map(|tetromino| tetromino.notate()).collect());
+
+        match self.wait_for_response(id, timeout)?
+        {
+            Response::Result(_)           => Ok(()),
+            Response::Error((_, payload))  => 
+            {
+                let rejected = LtpClient::parse_rejected_move(& payload)
+                    .and_then(|idx| tetrominos.get(idx))
+                    .map_or("a move".to_owned(), |tetromino| format!("move '{}'", tetromino.notate()));
+                Err(error::error!("The engine rejected request {}: {}", id, payload)).context(format!("The engine did not play {}.", rejected))
+            }
+        }
+    }
+
+    ///
+    /// Enables or disables pondering: while enabled, the engine searches the replies to 
+    /// each move it generates until the controller plays the game past them.
+    ///
+    pub fn cmd_set_pondering (& mut self, enabled: bool) -> u32
+    {
+        let setting = match enabled 
+        {
+            true  => "on",
+            false => "off"
+        };
+        self.dispatch(LtpCommand::SetPondering, & vec![setting.to_owned()])
+    }
+
+    ///
+    /// Enables or disables the engine's reports of its progress while it searches.
+    ///
+    pub fn cmd_set_search_info (& mut self, enabled: bool) -> u32
+    {
+        let setting = match enabled 
+        {
+            true  => "on",
+            false => "off"
+        };
+        self.dispatch(LtpCommand::SetSearchInfo, & vec![setting.to_owned()])
+    }
+
+    ///
+    /// Sets the engine's playing strength, from 0 to 5, which applies from its next search.
+    ///
+    pub fn cmd_set_strength (& mut self, level: usize) -> u32
+    {
+        self.dispatch(LtpCommand::SetStrength, & vec![level.to_string()])
+    }
+
+    ///
+    /// Undoes the last move in the position, provided one exists.
+    ///
+    pub fn cmd_undo (& mut self) -> u32
+    {
+        self.dispatch(LtpCommand::Undo, & vec![])
+    }
+
+    ///
+    /// Dispatches the given LITS text protocol command, and returns its id, which 
+    /// increments with each command and is echoed by the engine in the response.
+    ///
+    pub fn dispatch (& mut self, command: LtpCommand, args: & Vec<String>) -> u32
+    {
+        // Forms the command line from the given command and args.
+
+        let commandline = match args.len()
+        {
+            0 => format!(
+                "{}\n",
+                command.command()
+            ),
+            _ => format!(
+                "{} {}\n",
+                command.command(), args.join(" ")
+            )
+        };
+
+        // Delivers the command to the engine via stdin.
+
+        let cmd = Command::new(& commandline);
+        let id = self.handle.send(cmd.clone());
+        log::info!("Sent command {}: {}", id, cmd.to_string());
+
+        id
+    }
+
+    ///
+    /// Requests the best move for the given player and waits up to the given time for it.
+    ///
+    pub fn gen_move (& mut self, who: & Player, timeout: Duration) -> Result<Tetromino>
+    {
+        let id = self.cmd_gen_move(who);
+        let response = self.wait_for(id, timeout)?;
+        LtpClient::parse_move_response(& response).map(|(tetromino, _, _)| tetromino)
+    }
+
+    ///
+    /// Requests the best move for the given player, with the engine's eval in X's 
+    /// perspective, and waits up to the given time for it.
+    ///
+    pub fn gen_move_eval (& mut self, who: & Player, timeout: Duration) -> Result<(Tetromino, f32)>
+    {
+        let context = "Expected the engine to report an eval.";
+
+        let id = self.cmd_gen_move_eval(who);
+        let response = self.wait_for(id, timeout)?;
+        let (tetromino, eval, _) = LtpClient::parse_move_response(& response)?;
+
+        Ok((tetromino, eval.ok_or_else(|| error::error!("Invalid move response '{}'.", response)).context(context)?))
+    }
+
+    ///
+    /// Shuts down the process backing this engine.
+    ///
+    pub fn halt (& mut self)
+    {
+        self.dispatch(LtpCommand::Shutdown, & vec![]);
+    }
+
+    ///
+    /// Initializes the engine, waiting up to the given time for it to respond, and checks 
+    /// that it speaks a compatible protocol version.
+    ///
+    pub fn initialize (& mut self, timeout: Duration) -> Result<()>
+    {
+        let id = self.dispatch(LtpCommand::Initialize, & vec![]);
+        let response = self.wait_for(id, timeout)?;
+        LtpClient::check_protocol(id, & response)?;
+
+        log::info!("Engine speaks protocol version {}.", PROTOCOL_VERSION);
+        Ok(())
+    }
+
+    ///
+    /// Starts the engine at the given path and initializes it, waiting up to 30 seconds 
+    /// for it to respond.
+    ///
+    pub fn new (exe_path: & str) -> Result<LtpClient>
+    {
+        let mut client = LtpClient::start(exe_path)?;
+        client.initialize(Duration::from_secs(30))?;
+        Ok(client)
+    }
+
+    ///
+    /// Parses an analysis response of the form '<eval>...', with its id already removed,
+    /// into the evals in X's perspective.
+    ///
+    pub fn parse_analysis_response (response: & str) -> Result<Vec<f32>>
+    {
+        let context = format!("Invalid analysis response '{}'.", response);
+
+        response.split_whitespace()
+            .map(|eval| eval.parse::<f32>().context(context.clone()))
+            .collect()
+    }
+
+    ///
+    /// Parses a progress line of the form 'info move <move> eval <e> nodes <n> nps <r> 
+    /// time <ms>', with its id already removed.
+    ///
+    pub fn parse_info_response (response: & str) -> Result<SearchInfo>
+    {
+        let context = format!("Invalid search info '{}'.", response);
+
+        let tokens = response.split_whitespace().collect::<Vec<& str>>();
+        let field = |name: & str| tokens.iter()
+            .position(|token| * token == name)
+            .and_then(|idx| tokens.get(idx + 1))
+            .ok_or_else(|| error::error!("Expected the field '{}'.", name));
+
+        if tokens.first() != Some(& "info")
+        {
+            return Err(error::error!("Expected an info line.")).context(context);
+        }
+
+        Ok(SearchInfo 
+        {
+            tetromino: Tetromino::parse(field("move").context(context.clone())?).context(context.clone())?,
+            eval: field("eval").context(context.clone())?.parse::<f32>().context(context.clone())?,
+            nodes: field("nodes").context(context.clone())?.parse::<usize>().context(context.clone())?,
+            nps: field("nps").context(context.clone())?.parse::<usize>().context(context.clone())?,
+            time_ms: field("time").context(context.clone())?.parse::<u64>().context(context)?
+        })
+    }
+
+    ///
+    /// Parses a move response of the form '<move> [<eval>] [pv <move>...]', with its id 
+    /// already removed, into the move, its eval in X's perspective if the engine provided 
+    /// one, and the principal variation, which is empty if the engine provided none.
+    ///
+    pub fn parse_move_response (response: & str) -> Result<(Tetromino, Option<f32>, Vec<Tetromino>)>
+    {
+        let context = format!("Invalid move response '{}'.", response);
+
+        let (head, pv) = match response.split_once(" pv")
+        {
+            Some((head, pv)) => (head, pv),
+            None             => (response, "")
+        };
+        let pv = pv.split_whitespace()
+            .map(|notation| Tetromino::parse(notation).context(context.clone()))
+            .collect::<Result<Vec<Tetromino>>>()?;
+
+        let tokens = head.split_whitespace().collect::<Vec<& str>>();
+        let (tetromino, eval) = match tokens.len()
+        {
+            1 => (tokens[0], None),
+            2 => (tokens[0], Some(tokens[1])),
+            _ => return Err(error::error!("Expected a move and an optional eval.")).context(context)
+        };
+
+        let tetromino = Tetromino::parse(tetromino).context(context.clone())?;
+        let eval = match eval 
+        {
+            Some(eval) => Some(eval.parse::<f32>().context(context)?),
+            None       => None
+        };
+
+        Ok((tetromino, eval, pv))
+    }
+
+    ///
+    /// Parses an analyze-moves response, with its id already removed, which is a sequence 
+    /// of groups of the form 'move <move> visits <v> prior <p> eval <e> pv <move>...'.
+    ///
+    pub fn parse_moves_response (response: & str) -> Result<Vec<Candidate>>
+    {
+        let context = format!("Invalid analyze-moves response '{}'.", response);
+
+        let mut candidates = Vec::new();
+        let mut tokens = response.split_whitespace().peekable();
+
+        while tokens.peek().is_some()
+        {
+            let mut field = |name: & str| match (tokens.next(), tokens.next())
+            {
+                (Some(tag), Some(value)) if tag == name => Ok(value),
+                _                                       => Err(error::error!("Expected the field '{}'.", name))
+            };
+
+            let tetromino = Tetromino::parse(field("move").context(context.clone())?).context(context.clone())?;
+            let visits = field("visits").context(context.clone())?.parse::<f32>().context(context.clone())?;
+            let prior = field("prior").context(context.clone())?.parse::<f32>().context(context.clone())?;
+            let eval = field("eval").context(context.clone())?.parse::<f32>().context(context.clone())?;
+
+            if tokens.next() != Some("pv")
+            {
+                return Err(error::error!("Expected the field 'pv'.")).context(context);
+            }
+
+            let mut pv = Vec::new();
+            while let Some(notation) = tokens.next_if(|token| * token != "move")
+            {
+                pv.push(Tetromino::parse(notation).context(context.clone())?);
+            }
+
+            candidates.push(Candidate { tetromino, visits, prior, eval, pv });
+        }
+
+        Ok(candidates)
+    }
+
+    ///
+    /// Parses a rejection of a play-move command, of the form 'illegal move <idx>', with its 
+    /// id already removed, into the index of the rejected move.
+    ///
+    pub fn parse_rejected_move (response: & str) -> Option<usize>
+    {
+        response.strip_prefix("illegal move ").and_then(|idx| idx.trim().parse::<usize>().ok())
+    }
+
+    ///
+    /// Unwraps the payload of the response to the request with the given id, erroring 
+    /// if the engine rejected the request.
+    ///
+    fn payload (id: u32, resp: Response) -> Result<String>
+    {
+        match resp 
+        {
+            Response::Result((_, payload)) => Ok(payload),
+            Response::Error((_, payload))  => Err(error::error!("The engine rejected request {}: {}", id, payload))
+        }
+    }
+
+    ///
+    /// Takes the response to the request with the given id if it has arrived, without 
+    /// blocking; as in `wait_for`, responses to other requests are kept. Errors if the 
+    /// engine rejects the request.
+    ///
+    pub fn poll_for (& mut self, id: u32) -> Option<Result<String>>
+    {
+        while let Ok((rid, resp)) = self.receive(Duration::ZERO)
+        {
+            self.pending.insert(rid, resp);
+        }
+
+        self.pending.remove(& id).map(|resp| LtpClient::payload(id, resp))
+    }
+
+    ///
+    /// Polls responses from the engine, erroring if the response has not 
+    /// yet been received. The response is returned along with the id of 
+    /// the request it answers.
+    ///
+    pub fn poll_response (& mut self) -> Result<(u32, String)>
+    {
+        self.poll_response_timeout(Duration::from_millis(100))
+    }
+
+    ///
+    /// Polls responses from the engine, retrying until the total time budget elapses,
+    /// and erroring if the response has still not been received by then. The response 
+    /// is returned along with the id of the request it answers; a response that was kept 
+    /// while waiting for another id is returned first.
+    ///
+    pub fn poll_response_timeout (& mut self, total: Duration) -> Result<(u32, String)>
+    {
+        if let Some((id, resp)) = self.pending.pop_first()
+        {
+            return Ok((id, resp.text()));
+        }
+
+        let (id, resp) = self.receive(total)?;
+        Ok((id, resp.text()))
+    }
+
+    ///
+    /// Receives the next response from the engine, retrying until the total time budget 
+    /// elapses, and returns it along with the id of the request it answers.
+    ///
+    fn receive (& mut self, total: Duration) -> Result<(u32, Response)>
+    {
+        let start = Instant::now();
+
+        loop 
+        {
+            let remaining = total.saturating_sub(start.elapsed());
+
+            match self.handle.wait_response(remaining.min(Duration::from_millis(100)))
+            {
+                Ok(resp) => 
+                {
+                    log::info!("Received response '{}'.", resp.text());
+
+                    let (id, payload) = LtpClient::split_response(& resp.text())?;
+
+                    // No request uses the id 0, which carries the engine's progress reports.
+
+                    if id == 0 
+                    {
+                        match LtpClient::parse_info_response(& payload)
+                        {
+                            Ok(info) => self.info = Some(info),
+                            Err(e)   => log::warn!("{:#}", e)
+                        };
+                    }
+                    else 
+                    {
+                        let resp = match resp 
+                        {
+                            Response::Error(_)  => Response::Error((Some(id), payload)),
+                            Response::Result(_) => Response::Result((Some(id), payload))
+                        };
+                        return Ok((id, resp));
+                    }
+                },
+                Err(gtp::controller::Error::PollAgain) => {},
+                Err(e) => return Err(error::error!("{:?}", e)).context("Could not read a response from the engine.")
+            }
+
+            if start.elapsed() >= total 
+            {
+                return Err(error::error!("Could not find a response within {}ms; try again later.", total.as_millis()));
+            }
+        }
+    }
+
+    ///
+    /// Returns the latest report of the engine's progress in a search, if any.
+    ///
+    pub fn search_info (& self) -> Option<& SearchInfo>
+    {
+        self.info.as_ref()
+    }
+
+    ///
+    /// Splits a response of the form '<id> [<payload>]' into its id and payload.
+    ///
+    fn split_response (response: & str) -> Result<(u32, String)>
+    {
+        let context = format!("Invalid response '{}'.", response);
+
+        let (id, payload) = response.split_once(' ').unwrap_or((response, ""));
+        let id = id.parse::<u32>().context(context)?;
+
+        Ok((id, payload.to_owned()))
+    }
+
+    ///
+    /// Starts the engine at the given path, without initializing it.
+    ///
+    pub fn start (exe_path: & str) -> Result<LtpClient>
+    {
+        let mut client = LtpClient { handle: Engine::new(exe_path, & []), pending: BTreeMap::new(), info: None };
+        client.handle.start()
+            .map_err(|e| error::error!("{:?}", e))
+            .context(format!("Could not start engine (with path {}).", exe_path))?;
+
+        Ok(client)
+    }
+
+    ///
+    /// Waits up to the given time for the response to the request with the given id, 
+    /// keeping any responses to other requests that arrive in the meantime. Errors if 
+    /// the engine rejects the request.
+    ///
+    pub fn wait_for (& mut self, id: u32, timeout: Duration) -> Result<String>
+    {
+        let resp = self.wait_for_response(id, timeout)?;
+        LtpClient::payload(id, resp)
+    }
+
+    ///
+    /// Waits up to the given time for the response to the request with the given id, as 
+    /// in `wait_for`, and returns it whether the engine accepted the request or not.
+    ///
+    fn wait_for_response (& mut self, id: u32, timeout: Duration) -> Result<Response>
+    {
+        let start = Instant::now();
+
+        loop 
+        {
+            if let Some(resp) = self.pending.remove(& id)
+            {
+                return Ok(resp);
+            }
+
+            let (rid, resp) = self.receive(timeout.saturating_sub(start.elapsed()))
+                .context(format!("Failed to wait for the response to request {}.", id))?;
+            self.pending.insert(rid, resp);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests 
+{
+    use super::*;
+
+    #[test]
+    fn parse_move_response_reads_the_eval_and_pv ()
+    {
+        Tetromino::initialize();
+
+        let mut board = Board::blank();
+        let first = board.enumerate_moves().into_iter().next().unwrap();
+        board.place_tetromino(& first).unwrap();
+        let second = board.enumerate_moves().into_iter().next().unwrap();
+
+        let response = format!("{} -0.25 pv {} {}", first.notate(), first.notate(), second.notate());
+        let (tetromino, eval, pv) = LtpClient::parse_move_response(& response).unwrap();
+        assert_eq!((tetromino, eval, pv), (first.clone(), Some(-0.25), vec![first.clone(), second]));
+
+        let (tetromino, eval, pv) = LtpClient::parse_move_response(& first.notate()).unwrap();
+        assert_eq!((tetromino, eval, pv), (first.clone(), None, vec![]));
+
+        assert!(LtpClient::parse_move_response(& format!("{} high", first.notate())).is_err());
+    }
+
+    ///
+    /// Starts a stub engine that runs the given shell script, which lives in the given 
+    /// directory.
+    ///
+    #[cfg(unix)]
+    fn stub (dir: & tempfile::TempDir, script: & str) -> LtpClient
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = dir.path().join("stub.sh");
+        std::fs::write(& path, format!("#!/bin/sh\n{}", script)).unwrap();
+        std::fs::set_permissions(& path, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        LtpClient::start(path.to_str().unwrap()).unwrap()
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn poll_response_timeout_retries_within_its_budget ()
+    {
+        let dir = tempfile::tempdir().unwrap();
+        let mut client = stub(& dir, "while read id rest; do sleep 0.25; printf '= %s ok\\n\\n' \"$id\"; done\n");
+
+        let start = Instant::now();
+        let id = client.cmd_new_game();
+        assert!(client.poll_response_timeout(Duration::from_millis(50)).is_err());
+
+        assert_eq!(client.poll_response_timeout(Duration::from_secs(1)).unwrap(), (id, "ok".to_owned()));
+        assert!(start.elapsed() >= Duration::from_millis(250));
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn out_of_order_responses_reach_their_requests ()
+    {
+        // The stub answers two commands in reverse order, echoing each command's name. Each 
+        // command line is followed by a blank line, and the answers are written apart, since 
+        // the controller only parses a response when new output arrives.
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut client = stub(& dir, "read a x; read _; read b y; printf '= %s %s\\n\\n' \"$b\" \"$y\"; sleep 0.1; printf '= %s %s\\n\\n' \"$a\" \"$x\"; cat > /dev/null\n");
+
+        let first = client.cmd_new_game();
+        let second = client.cmd_undo();
+        assert_ne!(first, second);
+
+        assert_eq!(client.wait_for(first, Duration::from_secs(1)).unwrap(), "new-game");
+        assert_eq!(client.wait_for(second, Duration::from_secs(1)).unwrap(), "undo-move");
+    }
+
+    #[test]
+    fn split_response_separates_the_id ()
+    {
+        assert_eq!(LtpClient::split_response("12 a5 0.5").unwrap(), (12, "a5 0.5".to_owned()));
+        assert_eq!(LtpClient::split_response("3").unwrap(), (3, String::new()));
+        assert!(LtpClient::split_response("ok 3").is_err());
+    }
+}
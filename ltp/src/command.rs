@@ -12,15 +12,25 @@ pub enum LtpCommand
     // State commands.
 
     ApplySetupPosition,         // Applies a board position with the given hashstring.
+    ApplyNamedSetup,            // Applies the named setup from the catalogue in lits::setups.
     NewGame,                    // Starts a new game with a blank scoring set.
     PlaceTetromino,             // Places a tetromino, provided it is legal.
     Undo,                       // Undoes the last move, provided one exists.
 
+    // Engine settings.
+
+    SetPondering,               // Enables or disables pondering on the replies to the engine's moves.
+    SetStrength,                // Sets the engine's playing strength, from 0 to 5.
+    SetSearchInfo,              // Enables or disables the engine's progress reports while it searches.
+
     // Analytical commands.
 
     AnalyzePosition,            // Returns a vector of float values representing X's favour over the course of the game.
+    AnalyzeMoves,               // Returns the most visited moves of a search, with their statistics and variations.
     CancelSearch,               // Aborts a running move search early.
     GenMove,                    // Gets the best move for the current player.
+    GenMoveEval,                // Gets the best move for the current player and the engine's eval in X's perspective.
+    GenMoveFast,                // Gets the policy's move for the current player, without a search.
 }
 
 impl LtpCommand 
@@ -36,13 +46,21 @@ impl LtpCommand
             LtpCommand::Shutdown           => "shutdown".to_owned(),
 
             LtpCommand::ApplySetupPosition => "setup-position".to_owned(),
+            LtpCommand::ApplyNamedSetup    => "setup-position-named".to_owned(),
             LtpCommand::NewGame            => "new-game".to_owned(),
             LtpCommand::PlaceTetromino     => "play-move".to_owned(),
             LtpCommand::Undo               => "undo-move".to_owned(),
 
+            LtpCommand::SetPondering       => "ponder".to_owned(),
+            LtpCommand::SetStrength        => "set-strength".to_owned(),
+            LtpCommand::SetSearchInfo      => "search-info".to_owned(),
+
             LtpCommand::AnalyzePosition    => "analyze-board".to_owned(),
+            LtpCommand::AnalyzeMoves       => "analyze-moves".to_owned(),
             LtpCommand::CancelSearch       => "cancel-search".to_owned(),
-            LtpCommand::GenMove            => "gen-move".to_owned()
+            LtpCommand::GenMove            => "gen-move".to_owned(),
+            LtpCommand::GenMoveEval        => "gen-move-eval".to_owned(),
+            LtpCommand::GenMoveFast        => "gen-move-fast".to_owned()
         }
     }
 
@@ -53,8 +71,8 @@ impl LtpCommand
     {
         match self 
         {
-            LtpCommand::AnalyzePosition | LtpCommand::GenMove => true,
-            _                                                 => false
+            LtpCommand::AnalyzePosition | LtpCommand::AnalyzeMoves | LtpCommand::GenMove | LtpCommand::GenMoveEval | LtpCommand::GenMoveFast => true,
+            _                                                                                                                          => false
         }
     }
 }
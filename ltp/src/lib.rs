@@ -0,0 +1,7 @@
+
+pub mod client;
+pub mod command;
+
+pub use client::{Candidate, LtpClient, SearchInfo};
+pub use command::LtpCommand;
+
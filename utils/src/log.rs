@@ -1,7 +1,17 @@
 
 use flexi_logger::{Duplicate, FileSpec, Logger, LoggerHandle, with_thread, WriteMode};
+use lazy_static::lazy_static;
+use std::sync::Mutex;
 use super::error::*;
 
+lazy_static!
+{
+    ///
+    /// The handle to the active logger, kept so that its specification can be changed at runtime.
+    ///
+    static ref HANDLE : Mutex<Option<LoggerHandle>> = Mutex::new(None);
+}
+
 ///
 /// Macros to write to the backing file logger.
 ///
@@ -25,8 +35,48 @@ pub fn initialize (path: & str, filename: & str, spec: & str) -> Result<LoggerHa
         .format_for_files(with_thread)
         .start()?;
 
+    * HANDLE.lock().unwrap() = Some(logger.clone());
     info!("Logging initialization complete.");
 
     Ok(logger)
 }
 
+
+///
+/// Replaces the active log specification (for example, `"warn, engine::mcts=debug"`).
+///
+pub fn set_level (spec: & str) -> Result<()>
+{
+    match HANDLE.lock().unwrap().as_mut()
+    {
+        Some(handle) => Ok(handle.parse_new_spec(spec)?),
+        None         => Err(super::error::error!("The logger is not initialized."))
+    }
+}
+
+#[cfg(test)]
+mod tests 
+{
+    use super::*;
+
+    #[test]
+    fn off_spec_suppresses_info ()
+    {
+        let dir = tempfile::tempdir().unwrap();
+        let _handle = initialize(dir.path().to_str().unwrap(), "test", "off").unwrap();
+
+        info!("A line that is suppressed.");
+        assert!(! log::log_enabled!(log::Level::Info));
+
+        // Raising the level shows that the file is written at all.
+
+        set_level("info").unwrap();
+        info!("A line that is logged.");
+
+        let logged = std::fs::read_dir(dir.path()).unwrap()
+            .map(|entry| std::fs::read_to_string(entry.unwrap().path()).unwrap())
+            .collect::<String>();
+        assert!(logged.contains("A line that is logged."));
+        assert!(! logged.contains("A line that is suppressed."));
+    }
+}